@@ -15,9 +15,95 @@ use syn::{
 
 const ALPHABET: [&str; 10] = ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"];
 
+#[proc_macro_derive(Visit)]
+pub fn derive_visit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    visit_impl(&input).into()
+}
+
+/// `Visit` impl walking the fields in declaration order, which is also the order they are
+/// serialized in
+fn visit_impl(input: &DeriveInput) -> TokenStream {
+    let name = &input.ident;
+    let name_str = name.to_string();
+
+    let body = match &input.data {
+        Data::Struct(s) => {
+            let mut field_visits: Vec<TokenStream> = Vec::new();
+            for (i, field) in s.fields.iter().enumerate() {
+                let (field_name, member) = match &field.ident {
+                    Some(ident) => (ident.to_string(), quote!(#ident)),
+                    None => {
+                        let idx = syn::Index::from(i);
+                        (i.to_string(), quote!(#idx))
+                    }
+                };
+                field_visits.push(quote! {
+                    visitor.field(#field_name);
+                    crate::visit::Visit::visit(&self.#member, visitor);
+                });
+            }
+            quote! {
+                visitor.begin_struct(#name_str);
+                #(#field_visits)*
+                visitor.end();
+            }
+        }
+        Data::Enum(e) => {
+            let mut arms: Vec<TokenStream> = Vec::new();
+            for variant in &e.variants {
+                let variant_name = &variant.ident;
+                let variant_str = variant_name.to_string();
+                let mut bindings: Vec<Ident> = Vec::new();
+                let mut field_visits: Vec<TokenStream> = Vec::new();
+                for (i, field) in variant.fields.iter().enumerate() {
+                    let (field_name, binding) = match &field.ident {
+                        Some(ident) => (ident.to_string(), ident.clone()),
+                        None => (i.to_string(), format_ident!("{}", ALPHABET[i])),
+                    };
+                    field_visits.push(quote! {
+                        visitor.field(#field_name);
+                        crate::visit::Visit::visit(#binding, visitor);
+                    });
+                    bindings.push(binding);
+                }
+                let pattern = match &variant.fields {
+                    syn::Fields::Named(_) => quote!(Self::#variant_name { #(#bindings),* }),
+                    syn::Fields::Unnamed(_) => quote!(Self::#variant_name ( #(#bindings),* )),
+                    syn::Fields::Unit => quote!(Self::#variant_name),
+                };
+                arms.push(quote! {
+                    #pattern => {
+                        visitor.begin_variant(#name_str, #variant_str);
+                        #(#field_visits)*
+                        visitor.end();
+                    }
+                });
+            }
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => panic!("unimplemented"),
+    };
+
+    let generics = bounded_generics(&input.generics, quote!(crate::visit::Visit));
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics crate::visit::Visit for #name #type_generics #where_clause {
+            fn visit(&self, visitor: &mut dyn crate::visit::Visitor) {
+                #body
+            }
+        }
+    }
+}
+
 #[proc_macro_derive(Serializable, attributes(enum_info, bitfields))]
 pub fn derive_serializable(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    let visit = visit_impl(&input);
 
     let mut read_from = TokenStream::new();
     let mut write_to = TokenStream::new();
@@ -83,7 +169,10 @@ pub fn derive_serializable(input: proc_macro::TokenStream) -> proc_macro::TokenS
                     syn::Fields::Named(f) => {
                         for field in &f.named {
                             let name = &field.ident;
-                            field_reads.push(quote!( #name: Serializable::read_from(buf)? ));
+                            let ty = &field.ty;
+                            field_reads.push(quote!(
+                                #name: crate::span::field(stringify!(#name), || <#ty as Serializable>::read_from(buf))?
+                            ));
                             field_writes.push(quote!( self.#name.write_to(buf)?; ));
                         }
 
@@ -96,8 +185,12 @@ pub fn derive_serializable(input: proc_macro::TokenStream) -> proc_macro::TokenS
                     syn::Fields::Unnamed(f) => {
                         for (i, field) in f.unnamed.iter().enumerate() {
                             let idx = syn::Index::from(i);
+                            let ty = &field.ty;
+                            let field_name = i.to_string();
 
-                            field_reads.push(quote!(Serializable::read_from(buf)?));
+                            field_reads.push(quote!(
+                                crate::span::field(#field_name, || <#ty as Serializable>::read_from(buf))?
+                            ));
                             field_writes.push(quote!( self.#idx.write_to(buf)?; ));
                         }
 
@@ -143,12 +236,18 @@ pub fn derive_serializable(input: proc_macro::TokenStream) -> proc_macro::TokenS
 
                         for field in &f.named {
                             let name = &field.ident;
+                            let ty = &field.ty;
                             field_names.push(name.clone().unwrap());
 
-                            field_reads.push(quote!(#name: Serializable::read_from(buf)?));
+                            field_reads.push(quote!(
+                                #name: crate::span::field(stringify!(#name), || <#ty as Serializable>::read_from(buf))?
+                            ));
                             field_writes.push(quote!( #name.write_to(buf)?; ));
                         }
-                        num_to_variant.push(quote!( #idx => Self::#name{ #(#field_reads),* } ));
+                        num_to_variant.push(quote!( #idx => {
+                            crate::span::variant(stringify!(#name));
+                            Self::#name{ #(#field_reads),* }
+                        } ));
                         variant_to_num.push(quote!(
                             Self::#name {#(#field_names),*} => {
                             #ty::from_len(#idx).write_to(buf)?;
@@ -166,12 +265,19 @@ pub fn derive_serializable(input: proc_macro::TokenStream) -> proc_macro::TokenS
                             // let ident = &field.ident;
                             let field_name = format_ident!("{}", ALPHABET[i]);
                             field_names.push(field_name.clone());
+                            let ty = &field.ty;
+                            let span_name = i.to_string();
 
-                            field_reads.push(quote!(Serializable::read_from(buf)?));
+                            field_reads.push(quote!(
+                                crate::span::field(#span_name, || <#ty as Serializable>::read_from(buf))?
+                            ));
                             field_writes.push(quote!( #field_name.write_to(buf)?; ));
                         }
 
-                        num_to_variant.push(quote!( #idx => Self::#name( #(#field_reads),* ) ));
+                        num_to_variant.push(quote!( #idx => {
+                            crate::span::variant(stringify!(#name));
+                            Self::#name( #(#field_reads),* )
+                        } ));
                         variant_to_num.push(quote!(
                             Self::#name(#(#field_names),*) => {
                             #ty::from_len(#idx).write_to(buf)?;
@@ -180,7 +286,10 @@ pub fn derive_serializable(input: proc_macro::TokenStream) -> proc_macro::TokenS
                         ));
                     }
                     syn::Fields::Unit => {
-                        num_to_variant.push(quote!(#idx => Self::#name));
+                        num_to_variant.push(quote!(#idx => {
+                            crate::span::variant(stringify!(#name));
+                            Self::#name
+                        }));
                         variant_to_num
                             .push(quote!(Self::#name => #ty::from_len(#idx).write_to(buf)?));
                     }
@@ -189,7 +298,7 @@ pub fn derive_serializable(input: proc_macro::TokenStream) -> proc_macro::TokenS
             }
 
             read_from = quote! {
-                Ok(match <#ty>::read_from(buf)?.into_len() {
+                Ok(match crate::span::field("variant", || <#ty>::read_from(buf))?.into_len() {
                     #(#num_to_variant,)*
                     x @ _ => return Err(crate::Error::SerializeError(format!("invalid enum index: {}",x)))
                 })
@@ -208,7 +317,9 @@ pub fn derive_serializable(input: proc_macro::TokenStream) -> proc_macro::TokenS
     };
     let name = input.ident;
 
-    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    // span recording shows the values it reads
+    let generics = bounded_generics(&input.generics, quote!(::core::fmt::Debug));
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
 
     quote! {
         impl #impl_generics Serializable for #name #type_generics #where_clause {
@@ -219,6 +330,8 @@ pub fn derive_serializable(input: proc_macro::TokenStream) -> proc_macro::TokenS
                 #write_to
             }
         }
+
+        #visit
     }
     .into()
 }
@@ -562,3 +675,12 @@ impl Parse for PacketLookupInput {
 //     }
 //     .into()
 // }
+
+/// `generics` with `bound` added to every type parameter, like the std derives do
+fn bounded_generics(generics: &syn::Generics, bound: TokenStream) -> syn::Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    generics
+}
@@ -0,0 +1,400 @@
+//! Decodes raw protocol streams, capture files and single packets.
+//!
+//! ```text
+//! mcdump stream <file> --direction <dir> [--state <state>] [--aes-key <hex>]
+//!               [--private-key <hex der | pem file>] [--compression <threshold>]
+//!               [--json] [--spans] [--debug]
+//! mcdump capture <file> [--aes-key <hex>] [--json] [--spans] [--debug]
+//! mcdump hex <hex payload> --direction <dir> --state <state> --id <id> [--json] [--spans] [--debug]
+//! ```
+//!
+//! `stream` decodes one direction of a connection as raw TCP bytes (like
+//! `tests/sample_data/C2S.bin`). The state follows the handshake and login, encryption starts
+//! after `EncryptionRequest`/`EncryptionResponse` with `--aes-key`, or with the key decrypted
+//! from `EncryptionResponse` with the server's `--private-key`. Clientbound streams enable
+//! compression on `SetCompression`; serverbound streams can't see it, so `--compression` is
+//! applied after `EncryptionResponse` (or `LoginStart` when no key is given), or from the start
+//! when `--state` is configuration or play.
+//!
+//! Packets are printed with `pretty`, `--debug` prints their full `Debug` tree instead and
+//! `--spans` the bytes every field was decoded from.
+
+use std::{
+    cell::Cell,
+    env,
+    error::Error,
+    fs::{self, File},
+    io::{self, BufReader, Read, Write},
+    process::ExitCode,
+    rc::Rc,
+    time::Duration,
+};
+
+use mc_rust_protocol::{
+    CompressionThreshold, RawPacket,
+    capture::Capture,
+    packet::{self, Direction, Packet, State},
+    packet_decoder::{NetworkDecoder, PacketDecodeError},
+    span, visit,
+};
+use rsa::{
+    Pkcs1v15Encrypt, RsaPrivateKey,
+    pkcs8::{DecodePrivateKey, der::zeroize::Zeroizing},
+};
+use serde_json::json;
+
+const USAGE: &str = "usage:
+    mcdump stream <file> --direction <serverbound|clientbound> [--state <state>] [--aes-key <hex>]
+                  [--private-key <hex der | pem file>] [--compression <threshold>]
+                  [--json] [--spans] [--debug]
+    mcdump capture <file> [--aes-key <hex>] [--json] [--spans] [--debug]
+    mcdump hex <hex payload> --direction <serverbound|clientbound> --state <state> --id <id> [--json] [--spans] [--debug]
+
+states: handshake, status, login, configuration, play";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        // the output was piped into something like `head`
+        Err(err)
+            if err
+                .downcast_ref::<io::Error>()
+                .is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    positional: Vec<String>,
+    direction: Option<Direction>,
+    state: Option<State>,
+    aes_key: Option<[u8; 16]>,
+    private_key: Option<RsaPrivateKey>,
+    compression: Option<CompressionThreshold>,
+    id: Option<i32>,
+    json: bool,
+    spans: bool,
+    debug: bool,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, Box<dyn Error>> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--direction" => options.direction = Some(parse_direction(value()?)?),
+            "--state" => options.state = Some(parse_state(value()?)?),
+            "--aes-key" => {
+                options.aes_key = Some(
+                    hex::decode(value()?)?
+                        .try_into()
+                        .map_err(|_| "the AES key must be 16 bytes")?,
+                )
+            }
+            "--private-key" => options.private_key = Some(parse_private_key(value()?)?),
+            "--compression" => options.compression = Some(value()?.parse()?),
+            "--id" => options.id = Some(parse_int(value()?)?),
+            "--json" => options.json = true,
+            "--spans" => options.spans = true,
+            "--debug" => options.debug = true,
+            "-h" | "--help" => return Err(USAGE.into()),
+            arg if arg.starts_with("--") => return Err(format!("unknown option {}", arg).into()),
+            arg => options.positional.push(arg.to_owned()),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_direction(value: &str) -> Result<Direction, Box<dyn Error>> {
+    match value.to_lowercase().as_str() {
+        "serverbound" | "c2s" => Ok(Direction::Serverbound),
+        "clientbound" | "s2c" => Ok(Direction::Clientbound),
+        _ => Err(format!("invalid direction {}", value).into()),
+    }
+}
+
+fn parse_state(value: &str) -> Result<State, Box<dyn Error>> {
+    match value.to_lowercase().as_str() {
+        "handshake" => Ok(State::Handshake),
+        "status" => Ok(State::Status),
+        "login" => Ok(State::Login),
+        "configuration" | "config" => Ok(State::Configuration),
+        "play" => Ok(State::Play),
+        _ => Err(format!("invalid state {}", value).into()),
+    }
+}
+
+fn parse_int(value: &str) -> Result<i32, Box<dyn Error>> {
+    Ok(match value.strip_prefix("0x") {
+        Some(hex) => i32::from_str_radix(hex, 16)?,
+        None => value.parse()?,
+    })
+}
+
+/// Hex encoded PKCS#8 DER, or the path of a PKCS#8 PEM file
+fn parse_private_key(value: &str) -> Result<RsaPrivateKey, Box<dyn Error>> {
+    if let Ok(der) = hex::decode(value) {
+        return Ok(RsaPrivateKey::from_pkcs8_der(&Zeroizing::new(der))?);
+    }
+    Ok(RsaPrivateKey::from_pkcs8_pem(&fs::read_to_string(value)?)?)
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let (command, args) = args.split_first().ok_or(USAGE)?;
+    let options = parse_options(args)?;
+    let [input] = &options.positional[..] else {
+        return Err(USAGE.into());
+    };
+    match command.as_str() {
+        "stream" => dump_stream(input, &options),
+        "capture" => dump_capture(input, &options),
+        "hex" => dump_hex(input, &options),
+        _ => Err(USAGE.into()),
+    }
+}
+
+/// One decoded packet, printed as a text block or a JSON line
+struct Entry<'a> {
+    index: usize,
+    offset: usize,
+    timestamp: Option<Duration>,
+    direction: Direction,
+    state: State,
+    raw: &'a RawPacket,
+    packet: &'a Result<Packet, mc_rust_protocol::Error>,
+}
+
+impl Entry<'_> {
+    fn print(&self, options: &Options) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        let spans = options.spans.then(|| {
+            span::annotate_packet(self.state, self.direction, self.raw.id, &self.raw.payload)
+        });
+        if options.json {
+            let mut line = json!({
+                "index": self.index,
+                "offset": self.offset,
+                "direction": format!("{:?}", self.direction).to_lowercase(),
+                "state": format!("{:?}", self.state).to_lowercase(),
+                "id": self.raw.id,
+                "length": self.raw.payload.len(),
+            });
+            if let Some(timestamp) = self.timestamp {
+                line["timestamp_us"] = json!(timestamp.as_micros() as u64);
+            }
+            match self.packet {
+                Ok(packet) => {
+                    line["name"] = json!(packet.name());
+                    line["packet"] = json!(format!("{:?}", packet));
+                    line["fields"] = visit::to_json(packet);
+                }
+                Err(err) => {
+                    line["error"] = json!(err.to_string());
+                    line["payload"] = json!(hex::encode(&self.raw.payload));
+                }
+            }
+            if let Some(spans) = spans {
+                line["spans"] = json!(spans.root);
+            }
+            return writeln!(out, "{}", line);
+        }
+
+        let timestamp = self
+            .timestamp
+            .map(|t| format!(" {:>10.3}ms", t.as_secs_f64() * 1000.0))
+            .unwrap_or_default();
+        let name = match self.packet {
+            Ok(packet) => packet.name(),
+            Err(_) => "<undecodable>",
+        };
+        writeln!(
+            out,
+            "#{} @{:#x}{} {:?} {:?} {:#04x} {} ({} bytes)",
+            self.index,
+            self.offset,
+            timestamp,
+            self.direction,
+            self.state,
+            self.raw.id,
+            name,
+            self.raw.payload.len()
+        )?;
+        if let Some(spans) = spans {
+            return write!(out, "{}", spans);
+        }
+        match self.packet {
+            Ok(packet) if options.debug => writeln!(out, "{:#?}", packet),
+            Ok(packet) => writeln!(out, "{:#}", packet),
+            Err(err) => writeln!(
+                out,
+                "error: {}\npayload: {}",
+                err,
+                hex::encode(&self.raw.payload)
+            ),
+        }
+    }
+}
+
+/// Counts the bytes read so packets can be located in the file
+struct CountingReader<R: Read> {
+    inner: R,
+    count: Rc<Cell<usize>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count.set(self.count.get() + read);
+        Ok(read)
+    }
+}
+
+fn dump_stream(path: &str, options: &Options) -> Result<(), Box<dyn Error>> {
+    let direction = options.direction.ok_or("--direction is required")?;
+    let mut state = options.state.unwrap_or(match direction {
+        Direction::Serverbound => State::Handshake,
+        Direction::Clientbound => State::Login,
+    });
+    let count = Rc::new(Cell::new(0));
+    let mut decoder = NetworkDecoder::new(CountingReader {
+        inner: BufReader::new(File::open(path)?),
+        count: count.clone(),
+    });
+
+    let has_key = options.aes_key.is_some() || options.private_key.is_some();
+    if let Some(threshold) = options.compression
+        && matches!(state, State::Configuration | State::Play)
+    {
+        decoder.set_compression(threshold);
+    }
+
+    for index in 0.. {
+        let offset = count.get();
+        let raw = match decoder.get_raw_packet() {
+            Ok(raw) => raw,
+            // the stream ended cleanly between two packets
+            Err(err) if count.get() == offset && is_eof(&err) => break,
+            Err(err) => return Err(format!("packet #{} at {:#x}: {}", index, offset, err).into()),
+        };
+        let packet = packet::packet_by_id(state, direction, raw.id, &mut &raw.payload[..]);
+        Entry {
+            index,
+            offset,
+            timestamp: None,
+            direction,
+            state,
+            raw: &raw,
+            packet: &packet,
+        }
+        .print(options)?;
+
+        let Ok(packet) = packet else { continue };
+        match &packet {
+            Packet::EncryptionRequest(_) => {
+                let key = options
+                    .aes_key
+                    .ok_or("the stream is encrypted, --aes-key is required")?;
+                decoder.set_encryption(&key);
+            }
+            Packet::EncryptionResponse(response) => {
+                let key = match (&options.aes_key, &options.private_key) {
+                    (Some(key), _) => *key,
+                    (None, Some(private_key)) => private_key
+                        .decrypt(Pkcs1v15Encrypt, &response.shared_secret.data)?
+                        .try_into()
+                        .map_err(|_| "the shared secret must be 16 bytes")?,
+                    (None, None) => {
+                        return Err(
+                            "the stream is encrypted, --aes-key or --private-key is required"
+                                .into(),
+                        );
+                    }
+                };
+                decoder.set_encryption(&key);
+                if let Some(threshold) = options.compression {
+                    decoder.set_compression(threshold);
+                }
+            }
+            Packet::LoginStart(_) if !has_key => {
+                if let Some(threshold) = options.compression {
+                    decoder.set_compression(threshold);
+                }
+            }
+            Packet::SetCompression(p) => {
+                if let Ok(threshold) = CompressionThreshold::try_from(p.theshold.0) {
+                    decoder.set_compression(threshold);
+                }
+            }
+            _ => {}
+        }
+        if let Some(next) = packet.state_transition() {
+            state = next;
+        }
+    }
+    Ok(())
+}
+
+/// Whether reading failed because the stream ended
+fn is_eof(err: &PacketDecodeError) -> bool {
+    matches!(
+        err,
+        PacketDecodeError::SerializeError(mc_rust_protocol::Error::IoError(err))
+            if err.kind() == io::ErrorKind::UnexpectedEof
+    )
+}
+
+fn dump_capture(path: &str, options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut capture = Capture::open(path)?;
+    if let Some(key) = options.aes_key {
+        capture.header.shared_secret = Some(key);
+    }
+    for (index, packet) in capture.packets()?.enumerate() {
+        let packet = packet?;
+        Entry {
+            index,
+            offset: packet.offset,
+            timestamp: Some(packet.timestamp),
+            direction: packet.direction,
+            state: packet.state,
+            raw: &packet.raw,
+            packet: &packet.packet,
+        }
+        .print(options)?;
+    }
+    Ok(())
+}
+
+fn dump_hex(payload: &str, options: &Options) -> Result<(), Box<dyn Error>> {
+    let direction = options.direction.ok_or("--direction is required")?;
+    let state = options.state.ok_or("--state is required")?;
+    let id = options.id.ok_or("--id is required")?;
+    let payload: String = payload.chars().filter(|c| !c.is_whitespace()).collect();
+    let raw = RawPacket {
+        id,
+        payload: hex::decode(payload)?,
+    };
+    let packet = packet::packet_by_id(state, direction, raw.id, &mut &raw.payload[..]);
+    Entry {
+        index: 0,
+        offset: 0,
+        timestamp: None,
+        direction,
+        state,
+        raw: &raw,
+        packet: &packet,
+    }
+    .print(options)?;
+    packet.map(|_| ()).map_err(Into::into)
+}
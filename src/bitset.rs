@@ -2,9 +2,12 @@ use std::io::Read;
 
 use super::Serializable;
 
-use crate::{Error, PrefixedArray};
+use crate::{
+    Error, PrefixedArray,
+    visit::{Value, Visit, Visitor},
+};
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct BitSet {
     data: PrefixedArray<i64>,
 }
@@ -27,20 +30,26 @@ impl BitSet {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FixedBitSet<const L: usize> {
     data: Vec<u8>,
 }
 
+impl<const L: usize> Visit for FixedBitSet<L> {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(Value::Bytes(&self.data));
+    }
+}
+
 impl<const L: usize> Serializable for FixedBitSet<L> {
     fn read_from<R: std::io::Read>(buf: &mut R) -> Result<Self, crate::Error> {
-        let size = (L + 7) / 8; // integer division rounding up
+        let size = L.div_ceil(8);
         let mut data = Vec::with_capacity(size);
         buf.take(size as u64).read_to_end(&mut data)?;
         Ok(FixedBitSet { data })
     }
     fn write_to<W: std::io::Write>(&self, buf: &mut W) -> Result<(), crate::Error> {
-        if self.data.len() != (L + 7) / 8 {
+        if self.data.len() != L.div_ceil(8) {
             return Err(Error::SerializeError(format!(
                 "wrong fixed bitset length: {}",
                 L
@@ -61,6 +70,14 @@ impl<const L: usize> FixedBitSet<L> {
     }
 
     pub fn new() -> Self {
-        Self { data: vec![0; L] }
+        Self {
+            data: vec![0; L.div_ceil(8)],
+        }
+    }
+}
+
+impl<const L: usize> Default for FixedBitSet<L> {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -0,0 +1,612 @@
+//! Capture files: both directions of a connection as they went over the wire, with timestamps
+//! and the metadata needed to decode them later.
+//!
+//! # Format
+//!
+//! All numbers are big endian, `VarInt`s are the protocol's.
+//!
+//! ```text
+//! magic                  8 bytes   "MCRPCAP\0"
+//! version                u8        1
+//! protocol_version       VarInt
+//! started_at             i64       unix time the capture started, in milliseconds
+//! compression_threshold  bool + VarInt (if true)
+//!                                  for captures that start with compression already enabled
+//! shared_secret          bool + 16 bytes (if true)
+//!                                  AES key of the connection, if known when the capture started
+//! records                until the end of the file:
+//!     timestamp          u64       microseconds since the writer was created
+//!     kind               u8        0: serverbound bytes, 1: clientbound bytes, 2: shared secret
+//!     data               VarInt length + bytes
+//! ```
+//!
+//! Data records hold raw TCP bytes (still encrypted and compressed), so a record can contain
+//! several packets or part of one. Records are written in the order they happened; a shared
+//! secret record can show up anywhere and applies to the whole capture, since a recorder usually
+//! learns the key after the bytes that set it up went by.
+//!
+//! A reader applies the header's `compression_threshold` from the first byte of both directions,
+//! while the shared secret, from the header or a record, only takes effect where decoding reaches
+//! `EncryptionRequest` (clientbound) or `EncryptionResponse` (serverbound).
+
+use std::{
+    cell::Cell,
+    fs::File,
+    io::{self, BufReader, Read, Write},
+    path::Path,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use thiserror::Error;
+
+use crate::{
+    CompressionThreshold, PROTOCOL_VERSION, RawPacket, Serializable, VarInt, Visit,
+    packet::{self, Direction, Packet, State},
+    packet_decoder::{NetworkDecoder, PacketDecodeError},
+};
+
+pub const MAGIC: [u8; 8] = *b"MCRPCAP\0";
+pub const FORMAT_VERSION: u8 = 1;
+
+#[derive(Error, Debug)]
+pub enum CaptureError {
+    #[error("Io Error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("serialize error: {0}")]
+    SerializeError(#[from] crate::Error),
+    #[error("failed to decode packet: {0}")]
+    DecodeError(#[from] PacketDecodeError),
+    #[error("not a capture file")]
+    InvalidMagic,
+    #[error("unsupported capture format version {0}")]
+    UnsupportedVersion(u8),
+    #[error("invalid record: {0}")]
+    InvalidRecord(String),
+    #[error("the connection is encrypted but the capture has no shared secret")]
+    MissingSharedSecret,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
+pub struct CaptureHeader {
+    pub protocol_version: i32,
+    /// Unix time in milliseconds, `new` takes the time it is called rather than that of the
+    /// first record
+    pub started_at: i64,
+    pub compression_threshold: Option<CompressionThreshold>,
+    pub shared_secret: Option<[u8; 16]>,
+}
+
+impl CaptureHeader {
+    /// Header for a capture of this crate's protocol version starting now
+    pub fn new() -> Self {
+        CaptureHeader {
+            protocol_version: PROTOCOL_VERSION,
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|t| t.as_millis() as i64)
+                .unwrap_or_default(),
+            compression_threshold: None,
+            shared_secret: None,
+        }
+    }
+
+    pub fn with_compression_threshold(mut self, threshold: CompressionThreshold) -> Self {
+        self.compression_threshold = Some(threshold);
+        self
+    }
+
+    pub fn with_shared_secret(mut self, shared_secret: [u8; 16]) -> Self {
+        self.shared_secret = Some(shared_secret);
+        self
+    }
+}
+
+impl Default for CaptureHeader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializable for CaptureHeader {
+    fn read_from<R: Read>(buf: &mut R) -> Result<Self, crate::Error> {
+        let protocol_version = VarInt::read_from(buf)?.0;
+        let started_at = i64::read_from(buf)?;
+        let compression_threshold =
+            Option::<VarInt>::read_from(buf)?.map(|threshold| threshold.0 as CompressionThreshold);
+        let shared_secret = if bool::read_from(buf)? {
+            let mut secret = [0u8; 16];
+            buf.read_exact(&mut secret)?;
+            Some(secret)
+        } else {
+            None
+        };
+        Ok(CaptureHeader {
+            protocol_version,
+            started_at,
+            compression_threshold,
+            shared_secret,
+        })
+    }
+
+    fn write_to<W: Write>(&self, buf: &mut W) -> Result<(), crate::Error> {
+        VarInt(self.protocol_version).write_to(buf)?;
+        self.started_at.write_to(buf)?;
+        self.compression_threshold
+            .map(|threshold| VarInt(threshold as i32))
+            .write_to(buf)?;
+        self.shared_secret.is_some().write_to(buf)?;
+        if let Some(secret) = &self.shared_secret {
+            buf.write_all(secret)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Visit)]
+pub enum RecordKind {
+    Data(Direction),
+    SharedSecret,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
+pub struct Record {
+    /// Time since the capture started
+    pub timestamp: Duration,
+    pub kind: RecordKind,
+    pub data: Vec<u8>,
+}
+
+impl Serializable for Record {
+    fn read_from<R: Read>(buf: &mut R) -> Result<Self, crate::Error> {
+        let timestamp = Duration::from_micros(u64::read_from(buf)?);
+        let kind = match u8::read_from(buf)? {
+            0 => RecordKind::Data(Direction::Serverbound),
+            1 => RecordKind::Data(Direction::Clientbound),
+            2 => RecordKind::SharedSecret,
+            kind => {
+                return Err(crate::Error::SerializeError(format!(
+                    "invalid record kind: {}",
+                    kind
+                )));
+            }
+        };
+        let len = VarInt::read_from(buf)?.0 as u64;
+        let mut data = Vec::new();
+        buf.take(len).read_to_end(&mut data)?;
+        if data.len() as u64 != len {
+            return Err(crate::Error::IoError(io::ErrorKind::UnexpectedEof.into()));
+        }
+        Ok(Record {
+            timestamp,
+            kind,
+            data,
+        })
+    }
+
+    fn write_to<W: Write>(&self, buf: &mut W) -> Result<(), crate::Error> {
+        (self.timestamp.as_micros() as u64).write_to(buf)?;
+        let kind: u8 = match self.kind {
+            RecordKind::Data(Direction::Serverbound) => 0,
+            RecordKind::Data(Direction::Clientbound) => 1,
+            RecordKind::SharedSecret => 2,
+        };
+        kind.write_to(buf)?;
+        VarInt(self.data.len() as i32).write_to(buf)?;
+        buf.write_all(&self.data)?;
+        Ok(())
+    }
+}
+
+struct CaptureSink<W: Write> {
+    writer: W,
+    start: Instant,
+}
+
+/// Writes a capture file. Clones share the same file, so one writer can record both directions
+/// from different threads.
+///
+/// ```ignore
+/// let capture = CaptureWriter::new(File::create("session.mccap")?, &CaptureHeader::new())?;
+/// // record below the encryption, and buffer so each packet becomes a single record
+/// let mut decoder = NetworkDecoder::new(BufReader::new(capture.reader(Direction::Clientbound, stream.try_clone()?)));
+/// let mut encoder = NetworkEncoder::new(BufWriter::new(capture.writer(Direction::Serverbound, stream)));
+/// ```
+pub struct CaptureWriter<W: Write> {
+    sink: Arc<Mutex<CaptureSink<W>>>,
+}
+
+impl<W: Write> Clone for CaptureWriter<W> {
+    fn clone(&self) -> Self {
+        CaptureWriter {
+            sink: self.sink.clone(),
+        }
+    }
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(mut writer: W, header: &CaptureHeader) -> Result<Self, CaptureError> {
+        writer.write_all(&MAGIC)?;
+        FORMAT_VERSION.write_to(&mut writer)?;
+        header.write_to(&mut writer)?;
+        Ok(CaptureWriter {
+            sink: Arc::new(Mutex::new(CaptureSink {
+                writer,
+                start: Instant::now(),
+            })),
+        })
+    }
+
+    /// Records bytes that went over the wire in `direction`
+    pub fn record(&self, direction: Direction, data: &[u8]) -> Result<(), CaptureError> {
+        self.write_record(RecordKind::Data(direction), data)
+    }
+
+    /// Records the AES key once it is known, so the capture can be decrypted later
+    pub fn record_shared_secret(&self, shared_secret: &[u8; 16]) -> Result<(), CaptureError> {
+        self.write_record(RecordKind::SharedSecret, shared_secret)
+    }
+
+    fn write_record(&self, kind: RecordKind, data: &[u8]) -> Result<(), CaptureError> {
+        let mut sink = self.sink.lock().unwrap();
+        let record = Record {
+            timestamp: sink.start.elapsed(),
+            kind,
+            data: data.to_vec(),
+        };
+        record.write_to(&mut sink.writer)?;
+        Ok(())
+    }
+
+    pub fn flush(&self) -> Result<(), CaptureError> {
+        self.sink.lock().unwrap().writer.flush()?;
+        Ok(())
+    }
+
+    /// Wraps `reader` so everything read from it is recorded as `direction`
+    pub fn reader<R: Read>(&self, direction: Direction, reader: R) -> RecordingReader<R, W> {
+        RecordingReader {
+            inner: reader,
+            direction,
+            capture: self.clone(),
+        }
+    }
+
+    /// Wraps `writer` so everything written to it is recorded as `direction`
+    pub fn writer<X: Write>(&self, direction: Direction, writer: X) -> RecordingWriter<X, W> {
+        RecordingWriter {
+            inner: writer,
+            direction,
+            capture: self.clone(),
+        }
+    }
+
+    /// Flushes and returns the underlying writer, `None` while other clones
+    /// (or recording readers and writers) are still alive
+    pub fn into_inner(self) -> Option<W> {
+        let mut sink = Arc::into_inner(self.sink)?.into_inner().unwrap();
+        sink.writer.flush().ok()?;
+        Some(sink.writer)
+    }
+}
+
+pub struct RecordingReader<R: Read, W: Write> {
+    inner: R,
+    direction: Direction,
+    capture: CaptureWriter<W>,
+}
+
+impl<R: Read, W: Write> Read for RecordingReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if read > 0 {
+            self.capture
+                .record(self.direction, &buf[..read])
+                .map_err(io::Error::other)?;
+        }
+        Ok(read)
+    }
+}
+
+pub struct RecordingWriter<X: Write, W: Write> {
+    inner: X,
+    direction: Direction,
+    capture: CaptureWriter<W>,
+}
+
+impl<X: Write, W: Write> Write for RecordingWriter<X, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if written > 0 {
+            self.capture
+                .record(self.direction, &buf[..written])
+                .map_err(io::Error::other)?;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A capture file loaded in memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub header: CaptureHeader,
+    pub records: Vec<Record>,
+}
+
+impl Capture {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CaptureError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, CaptureError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(CaptureError::InvalidMagic);
+        }
+        let version = u8::read_from(&mut reader)?;
+        if version != FORMAT_VERSION {
+            return Err(CaptureError::UnsupportedVersion(version));
+        }
+        let header = CaptureHeader::read_from(&mut reader)?;
+
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest)?;
+        let mut rest = &rest[..];
+        let mut records = Vec::new();
+        while !rest.is_empty() {
+            records.push(
+                Record::read_from(&mut rest)
+                    .map_err(|err| CaptureError::InvalidRecord(err.to_string()))?,
+            );
+        }
+        // stable, so records with the same timestamp keep the order they were written in
+        records.sort_by_key(|record| record.timestamp);
+
+        Ok(Capture { header, records })
+    }
+
+    /// The shared secret from the header, or else from the first shared secret record
+    pub fn shared_secret(&self) -> Result<Option<[u8; 16]>, CaptureError> {
+        if let Some(secret) = self.header.shared_secret {
+            return Ok(Some(secret));
+        }
+        self.records
+            .iter()
+            .find(|record| record.kind == RecordKind::SharedSecret)
+            .map(|record| {
+                record.data[..].try_into().map_err(|_| {
+                    CaptureError::InvalidRecord("shared secret must be 16 bytes".to_owned())
+                })
+            })
+            .transpose()
+    }
+
+    /// Decodes both directions, in the order the packets were sent.
+    ///
+    /// Decoding follows the connection like the client and server did: states switch on the
+    /// packets that switch them, decryption starts after `EncryptionRequest`/`EncryptionResponse`
+    /// and compression after `SetCompression`.
+    pub fn packets(&self) -> Result<CapturePackets, CaptureError> {
+        let shared_secret = self.shared_secret()?;
+        let stream = |direction| {
+            let mut data = Vec::new();
+            let mut chunks = Vec::new();
+            for (index, record) in self.records.iter().enumerate() {
+                if record.kind == RecordKind::Data(direction) {
+                    chunks.push(Chunk {
+                        offset: data.len(),
+                        record: index,
+                        timestamp: record.timestamp,
+                    });
+                    data.extend_from_slice(&record.data);
+                }
+            }
+            let len = data.len();
+            let position = Rc::new(Cell::new(0));
+            let truncated = Rc::new(Cell::new(false));
+            let mut decoder = NetworkDecoder::new(StreamReader {
+                data,
+                position: position.clone(),
+                truncated: truncated.clone(),
+            });
+            if let Some(threshold) = self.header.compression_threshold {
+                decoder.set_compression(threshold);
+            }
+            DirectionStream {
+                decoder,
+                position,
+                truncated,
+                len,
+                chunks,
+                state: State::Handshake,
+            }
+        };
+
+        Ok(CapturePackets {
+            serverbound: stream(Direction::Serverbound),
+            clientbound: stream(Direction::Clientbound),
+            shared_secret,
+            pending_error: None,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct CapturedPacket {
+    /// Time since the capture started, of the record the packet starts in
+    pub timestamp: Duration,
+    pub direction: Direction,
+    /// Where the packet starts in the bytes of its direction
+    pub offset: usize,
+    /// State the packet was decoded in
+    pub state: State,
+    pub raw: RawPacket,
+    /// The decoded packet, or why it couldn't be decoded (the stream keeps going either way)
+    pub packet: Result<Packet, crate::Error>,
+}
+
+/// Reads one direction's bytes, sharing the position so the decoder's progress is visible
+struct StreamReader {
+    data: Vec<u8>,
+    position: Rc<Cell<usize>>,
+    /// Set when the decoder wanted more bytes than the capture has
+    truncated: Rc<Cell<bool>>,
+}
+
+impl Read for StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let position = self.position.get();
+        let read = (&self.data[position..]).read(buf)?;
+        if read == 0 && !buf.is_empty() {
+            self.truncated.set(true);
+        }
+        self.position.set(position + read);
+        Ok(read)
+    }
+}
+
+/// Where a record starts in its direction's stream
+struct Chunk {
+    offset: usize,
+    /// Index in `Capture::records`, orders the chunks of both directions
+    record: usize,
+    timestamp: Duration,
+}
+
+struct DirectionStream {
+    decoder: NetworkDecoder<StreamReader>,
+    position: Rc<Cell<usize>>,
+    truncated: Rc<Cell<bool>>,
+    len: usize,
+    chunks: Vec<Chunk>,
+    state: State,
+}
+
+impl DirectionStream {
+    /// The record the next packet starts in, `None` at the end of the stream
+    fn next_chunk(&self) -> Option<&Chunk> {
+        let position = self.position.get();
+        if position >= self.len {
+            return None;
+        }
+        let index = self
+            .chunks
+            .partition_point(|chunk| chunk.offset <= position)
+            - 1;
+        Some(&self.chunks[index])
+    }
+
+    fn finish(&mut self) {
+        self.position.set(self.len);
+    }
+}
+
+/// Iterator over the packets of a capture, see `Capture::packets`
+pub struct CapturePackets {
+    serverbound: DirectionStream,
+    clientbound: DirectionStream,
+    shared_secret: Option<[u8; 16]>,
+    pending_error: Option<CaptureError>,
+}
+
+impl CapturePackets {
+    fn stream(&mut self, direction: Direction) -> &mut DirectionStream {
+        match direction {
+            Direction::Serverbound => &mut self.serverbound,
+            Direction::Clientbound => &mut self.clientbound,
+        }
+    }
+
+    fn enable_encryption(&mut self, direction: Direction) {
+        match self.shared_secret {
+            Some(secret) => self.stream(direction).decoder.set_encryption(&secret),
+            None => {
+                self.stream(direction).finish();
+                self.pending_error = Some(CaptureError::MissingSharedSecret);
+            }
+        }
+    }
+
+    /// Applies what the packet changes about the connection
+    fn follow(&mut self, direction: Direction, packet: &Packet) {
+        match packet {
+            Packet::Handshake(p) => self.clientbound.state = p.intent.into(),
+            // the server encrypts everything after the request, the client after the response
+            Packet::EncryptionRequest(_) | Packet::EncryptionResponse(_) => {
+                self.enable_encryption(direction)
+            }
+            // the client only sends packets after it has processed this one
+            Packet::SetCompression(p) => {
+                if let Ok(threshold) = CompressionThreshold::try_from(p.theshold.0) {
+                    self.serverbound.decoder.set_compression(threshold);
+                    self.clientbound.decoder.set_compression(threshold);
+                }
+            }
+            _ => {}
+        }
+        if let Some(state) = packet.state_transition() {
+            self.stream(direction).state = state;
+        }
+    }
+}
+
+impl Iterator for CapturePackets {
+    type Item = Result<CapturedPacket, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.pending_error.take() {
+            return Some(Err(err));
+        }
+
+        let direction = match (self.serverbound.next_chunk(), self.clientbound.next_chunk()) {
+            (None, None) => return None,
+            (Some(_), None) => Direction::Serverbound,
+            (None, Some(_)) => Direction::Clientbound,
+            (Some(serverbound), Some(clientbound)) => {
+                if serverbound.record <= clientbound.record {
+                    Direction::Serverbound
+                } else {
+                    Direction::Clientbound
+                }
+            }
+        };
+        let stream = self.stream(direction);
+        let timestamp = stream.next_chunk()?.timestamp;
+        let state = stream.state;
+        let offset = stream.position.get();
+
+        let raw = stream.decoder.get_raw_packet();
+        if stream.truncated.get() {
+            // the capture stopped in the middle of a packet
+            stream.finish();
+            return self.next();
+        }
+        let raw = match raw {
+            Ok(raw) => raw,
+            Err(err) => {
+                stream.finish();
+                return Some(Err(err.into()));
+            }
+        };
+
+        let packet = packet::packet_by_id(state, direction, raw.id, &mut &raw.payload[..]);
+        if let Ok(packet) = &packet {
+            self.follow(direction, packet);
+        }
+
+        Some(Ok(CapturedPacket {
+            timestamp,
+            direction,
+            offset,
+            state,
+            raw,
+            packet,
+        }))
+    }
+}
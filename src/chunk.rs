@@ -0,0 +1,487 @@
+//! Saved chunks, the NBT of region files, to `ChunkDataAndUpdateLight` packets.
+//!
+//! Palettes map block states and biomes by name, the packet uses protocol IDs. `ChunkRegistries`
+//! holds them: block states and block entity types from the reports of vanilla's data generator
+//! (`java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports`), biomes in the
+//! order of the `RegistryData` the server sent.
+//!
+//! Indices of palettes small enough for the network are copied as they are, larger palettes are
+//! repacked with global IDs.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    LenPrefixedBytes, PrefixedArray, Serializable, VarInt,
+    bitset::BitSet,
+    nbt::Tag,
+    packet::{BlockEntity, ChunkData, HeightMap, LightData, PackedXZ, s2c::play},
+};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ChunkError {
+    #[error("invalid report: {0}")]
+    InvalidReport(String),
+    #[error("missing or invalid {0}")]
+    InvalidField(String),
+    #[error("chunk status is {0}, only full chunks can be sent")]
+    NotFull(String),
+    #[error("unknown block state {0}")]
+    UnknownBlock(String),
+    #[error("unknown biome {0}")]
+    UnknownBiome(String),
+}
+
+/// Heightmaps the client uses, with their protocol IDs
+const HEIGHTMAPS: [(&str, i32); 3] = [
+    ("WORLD_SURFACE", 1),
+    ("MOTION_BLOCKING", 4),
+    ("MOTION_BLOCKING_NO_LEAVES", 5),
+];
+const AIR: [&str; 3] = ["minecraft:air", "minecraft:cave_air", "minecraft:void_air"];
+const BLOCKS_PER_SECTION: usize = 4096;
+const BIOMES_PER_SECTION: usize = 64;
+/// A nibble per block
+const LIGHT_BYTES: usize = BLOCKS_PER_SECTION / 2;
+/// Larger palettes are sent with global IDs
+const MAX_BLOCK_BITS: u32 = 8;
+const MAX_BIOME_BITS: u32 = 3;
+
+/// Protocol IDs for the names in saved chunks
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkRegistries {
+    /// `minecraft:oak_log[axis=y]`, properties sorted by name
+    block_states: HashMap<String, i32>,
+    default_states: HashMap<String, i32>,
+    air_states: Vec<i32>,
+    block_state_count: usize,
+    biomes: HashMap<String, i32>,
+    block_entity_types: HashMap<String, i32>,
+}
+
+impl ChunkRegistries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Block states from `reports/blocks.json`, block entity types from
+    /// `reports/registries.json`
+    pub fn from_reports(blocks: &Value, registries: &Value) -> Result<Self, ChunkError> {
+        let invalid = |what: &str| ChunkError::InvalidReport(what.to_owned());
+        let mut result = ChunkRegistries::new();
+        let blocks = blocks.as_object().ok_or_else(|| invalid("blocks.json"))?;
+        for (name, block) in blocks {
+            let states = block["states"]
+                .as_array()
+                .ok_or_else(|| invalid(&format!("states of {}", name)))?;
+            for state in states {
+                let id = state["id"]
+                    .as_i64()
+                    .ok_or_else(|| invalid(&format!("state id of {}", name)))?;
+                let properties: Vec<(&str, &str)> = state["properties"]
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(key, value)| Some((key.as_str(), value.as_str()?)))
+                    .collect();
+                let default = state["default"].as_bool().unwrap_or(false);
+                result.add_block_state(name, &properties, id as i32, default);
+            }
+        }
+        let entries = registries["minecraft:block_entity_type"]["entries"]
+            .as_object()
+            .ok_or_else(|| invalid("minecraft:block_entity_type in registries.json"))?;
+        for (name, entry) in entries {
+            let id = entry["protocol_id"]
+                .as_i64()
+                .ok_or_else(|| invalid(&format!("protocol_id of {}", name)))?;
+            result.block_entity_types.insert(name.clone(), id as i32);
+        }
+        Ok(result)
+    }
+
+    /// Biome IDs are their index in the server's `minecraft:worldgen/biome` registry data
+    pub fn with_biomes<S: Into<String>>(mut self, biomes: impl IntoIterator<Item = S>) -> Self {
+        self.biomes = biomes
+            .into_iter()
+            .enumerate()
+            .map(|(id, name)| (name.into(), id as i32))
+            .collect();
+        self
+    }
+
+    pub fn add_block_state(
+        &mut self,
+        name: &str,
+        properties: &[(&str, &str)],
+        id: i32,
+        default: bool,
+    ) {
+        self.block_states.insert(state_key(name, properties), id);
+        if default {
+            self.default_states.insert(name.to_owned(), id);
+        }
+        if AIR.contains(&name) {
+            self.air_states.push(id);
+        }
+        self.block_state_count = self.block_state_count.max(id as usize + 1);
+    }
+
+    pub fn add_block_entity_type(&mut self, name: &str, id: i32) {
+        self.block_entity_types.insert(name.to_owned(), id);
+    }
+
+    /// The ID of a palette entry, `{Name:"minecraft:oak_log",Properties:{axis:"y"}}`. Missing
+    /// properties make it the block's default state.
+    pub fn block_state(&self, entry: &Tag) -> Result<i32, ChunkError> {
+        let name = entry
+            .get_as::<&str>("Name")
+            .ok_or_else(|| ChunkError::InvalidField("block state Name".to_owned()))?;
+        let properties: Vec<(&str, &str)> = entry
+            .get("Properties")
+            .and_then(Tag::as_compound)
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, value)| Some((key.as_str(), value.as_str()?)))
+            .collect();
+        let key = state_key(name, &properties);
+        self.block_states
+            .get(&key)
+            .or_else(|| {
+                properties
+                    .is_empty()
+                    .then(|| self.default_states.get(name))
+                    .flatten()
+            })
+            .copied()
+            .ok_or(ChunkError::UnknownBlock(key))
+    }
+
+    pub fn biome(&self, name: &str) -> Result<i32, ChunkError> {
+        self.biomes
+            .get(name)
+            .copied()
+            .ok_or_else(|| ChunkError::UnknownBiome(name.to_owned()))
+    }
+}
+
+fn state_key(name: &str, properties: &[(&str, &str)]) -> String {
+    if properties.is_empty() {
+        return name.to_owned();
+    }
+    let mut properties = properties.to_vec();
+    properties.sort_unstable();
+    let properties: Vec<String> = properties
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    format!("{}[{}]", name, properties.join(","))
+}
+
+/// Converts saved chunks of one dimension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkConverter {
+    pub registries: ChunkRegistries,
+    /// The lowest section, `min_y / 16`
+    pub min_section: i32,
+    pub section_count: usize,
+}
+
+impl ChunkConverter {
+    /// For the overworld's height, y -64 to 319
+    pub fn new(registries: ChunkRegistries) -> Self {
+        ChunkConverter {
+            registries,
+            min_section: -4,
+            section_count: 24,
+        }
+    }
+
+    /// `min_y` and `height` of the dimension type, multiples of 16
+    pub fn with_height(mut self, min_y: i32, height: u32) -> Self {
+        self.min_section = min_y.div_euclid(16);
+        self.section_count = height as usize / 16;
+        self
+    }
+
+    /// The packet for a saved chunk compound, with `xPos`, `zPos`, `sections`, `Heightmaps` and
+    /// `block_entities`. Block entities of unknown types are left out.
+    pub fn convert(&self, chunk: &Tag) -> Result<play::ChunkDataAndUpdateLight, ChunkError> {
+        let field = |name: &str| ChunkError::InvalidField(name.to_owned());
+        match chunk.get_as::<&str>("Status") {
+            None | Some("minecraft:full" | "full") => {}
+            Some(status) => return Err(ChunkError::NotFull(status.to_owned())),
+        }
+        let chunk_x = chunk.get_as("xPos").ok_or_else(|| field("xPos"))?;
+        let chunk_z = chunk.get_as("zPos").ok_or_else(|| field("zPos"))?;
+        let sections = chunk
+            .get("sections")
+            .and_then(Tag::as_list)
+            .unwrap_or_default();
+        let section = |y: i32| {
+            sections
+                .iter()
+                .find(|section| section.get_as::<i8>("Y").map(i32::from) == Some(y))
+        };
+
+        let mut data = Vec::new();
+        for y in self.min_section..self.min_section + self.section_count as i32 {
+            self.write_section(section(y), &mut data)?;
+        }
+
+        let heightmaps = HEIGHTMAPS
+            .iter()
+            .filter_map(|(name, id)| {
+                let longs = chunk.get("Heightmaps")?.get(name)?.as_long_array()?;
+                Some(HeightMap {
+                    ty: VarInt(*id),
+                    data: PrefixedArray::new(longs.to_vec()),
+                })
+            })
+            .collect();
+
+        let block_entities = chunk
+            .get("block_entities")
+            .and_then(Tag::as_list)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|tag| self.block_entity(tag))
+            .collect();
+
+        Ok(play::ChunkDataAndUpdateLight {
+            chunk_x,
+            chunk_z,
+            data: ChunkData {
+                heightmaps: PrefixedArray::new(heightmaps),
+                data: LenPrefixedBytes::new(data),
+                block_entities: PrefixedArray::new(block_entities),
+            },
+            light: self.light(&section)?,
+        })
+    }
+
+    fn write_section(&self, section: Option<&Tag>, out: &mut Vec<u8>) -> Result<(), ChunkError> {
+        let registries = &self.registries;
+        let air = registries
+            .default_states
+            .get(AIR[0])
+            .copied()
+            .unwrap_or_default();
+        let block_states = section.and_then(|section| section.get("block_states"));
+        let palette = match block_states.and_then(|states| states.get("palette")) {
+            Some(palette) => palette
+                .as_list()
+                .filter(|palette| !palette.is_empty())
+                .ok_or_else(|| ChunkError::InvalidField("block_states palette".to_owned()))?
+                .iter()
+                .map(|entry| registries.block_state(entry))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![air],
+        };
+        let indices = block_states
+            .and_then(|states| states.get("data"))
+            .and_then(Tag::as_long_array)
+            .unwrap_or_default();
+        let blocks = Palette {
+            ids: palette,
+            data: indices,
+            entries: BLOCKS_PER_SECTION,
+            min_bits: 4,
+            max_bits: MAX_BLOCK_BITS,
+            global_bits: bits_for(registries.block_state_count),
+        };
+        let non_air = blocks.count(|id| !registries.air_states.contains(&id))?;
+        out.extend_from_slice(&(non_air as i16).to_be_bytes());
+        blocks.write(out)?;
+
+        let biomes = section.and_then(|section| section.get("biomes"));
+        let palette = match biomes.and_then(|biomes| biomes.get("palette")) {
+            Some(palette) => palette
+                .as_list()
+                .filter(|palette| !palette.is_empty())
+                .ok_or_else(|| ChunkError::InvalidField("biomes palette".to_owned()))?
+                .iter()
+                .map(|name| {
+                    let name = name
+                        .as_str()
+                        .ok_or_else(|| ChunkError::InvalidField("biome name".to_owned()))?;
+                    registries.biome(name)
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![registries.biome("minecraft:plains").unwrap_or_default()],
+        };
+        let indices = biomes
+            .and_then(|biomes| biomes.get("data"))
+            .and_then(Tag::as_long_array)
+            .unwrap_or_default();
+        Palette {
+            ids: palette,
+            data: indices,
+            entries: BIOMES_PER_SECTION,
+            min_bits: 1,
+            max_bits: MAX_BIOME_BITS,
+            global_bits: bits_for(registries.biomes.len()),
+        }
+        .write(out)
+    }
+
+    fn block_entity(&self, tag: &Tag) -> Option<BlockEntity> {
+        let ty = *self
+            .registries
+            .block_entity_types
+            .get(tag.get_as::<&str>("id")?)?;
+        let (x, y, z): (i32, i32, i32) = (tag.get_as("x")?, tag.get_as("y")?, tag.get_as("z")?);
+        let mut data = tag.clone();
+        for key in ["id", "x", "y", "z", "keepPacked"] {
+            data.remove(key);
+        }
+        Some(BlockEntity {
+            packed_xz: PackedXZ::new(x.rem_euclid(16) as u8, z.rem_euclid(16) as u8),
+            y: y as i16,
+            ty: VarInt(ty),
+            data,
+        })
+    }
+
+    /// Light sections reach one past the blocks at the bottom and the top
+    fn light<'a>(
+        &self,
+        section: &impl Fn(i32) -> Option<&'a Tag>,
+    ) -> Result<LightData, ChunkError> {
+        let count = self.section_count + 2;
+        let longs = count.div_ceil(64);
+        let mut light = LightData {
+            sky_light_mask: BitSet::new(longs),
+            block_light_mask: BitSet::new(longs),
+            empty_sky_light_mask: BitSet::new(longs),
+            empty_block_light_mask: BitSet::new(longs),
+            sky_light_arrays: PrefixedArray::new(Vec::new()),
+            block_light_arrays: PrefixedArray::new(Vec::new()),
+        };
+        for i in 0..count {
+            let Some(section) = section(self.min_section - 1 + i as i32) else {
+                continue;
+            };
+            let add = |key: &str, mask: &mut BitSet, empty: &mut BitSet, arrays: &mut Vec<_>| {
+                match section.get(key).and_then(Tag::as_byte_array) {
+                    Some(nibbles) if nibbles.len() != LIGHT_BYTES => {
+                        return Err(ChunkError::InvalidField(key.to_owned()));
+                    }
+                    Some(nibbles) if nibbles.iter().all(|b| *b == 0) => empty.set(i as u64),
+                    Some(nibbles) => {
+                        mask.set(i as u64);
+                        arrays.push(LenPrefixedBytes::<VarInt>::new(nibbles.to_vec()));
+                    }
+                    None => {}
+                }
+                Ok(())
+            };
+            add(
+                "SkyLight",
+                &mut light.sky_light_mask,
+                &mut light.empty_sky_light_mask,
+                &mut light.sky_light_arrays.data,
+            )?;
+            add(
+                "BlockLight",
+                &mut light.block_light_mask,
+                &mut light.empty_block_light_mask,
+                &mut light.block_light_arrays.data,
+            )?;
+        }
+        Ok(light)
+    }
+}
+
+/// Bits for IDs below `count`
+fn bits_for(count: usize) -> u32 {
+    usize::BITS - count.saturating_sub(1).leading_zeros()
+}
+
+/// A paletted container as saved: palette IDs and packed indices, which don't span longs
+struct Palette<'a> {
+    ids: Vec<i32>,
+    data: &'a [i64],
+    entries: usize,
+    min_bits: u32,
+    max_bits: u32,
+    global_bits: u32,
+}
+
+impl Palette<'_> {
+    /// Bits per saved index
+    fn bits(&self) -> u32 {
+        bits_for(self.ids.len()).max(self.min_bits)
+    }
+
+    fn index(&self, i: usize) -> usize {
+        let bits = self.bits() as usize;
+        let per_long = 64 / bits;
+        let long = self.data[i / per_long] as u64;
+        ((long >> ((i % per_long) * bits)) & ((1 << bits) - 1)) as usize
+    }
+
+    fn check(&self) -> Result<(), ChunkError> {
+        if self.ids.len() > 1 {
+            // more entries than the registry has IDs
+            if bits_for(self.ids.len()) > self.global_bits {
+                return Err(ChunkError::InvalidField("palette length".to_owned()));
+            }
+            let per_long = 64 / self.bits() as usize;
+            if self.data.len() != self.entries.div_ceil(per_long) {
+                return Err(ChunkError::InvalidField("palette data length".to_owned()));
+            }
+            if (0..self.entries).any(|i| self.index(i) >= self.ids.len()) {
+                return Err(ChunkError::InvalidField("palette index".to_owned()));
+            }
+        }
+        Ok(())
+    }
+
+    fn count(&self, pred: impl Fn(i32) -> bool) -> Result<usize, ChunkError> {
+        self.check()?;
+        Ok(match self.ids[..] {
+            [id] => usize::from(pred(id)) * self.entries,
+            _ => (0..self.entries)
+                .filter(|i| pred(self.ids[self.index(*i)]))
+                .count(),
+        })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) -> Result<(), ChunkError> {
+        self.check()?;
+        let mut longs = Vec::new();
+        match self.ids[..] {
+            [id] => {
+                out.push(0);
+                VarInt(id).write_to(out).expect("writing to a Vec");
+            }
+            _ if self.bits() <= self.max_bits => {
+                out.push(self.bits() as u8);
+                VarInt(self.ids.len() as i32)
+                    .write_to(out)
+                    .expect("writing to a Vec");
+                for id in &self.ids {
+                    VarInt(*id).write_to(out).expect("writing to a Vec");
+                }
+                longs.extend_from_slice(self.data);
+            }
+            _ => {
+                let bits = self.global_bits as usize;
+                let per_long = 64 / bits;
+                out.push(bits as u8);
+                longs = vec![0; self.entries.div_ceil(per_long)];
+                for i in 0..self.entries {
+                    let id = self.ids[self.index(i)] as u64;
+                    longs[i / per_long] |= (id << ((i % per_long) * bits)) as i64;
+                }
+            }
+        }
+        for long in longs {
+            out.extend_from_slice(&long.to_be_bytes());
+        }
+        Ok(())
+    }
+}
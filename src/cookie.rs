@@ -0,0 +1,321 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Read, Write},
+};
+
+use thiserror::Error;
+
+use crate::{
+    LenPrefixedBytes, PROTOCOL_VERSION, VarInt,
+    packet::{
+        self, Direction, Intent, Packet, PacketType, State,
+        c2s::{
+            configuration::CookieResponseConfiguration, handshake::Handshake,
+            login::CookieResponseLogin, play::CookieResponsePlay,
+        },
+        s2c::{
+            configuration::{
+                CookieRequestConfiguration, StoreCookieConfiguration, TransferConfiguration,
+            },
+            login::CookieRequestLogin,
+            play::{CookieRequest, StoreCookiePlay, TransferPlay},
+        },
+    },
+    packet_decoder::{NetworkDecoder, PacketDecodeError},
+    packet_encoder::{NetworkEncoder, PacketEncodeError},
+};
+
+/// Largest cookie payload vanilla clients store and vanilla servers accept
+pub const MAX_COOKIE_SIZE: usize = 5120;
+
+#[derive(Error, Debug)]
+pub enum CookieError {
+    #[error("cookie {key} is {size} bytes, the limit is {MAX_COOKIE_SIZE}")]
+    TooLarge { key: String, size: usize },
+    #[error("cookies can't be sent in the {0:?} state")]
+    InvalidState(State),
+    #[error("failed to decode packet: {0}")]
+    DecodeError(#[from] PacketDecodeError),
+    #[error("failed to encode packet: {0}")]
+    EncodeError(#[from] PacketEncodeError),
+    #[error("serialize error: {0}")]
+    SerializeError(#[from] crate::Error),
+    #[error("unexpected packet: {0}")]
+    UnexpectedPacket(String),
+}
+
+fn check_size(key: &str, payload: &[u8]) -> Result<(), CookieError> {
+    if payload.len() > MAX_COOKIE_SIZE {
+        return Err(CookieError::TooLarge {
+            key: key.to_owned(),
+            size: payload.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Client side cookie storage. Vanilla keeps cookies in memory for the lifetime of the
+/// game session, so they survive transfers but not restarts.
+pub trait CookieStore {
+    fn get(&self, key: &str) -> Option<Vec<u8>>;
+    /// Called by `store` after the size limit was checked
+    fn set(&mut self, key: String, payload: Vec<u8>);
+    fn remove(&mut self, key: &str) -> Option<Vec<u8>>;
+
+    fn store(&mut self, key: &str, payload: Vec<u8>) -> Result<(), CookieError> {
+        check_size(key, &payload)?;
+        self.set(key.to_owned(), payload);
+        Ok(())
+    }
+
+    /// Feed every clientbound packet through this: stores `StoreCookie*` payloads and
+    /// returns the `CookieResponse*` answer for `CookieRequest*` packets.
+    fn handle(&mut self, packet: &Packet) -> Result<Option<Packet>, CookieError> {
+        let response = match packet {
+            Packet::StoreCookieConfiguration(p) => {
+                self.store(&p.key, p.payload.data.clone())?;
+                None
+            }
+            Packet::StoreCookiePlay(p) => {
+                self.store(&p.key, p.payload.data.clone())?;
+                None
+            }
+            Packet::CookieRequestLogin(p) => {
+                Some(Packet::CookieResponseLogin(CookieResponseLogin {
+                    key: p.key.clone(),
+                    payload: self.get(&p.key).map(LenPrefixedBytes::new),
+                }))
+            }
+            Packet::CookieRequestConfiguration(p) => Some(Packet::CookieResponseConfiguration(
+                CookieResponseConfiguration {
+                    key: p.key.clone(),
+                    payload: self.get(&p.key).map(LenPrefixedBytes::new),
+                },
+            )),
+            Packet::CookieRequest(p) => Some(Packet::CookieResponsePlay(CookieResponsePlay {
+                key: p.key.clone(),
+                payload: self.get(&p.key).map(LenPrefixedBytes::new),
+            })),
+            _ => None,
+        };
+        Ok(response)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CookieJar {
+    cookies: HashMap<String, Vec<u8>>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+}
+
+impl CookieStore for CookieJar {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.cookies.get(key).cloned()
+    }
+
+    fn set(&mut self, key: String, payload: Vec<u8>) {
+        self.cookies.insert(key, payload);
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.cookies.remove(key)
+    }
+}
+
+/// `StoreCookie*` packet for `state`, only the configuration and play states have one
+pub fn store_cookie(state: State, key: &str, payload: Vec<u8>) -> Result<Packet, CookieError> {
+    check_size(key, &payload)?;
+    let key = key.to_owned();
+    let payload = LenPrefixedBytes::new(payload);
+    match state {
+        State::Configuration => Ok(Packet::StoreCookieConfiguration(StoreCookieConfiguration {
+            key,
+            payload,
+        })),
+        State::Play => Ok(Packet::StoreCookiePlay(StoreCookiePlay { key, payload })),
+        state => Err(CookieError::InvalidState(state)),
+    }
+}
+
+/// `CookieRequest*` packet for `state`
+pub fn request_cookie(state: State, key: &str) -> Result<Packet, CookieError> {
+    let key = key.to_owned();
+    match state {
+        State::Login => Ok(Packet::CookieRequestLogin(CookieRequestLogin { key })),
+        State::Configuration => Ok(Packet::CookieRequestConfiguration(
+            CookieRequestConfiguration { key },
+        )),
+        State::Play => Ok(Packet::CookieRequest(CookieRequest { key })),
+        state => Err(CookieError::InvalidState(state)),
+    }
+}
+
+/// Key and payload of a cookie response, the payload is `None` when the client has no such cookie
+pub type Cookie<'a> = (&'a str, Option<&'a [u8]>);
+
+/// Server side: extracts the key and payload of a `CookieResponse*` packet, rejecting payloads
+/// over the size limit like vanilla does. Returns `None` for other packets.
+pub fn cookie_response(packet: &Packet) -> Option<Result<Cookie<'_>, CookieError>> {
+    let (key, payload) = match packet {
+        Packet::CookieResponseLogin(p) => (&p.key, &p.payload),
+        Packet::CookieResponseConfiguration(p) => (&p.key, &p.payload),
+        Packet::CookieResponsePlay(p) => (&p.key, &p.payload),
+        _ => return None,
+    };
+    let payload = payload.as_ref().map(|p| &p.data[..]);
+    if let Some(payload) = payload
+        && let Err(err) = check_size(key, payload)
+    {
+        return Some(Err(err));
+    }
+    Some(Ok((key, payload)))
+}
+
+/// Where a `Transfer*` packet sends the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferTarget {
+    pub host: String,
+    pub port: u16,
+}
+
+impl TransferTarget {
+    pub fn new(host: &str, port: u16) -> Self {
+        TransferTarget {
+            host: host.to_owned(),
+            port,
+        }
+    }
+
+    /// Client side: the target of a `TransferConfiguration`/`TransferPlay` packet
+    pub fn from_packet(packet: &Packet) -> Option<Self> {
+        let (host, port) = match packet {
+            Packet::TransferConfiguration(p) => (&p.host, p.port.0),
+            Packet::TransferPlay(p) => (&p.host, p.port.0),
+            _ => return None,
+        };
+        Some(TransferTarget {
+            host: host.clone(),
+            port: port as u16,
+        })
+    }
+
+    /// Server side: the `Transfer*` packet for `state`
+    pub fn packet(&self, state: State) -> Result<Packet, CookieError> {
+        let host = self.host.clone();
+        let port = VarInt(self.port as i32);
+        match state {
+            State::Configuration => Ok(Packet::TransferConfiguration(TransferConfiguration {
+                host,
+                port,
+            })),
+            State::Play => Ok(Packet::TransferPlay(TransferPlay { host, port })),
+            state => Err(CookieError::InvalidState(state)),
+        }
+    }
+
+    /// Server side: stores `cookies` on the client then transfers it, the packets have to
+    /// be sent in order
+    pub fn packets(
+        &self,
+        state: State,
+        cookies: &[(&str, &[u8])],
+    ) -> Result<Vec<Packet>, CookieError> {
+        let mut packets = cookies
+            .iter()
+            .map(|(key, payload)| store_cookie(state, key, payload.to_vec()))
+            .collect::<Result<Vec<_>, _>>()?;
+        packets.push(self.packet(state)?);
+        Ok(packets)
+    }
+
+    /// Client side: the handshake to send to the new server, the connection then
+    /// continues with the login state
+    pub fn handshake(&self) -> Handshake {
+        Handshake {
+            protocol_version: VarInt(PROTOCOL_VERSION),
+            server_adress: self.host.clone(),
+            server_port: self.port,
+            intent: Intent::Transfer,
+        }
+    }
+}
+
+impl Handshake {
+    pub fn is_transfer(&self) -> bool {
+        matches!(self.intent, Intent::Transfer)
+    }
+}
+
+/// Server side of a transfer: requests `keys` from a client that connected with
+/// `Intent::Transfer` and waits for all the answers.
+///
+/// Runs in the login state, after the caller has read `LoginStart` (and set up encryption and
+/// compression if wanted). Missing cookies are `None` in the result, a response for a key that
+/// wasn't requested is an error.
+pub fn read_transfer_cookies<R: Read, W: Write>(
+    decoder: &mut NetworkDecoder<R>,
+    encoder: &mut NetworkEncoder<W>,
+    keys: &[&str],
+) -> Result<HashMap<String, Option<Vec<u8>>>, CookieError> {
+    // keys still waiting for an answer, each key is requested once
+    let mut pending = HashSet::new();
+    for key in keys {
+        if pending.insert(*key) {
+            send(
+                encoder,
+                &CookieRequestLogin {
+                    key: (*key).to_owned(),
+                },
+            )?;
+        }
+    }
+
+    let mut cookies = HashMap::new();
+    while !pending.is_empty() {
+        let raw = decoder.get_raw_packet()?;
+        let packet = packet::packet_by_id(
+            State::Login,
+            Direction::Serverbound,
+            raw.id,
+            &mut &raw.payload[..],
+        )?;
+        match cookie_response(&packet) {
+            Some(response) => {
+                let (key, payload) = response?;
+                if !pending.remove(key) {
+                    // vanilla disconnects clients answering a request it didn't send
+                    return Err(CookieError::UnexpectedPacket(format!(
+                        "response for cookie {:?} that wasn't requested or was already answered",
+                        key
+                    )));
+                }
+                cookies.insert(key.to_owned(), payload.map(<[u8]>::to_vec));
+            }
+            None => return Err(CookieError::UnexpectedPacket(format!("{:?}", packet))),
+        }
+    }
+    Ok(cookies)
+}
+
+fn send<W: Write, P: PacketType>(
+    encoder: &mut NetworkEncoder<W>,
+    packet: &P,
+) -> Result<(), CookieError> {
+    let mut buf = Vec::new();
+    packet.write(&mut buf)?;
+    encoder.write_packet(&buf)?;
+    Ok(())
+}
@@ -0,0 +1,512 @@
+//! Field-level differences between decoded packets, packet streams and captures.
+//!
+//! Packets are flattened with `Visit` into leaves named by their path, e.g.
+//! `profile.properties[0].name`, and compared leaf by leaf. Enum variants are leaves of their
+//! own, so a changed variant shows up once rather than as a change of every field.
+//!
+//! Streams are aligned by packet type: a packet that only one side has is reported as removed
+//! or added instead of shifting every following comparison.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+
+use crate::{
+    UUID,
+    capture::{Capture, CaptureError},
+    nbt::Tag,
+    packet::{Direction, Packet},
+    visit::{Value, Visit, Visitor},
+};
+
+/// Fields that differ between any two sessions, see `DiffOptions::with_volatile_fields`
+pub const VOLATILE_FIELDS: &[&str] = &[
+    "keep_alive_id",
+    "timestamp",
+    "salt",
+    "signature",
+    "message_signature",
+    "world_age",
+    "time_of_day",
+    "PingConfiguration.id",
+    "PongConfiguration.id",
+    "PingPlay.id",
+    "PongPlay.id",
+    "PingRequestPlay.payload",
+    "PingResponse.payload",
+];
+
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    pub float_tolerance: f64,
+    /// Fields to leave out of the comparison. `name` ignores every field called `name` at any
+    /// depth, `PacketName.path` the field at `path` (and everything below it) in that packet.
+    /// Indices in paths are left out, `a.b` matches `a[3].b`.
+    pub ignored: Vec<String>,
+    /// How far ahead to look for a packet of the same type before a packet is reported as
+    /// removed or added
+    pub lookahead: usize,
+}
+
+impl DiffOptions {
+    pub fn new() -> Self {
+        DiffOptions {
+            float_tolerance: 0.,
+            ignored: Vec::new(),
+            lookahead: 32,
+        }
+    }
+
+    pub fn with_float_tolerance(mut self, float_tolerance: f64) -> Self {
+        self.float_tolerance = float_tolerance;
+        self
+    }
+
+    pub fn with_ignored(mut self, field: &str) -> Self {
+        self.ignored.push(field.to_owned());
+        self
+    }
+
+    /// Ignores `VOLATILE_FIELDS`
+    pub fn with_volatile_fields(mut self) -> Self {
+        self.ignored
+            .extend(VOLATILE_FIELDS.iter().map(|field| (*field).to_owned()));
+        self
+    }
+
+    pub fn with_lookahead(mut self, lookahead: usize) -> Self {
+        self.lookahead = lookahead;
+        self
+    }
+
+    fn is_ignored(&self, packet: &str, path: &str) -> bool {
+        let segments: Vec<&str> = path
+            .split('.')
+            .map(|segment| segment.split('[').next().unwrap_or(segment))
+            .collect();
+        self.ignored
+            .iter()
+            .any(|pattern| match pattern.split_once('.') {
+                Some((name, rest)) if name == packet => {
+                    let pattern: Vec<&str> = rest.split('.').collect();
+                    segments.starts_with(&pattern)
+                }
+                Some(_) => false,
+                None => segments.contains(&pattern.as_str()),
+            })
+    }
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A leaf of a flattened value
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    None,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    Uuid(UUID),
+    Json(serde_json::Value),
+    Position { x: i32, y: i32, z: i32 },
+    Variant(&'static str),
+}
+
+impl FieldValue {
+    fn matches(&self, other: &FieldValue, float_tolerance: f64) -> bool {
+        match (self, other) {
+            (FieldValue::Float(a), FieldValue::Float(b)) => {
+                a == b || (a - b).abs() <= float_tolerance
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::None => f.write_str("None"),
+            FieldValue::Bool(val) => write!(f, "{}", val),
+            FieldValue::Int(val) => write!(f, "{}", val),
+            FieldValue::Float(val) => write!(f, "{}", val),
+            FieldValue::Str(val) => write!(f, "{:?}", val),
+            FieldValue::Bytes(val) if val.len() > 16 => {
+                write!(f, "<{} bytes: {}…>", val.len(), hex::encode(&val[..16]))
+            }
+            FieldValue::Bytes(val) => write!(f, "<{} bytes: {}>", val.len(), hex::encode(val)),
+            FieldValue::Uuid(val) => write!(f, "{}", val),
+            FieldValue::Json(val) => write!(f, "{}", val),
+            FieldValue::Position { x, y, z } => write!(f, "{},{},{}", x, y, z),
+            FieldValue::Variant(val) => f.write_str(val),
+        }
+    }
+}
+
+/// Flattens a value into `(path, leaf)` pairs, in field order
+pub fn flatten(value: &dyn Visit) -> Vec<(String, FieldValue)> {
+    let mut flattener = Flattener::default();
+    value.visit(&mut flattener);
+    flattener.fields
+}
+
+enum Frame {
+    Fields { prefix_len: usize },
+    Seq { prefix_len: usize, index: usize },
+}
+
+#[derive(Default)]
+struct Flattener {
+    path: String,
+    stack: Vec<Frame>,
+    fields: Vec<(String, FieldValue)>,
+}
+
+impl Flattener {
+    /// Moves the path to the next element when inside a sequence
+    fn enter(&mut self) {
+        if let Some(Frame::Seq { prefix_len, index }) = self.stack.last_mut() {
+            self.path.truncate(*prefix_len);
+            self.path.push_str(&format!("[{}]", index));
+            *index += 1;
+        }
+    }
+
+    fn push_segment(&mut self, name: &str) {
+        if !self.path.is_empty() {
+            self.path.push('.');
+        }
+        self.path.push_str(name);
+    }
+
+    fn leaf(&mut self, value: FieldValue) {
+        self.fields.push((self.path.clone(), value));
+    }
+
+    fn nbt(&mut self, tag: &Tag) {
+        match tag {
+            Tag::End => self.leaf(FieldValue::None),
+            Tag::Byte(val) => self.leaf(FieldValue::Int(*val as i128)),
+            Tag::Short(val) => self.leaf(FieldValue::Int(*val as i128)),
+            Tag::Int(val) => self.leaf(FieldValue::Int(*val as i128)),
+            Tag::Long(val) => self.leaf(FieldValue::Int(*val as i128)),
+            Tag::Float(val) => self.leaf(FieldValue::Float(*val as f64)),
+            Tag::Double(val) => self.leaf(FieldValue::Float(*val)),
+            Tag::ByteArray(val) => self.leaf(FieldValue::Bytes(val.clone())),
+            Tag::String(val) => self.leaf(FieldValue::Str(val.clone())),
+            Tag::List(val) => self.nbt_elements(val.iter(), |flattener, tag| flattener.nbt(tag)),
+            Tag::Compound(val) => {
+                let mut entries: Vec<_> = val.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                let prefix_len = self.path.len();
+                for (name, tag) in entries {
+                    self.path.truncate(prefix_len);
+                    self.push_segment(name);
+                    self.nbt(tag);
+                }
+                self.path.truncate(prefix_len);
+            }
+            Tag::IntArray(val) => self.nbt_elements(val.iter(), |flattener, val| {
+                flattener.leaf(FieldValue::Int(*val as i128))
+            }),
+            Tag::LongArray(val) => self.nbt_elements(val.iter(), |flattener, val| {
+                flattener.leaf(FieldValue::Int(*val as i128))
+            }),
+        }
+    }
+
+    fn nbt_elements<T>(
+        &mut self,
+        elements: impl Iterator<Item = T>,
+        mut element: impl FnMut(&mut Self, T),
+    ) {
+        let prefix_len = self.path.len();
+        for (i, val) in elements.enumerate() {
+            self.path.truncate(prefix_len);
+            self.path.push_str(&format!("[{}]", i));
+            element(self, val);
+        }
+        self.path.truncate(prefix_len);
+    }
+}
+
+impl Visitor for Flattener {
+    fn value(&mut self, value: Value<'_>) {
+        self.enter();
+        let value = match value {
+            Value::None => FieldValue::None,
+            Value::Bool(val) => FieldValue::Bool(val),
+            Value::I64(val) => FieldValue::Int(val as i128),
+            Value::U64(val) => FieldValue::Int(val as i128),
+            Value::F32(val) => FieldValue::Float(val as f64),
+            Value::F64(val) => FieldValue::Float(val),
+            Value::Str(val) => FieldValue::Str(val.to_owned()),
+            Value::Bytes(val) => FieldValue::Bytes(val.to_vec()),
+            Value::Uuid(val) => FieldValue::Uuid(val),
+            Value::Json(val) => FieldValue::Json(val.clone()),
+            Value::Position { x, y, z } => FieldValue::Position { x, y, z },
+            Value::Angle(degrees) => FieldValue::Float(degrees as f64),
+            Value::Nbt(tag) => return self.nbt(tag),
+        };
+        self.leaf(value);
+    }
+
+    fn begin_struct(&mut self, _name: &'static str) {
+        self.enter();
+        self.stack.push(Frame::Fields {
+            prefix_len: self.path.len(),
+        });
+    }
+
+    fn begin_variant(&mut self, _name: &'static str, variant: &'static str) {
+        self.enter();
+        self.leaf(FieldValue::Variant(variant));
+        self.stack.push(Frame::Fields {
+            prefix_len: self.path.len(),
+        });
+    }
+
+    fn begin_seq(&mut self, _len: usize) {
+        self.enter();
+        self.stack.push(Frame::Seq {
+            prefix_len: self.path.len(),
+            index: 0,
+        });
+    }
+
+    fn field(&mut self, name: &str) {
+        if let Some(Frame::Fields { prefix_len }) = self.stack.last() {
+            self.path.truncate(*prefix_len);
+            self.push_segment(name);
+        }
+    }
+
+    fn end(&mut self) {
+        if let Some(Frame::Fields { prefix_len } | Frame::Seq { prefix_len, .. }) = self.stack.pop()
+        {
+            self.path.truncate(prefix_len);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldDiff {
+    Changed {
+        path: String,
+        left: FieldValue,
+        right: FieldValue,
+    },
+    /// Only the left side has the field
+    Removed { path: String, left: FieldValue },
+    /// Only the right side has the field
+    Added { path: String, right: FieldValue },
+}
+
+impl Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldDiff::Changed { path, left, right } => {
+                write!(f, "{}: {} -> {}", path, left, right)
+            }
+            FieldDiff::Removed { path, left } => write!(f, "{}: removed ({})", path, left),
+            FieldDiff::Added { path, right } => write!(f, "{}: added ({})", path, right),
+        }
+    }
+}
+
+/// Differences between the fields of two packets. Packets of different types are compared
+/// like any other values, their fields rarely line up.
+pub fn diff_packets(left: &Packet, right: &Packet, options: &DiffOptions) -> Vec<FieldDiff> {
+    let packet = left.name();
+    let left = flatten(left);
+    let right = flatten(right);
+    let mut right_fields: HashMap<&str, &FieldValue> = right
+        .iter()
+        .map(|(path, value)| (path.as_str(), value))
+        .collect();
+
+    let mut diffs = Vec::new();
+    for (path, left) in &left {
+        let right = right_fields.remove(path.as_str());
+        if options.is_ignored(packet, path) {
+            continue;
+        }
+        match right {
+            Some(right) if left.matches(right, options.float_tolerance) => {}
+            Some(right) => diffs.push(FieldDiff::Changed {
+                path: path.clone(),
+                left: left.clone(),
+                right: right.clone(),
+            }),
+            None => diffs.push(FieldDiff::Removed {
+                path: path.clone(),
+                left: left.clone(),
+            }),
+        }
+    }
+    for (path, right) in &right {
+        if right_fields.contains_key(path.as_str()) && !options.is_ignored(packet, path) {
+            diffs.push(FieldDiff::Added {
+                path: path.clone(),
+                right: right.clone(),
+            });
+        }
+    }
+    diffs
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PacketDiff {
+    /// The packet at `index` on the left has no counterpart on the right
+    Removed { index: usize, name: &'static str },
+    /// The packet at `index` on the right has no counterpart on the left
+    Added { index: usize, name: &'static str },
+    /// Packets of the same type with different fields
+    Changed {
+        left: usize,
+        right: usize,
+        name: &'static str,
+        fields: Vec<FieldDiff>,
+    },
+}
+
+impl Display for PacketDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacketDiff::Removed { index, name } => write!(f, "- #{} {}", index, name),
+            PacketDiff::Added { index, name } => write!(f, "+ #{} {}", index, name),
+            PacketDiff::Changed {
+                left,
+                right,
+                name,
+                fields,
+            } => {
+                write!(f, "~ #{}/#{} {}", left, right, name)?;
+                for field in fields {
+                    write!(f, "\n    {}", field)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Aligns two streams by packet type and compares the packets paired up. Packets that are
+/// equal (apart from ignored fields) aren't reported.
+pub fn diff_streams(left: &[Packet], right: &[Packet], options: &DiffOptions) -> Vec<PacketDiff> {
+    let mut diffs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        let (l, r) = (&left[i], &right[j]);
+        if l.name() == r.name() {
+            let fields = diff_packets(l, r, options);
+            if !fields.is_empty() {
+                diffs.push(PacketDiff::Changed {
+                    left: i,
+                    right: j,
+                    name: l.name(),
+                    fields,
+                });
+            }
+            i += 1;
+            j += 1;
+            continue;
+        }
+        // whichever side gets back in step sooner has the extra packets
+        let find = |packets: &[Packet], start: usize, name: &str| {
+            packets
+                .iter()
+                .skip(start)
+                .take(options.lookahead)
+                .position(|packet| packet.name() == name)
+        };
+        match (find(right, j + 1, l.name()), find(left, i + 1, r.name())) {
+            (Some(added), removed) if removed.is_none_or(|removed| added <= removed) => {
+                diffs.extend((j..=j + added).map(|index| PacketDiff::Added {
+                    index,
+                    name: right[index].name(),
+                }));
+                j += added + 1;
+            }
+            (_, Some(removed)) => {
+                diffs.extend((i..=i + removed).map(|index| PacketDiff::Removed {
+                    index,
+                    name: left[index].name(),
+                }));
+                i += removed + 1;
+            }
+            _ => {
+                diffs.push(PacketDiff::Removed {
+                    index: i,
+                    name: l.name(),
+                });
+                diffs.push(PacketDiff::Added {
+                    index: j,
+                    name: r.name(),
+                });
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    diffs.extend((i..left.len()).map(|index| PacketDiff::Removed {
+        index,
+        name: left[index].name(),
+    }));
+    diffs.extend((j..right.len()).map(|index| PacketDiff::Added {
+        index,
+        name: right[index].name(),
+    }));
+    diffs
+}
+
+/// Differences between two captures, each direction compared on its own
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureDiff {
+    /// Indices count the decoded packets of one direction
+    pub serverbound: Vec<PacketDiff>,
+    pub clientbound: Vec<PacketDiff>,
+}
+
+impl CaptureDiff {
+    pub fn is_empty(&self) -> bool {
+        self.serverbound.is_empty() && self.clientbound.is_empty()
+    }
+}
+
+/// Decodes both captures and diffs them direction by direction. Packets that fail to decode
+/// are left out.
+pub fn diff_captures(
+    left: &Capture,
+    right: &Capture,
+    options: &DiffOptions,
+) -> Result<CaptureDiff, CaptureError> {
+    let split = |capture: &Capture| -> Result<(Vec<Packet>, Vec<Packet>), CaptureError> {
+        let mut serverbound = Vec::new();
+        let mut clientbound = Vec::new();
+        for packet in capture.packets()? {
+            let packet = packet?;
+            let Ok(decoded) = packet.packet else {
+                continue;
+            };
+            match packet.direction {
+                Direction::Serverbound => serverbound.push(decoded),
+                Direction::Clientbound => clientbound.push(decoded),
+            }
+        }
+        Ok((serverbound, clientbound))
+    };
+    let (left_serverbound, left_clientbound) = split(left)?;
+    let (right_serverbound, right_clientbound) = split(right)?;
+    Ok(CaptureDiff {
+        serverbound: diff_streams(&left_serverbound, &right_serverbound, options),
+        clientbound: diff_streams(&left_clientbound, &right_clientbound, options),
+    })
+}
@@ -0,0 +1,262 @@
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use crate::{
+    UUID, VarInt,
+    packet::{
+        Packet, PlayerAction, PlayerActions, PlayersActionsData, State,
+        c2s::{configuration::ServerboundKeepAliveConfiguration, play::ServerboundKeepAlivePlay},
+        s2c::{
+            configuration::ClientboundKeepAliveConfiguration,
+            play::{ClientboundKeepAlivePlay, PlayerInfoUpdate},
+        },
+    },
+};
+
+/// How often vanilla servers send a keep alive
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(15);
+/// How long vanilla servers wait for the answer before disconnecting the client
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+/// How long vanilla clients wait for a packet before giving up on the server
+pub const DEFAULT_CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Error, Debug)]
+pub enum KeepAliveError {
+    #[error("peer did not answer the keep alive in {0:?}")]
+    TimedOut(Duration),
+    #[error("unexpected keep alive id {received}, expected {expected:?}")]
+    UnexpectedId {
+        expected: Option<i64>,
+        received: i64,
+    },
+}
+
+/// Source of time for the keep alive logic, replace it to drive the timers manually in tests
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Server side keep alive: sends IDs on a schedule, validates the echoes, measures latency
+/// and detects dead clients. Works in both the configuration and the play state.
+///
+/// ```ignore
+/// // every tick
+/// if let Some(packet) = keep_alive.poll(state)? {
+///     send(packet);
+/// }
+/// // for every received packet
+/// if let Some(latency) = keep_alive.handle(&packet) {
+///     latency?;
+/// }
+/// ```
+pub struct KeepAliveServer<C: Clock = SystemClock> {
+    clock: C,
+    interval: Duration,
+    timeout: Duration,
+    next_id: i64,
+    last_sent: Option<Instant>,
+    pending: Option<(i64, Instant)>,
+    latency: Option<Duration>,
+}
+
+impl KeepAliveServer<SystemClock> {
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl Default for KeepAliveServer<SystemClock> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> KeepAliveServer<C> {
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            clock,
+            interval: DEFAULT_INTERVAL,
+            timeout: DEFAULT_TIMEOUT,
+            next_id: 0,
+            last_sent: None,
+            pending: None,
+            latency: None,
+        }
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Call this regularly (every tick). Returns the keep alive packet for `state` when one is due,
+    /// and an error when the previous one wasn't answered in time.
+    ///
+    /// Only `State::Configuration` and `State::Play` have keep alives, other states never send any.
+    pub fn poll(&mut self, state: State) -> Result<Option<Packet>, KeepAliveError> {
+        let now = self.clock.now();
+
+        if let Some((_, sent_at)) = self.pending {
+            let elapsed = now.duration_since(sent_at);
+            if elapsed >= self.timeout {
+                return Err(KeepAliveError::TimedOut(elapsed));
+            }
+            return Ok(None);
+        }
+
+        if self
+            .last_sent
+            .is_some_and(|last| now.duration_since(last) < self.interval)
+        {
+            return Ok(None);
+        }
+
+        let keep_alive_id = self.next_id;
+        let packet = match state {
+            State::Configuration => {
+                Packet::ClientboundKeepAliveConfiguration(ClientboundKeepAliveConfiguration {
+                    keep_alive_id,
+                })
+            }
+            State::Play => {
+                Packet::ClientboundKeepAlivePlay(ClientboundKeepAlivePlay { keep_alive_id })
+            }
+            _ => return Ok(None),
+        };
+
+        self.next_id = self.next_id.wrapping_add(1);
+        self.last_sent = Some(now);
+        self.pending = Some((keep_alive_id, now));
+        Ok(Some(packet))
+    }
+
+    /// Validates a keep alive answer and returns the round trip time
+    pub fn handle_response(&mut self, keep_alive_id: i64) -> Result<Duration, KeepAliveError> {
+        match self.pending {
+            Some((expected, sent_at)) if expected == keep_alive_id => {
+                let rtt = self.clock.now().duration_since(sent_at);
+                self.pending = None;
+                // same smoothing as vanilla
+                self.latency = Some(match self.latency {
+                    Some(latency) => (latency * 3 + rtt) / 4,
+                    None => rtt,
+                });
+                Ok(rtt)
+            }
+            pending => Err(KeepAliveError::UnexpectedId {
+                expected: pending.map(|(id, _)| id),
+                received: keep_alive_id,
+            }),
+        }
+    }
+
+    /// `handle_response` for any received packet, returns `None` for packets that aren't keep alives
+    pub fn handle(&mut self, packet: &Packet) -> Option<Result<Duration, KeepAliveError>> {
+        match packet {
+            Packet::ServerboundKeepAliveConfiguration(p) => {
+                Some(self.handle_response(p.keep_alive_id))
+            }
+            Packet::ServerboundKeepAlivePlay(p) => Some(self.handle_response(p.keep_alive_id)),
+            _ => None,
+        }
+    }
+
+    /// Smoothed latency, the value vanilla shows in the tab list
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// `PlayerInfoUpdate` that sets the tab list ping of `uuid` to the current latency
+    pub fn latency_update(&self, uuid: UUID) -> Option<Packet> {
+        let ping = self.latency?.as_millis().min(i32::MAX as u128) as i32;
+        Some(Packet::PlayerInfoUpdate(PlayerInfoUpdate {
+            actions: PlayersActionsData {
+                players_actions: vec![PlayerActions {
+                    uuid,
+                    player_actions: vec![PlayerAction::UpdateLatency { ping: VarInt(ping) }],
+                }],
+            },
+        }))
+    }
+}
+
+/// Client side keep alive: echoes the server's keep alives and notices when the server
+/// went silent.
+pub struct KeepAliveClient<C: Clock = SystemClock> {
+    clock: C,
+    timeout: Duration,
+    last_received: Instant,
+}
+
+impl KeepAliveClient<SystemClock> {
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl Default for KeepAliveClient<SystemClock> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> KeepAliveClient<C> {
+    pub fn with_clock(clock: C) -> Self {
+        let last_received = clock.now();
+        Self {
+            clock,
+            timeout: DEFAULT_CLIENT_TIMEOUT,
+            last_received,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Feed every received packet through this, returns the answer to send back for keep alives
+    pub fn handle(&mut self, packet: &Packet) -> Option<Packet> {
+        match packet {
+            Packet::ClientboundKeepAliveConfiguration(p) => {
+                self.last_received = self.clock.now();
+                Some(Packet::ServerboundKeepAliveConfiguration(
+                    ServerboundKeepAliveConfiguration {
+                        keep_alive_id: p.keep_alive_id,
+                    },
+                ))
+            }
+            Packet::ClientboundKeepAlivePlay(p) => {
+                self.last_received = self.clock.now();
+                Some(Packet::ServerboundKeepAlivePlay(ServerboundKeepAlivePlay {
+                    keep_alive_id: p.keep_alive_id,
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    /// Errors when no keep alive arrived for longer than the timeout
+    pub fn poll(&self) -> Result<(), KeepAliveError> {
+        let elapsed = self.clock.now().duration_since(self.last_received);
+        if elapsed >= self.timeout {
+            return Err(KeepAliveError::TimedOut(elapsed));
+        }
+        Ok(())
+    }
+}
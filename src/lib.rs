@@ -1,7 +1,8 @@
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use core::fmt;
 use hex::FromHexError;
-use macros::Serializable;
+use macros::{Serializable, Visit};
+use md5::{Digest, Md5};
 use std::{
     fmt::Display,
     io::{self, Read},
@@ -11,20 +12,35 @@ use std::{
 use thiserror::Error;
 
 pub mod bitset;
+pub mod capture;
+pub mod chunk;
 pub mod connection;
+pub mod cookie;
+pub mod diff;
+pub mod keep_alive;
 pub mod nbt;
 pub mod packet;
 pub mod packet_decoder;
 pub mod packet_encoder;
+pub mod pretty;
+pub mod proxy;
+pub mod region;
+pub mod replay;
+pub mod roundtrip;
 pub mod slot;
+pub mod span;
+pub mod status;
+pub mod text;
+pub mod visit;
 
+pub const PROTOCOL_VERSION: i32 = 773;
 pub const MAX_PACKET_SIZE: u64 = 2097152;
 pub const MAX_PACKET_DATA_SIZE: usize = 8388608;
 
 pub type CompressionThreshold = usize;
 pub type CompressionLevel = usize;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RawPacket {
     pub id: i32,
     pub payload: Vec<u8>,
@@ -63,7 +79,7 @@ impl Serializable for bool {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct VarInt(pub i32);
 
 const SEGMENT_BITS: u8 = 0x7F;
@@ -73,7 +89,7 @@ impl Serializable for VarInt {
     fn write_to<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
         let mut value = self.0 as u32;
         loop {
-            if (value as u8 & !0x7F) == 0 {
+            if (value & !0x7F) == 0 {
                 buf.write_u8(value as u8)?;
                 return Ok(());
             }
@@ -123,7 +139,7 @@ impl TryFrom<usize> for VarInt {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct VarLong(i64);
 
 impl Serializable for VarLong {
@@ -199,8 +215,8 @@ impl Serializable for String {
         if !(0..=32767).contains(&len) {
             return Err(Error::SerializeError("Invalid string size".to_owned()));
         }
-        let mut bytes: Vec<u8> = Vec::new();
-        buf.take(len as u64).read_to_end(&mut bytes)?;
+        let mut bytes: Vec<u8> = vec![0; len];
+        buf.read_exact(&mut bytes)?;
         Ok(String::from_utf8(bytes)?)
     }
     fn write_to<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
@@ -251,7 +267,7 @@ pub struct LenPrefixedBytes<L: Lengthable> {
 }
 
 impl<L: Lengthable> LenPrefixedBytes<L> {
-    fn new(data: Vec<u8>) -> Self {
+    pub fn new(data: Vec<u8>) -> Self {
         LenPrefixedBytes {
             data,
             _phantom_l: PhantomData,
@@ -265,6 +281,33 @@ impl<L: Lengthable> fmt::Debug for LenPrefixedBytes<L> {
     }
 }
 
+// by hand, deriving would require `L: Clone` etc. for the `PhantomData`
+impl<L: Lengthable> Clone for LenPrefixedBytes<L> {
+    fn clone(&self) -> Self {
+        LenPrefixedBytes::new(self.data.clone())
+    }
+}
+
+impl<L: Lengthable> PartialEq for LenPrefixedBytes<L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl<L: Lengthable> Eq for LenPrefixedBytes<L> {}
+
+impl<L: Lengthable> std::hash::Hash for LenPrefixedBytes<L> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+
+impl<L: Lengthable> visit::Visit for LenPrefixedBytes<L> {
+    fn visit(&self, visitor: &mut dyn visit::Visitor) {
+        visitor.value(visit::Value::Bytes(&self.data));
+    }
+}
+
 impl<L: Lengthable> Serializable for LenPrefixedBytes<L> {
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
         let len = L::read_from(buf)?.into_len();
@@ -284,9 +327,103 @@ impl<L: Lengthable> Serializable for LenPrefixedBytes<L> {
     }
 }
 
-#[derive(Debug)]
+/// A 128 bit UUID, stored the same way java's `java.util.UUID` stores it (big endian, most
+/// significant bits first).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct UUID(u128);
 
+impl UUID {
+    pub const NIL: UUID = UUID(0);
+
+    pub const fn from_u128(value: u128) -> Self {
+        UUID(value)
+    }
+
+    pub const fn as_u128(&self) -> u128 {
+        self.0
+    }
+
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        UUID(u128::from_be_bytes(bytes))
+    }
+
+    pub const fn to_bytes(&self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+
+    /// Equivalent of java's `UUID.nameUUIDFromBytes` (version 3, MD5 based)
+    pub fn name_uuid_from_bytes(name: &[u8]) -> Self {
+        let mut hash: [u8; 16] = Md5::digest(name).into();
+        hash[6] = (hash[6] & 0x0f) | 0x30;
+        hash[8] = (hash[8] & 0x3f) | 0x80;
+        UUID::from_bytes(hash)
+    }
+
+    /// The UUID an offline mode server assigns to a player, `UUID.nameUUIDFromBytes("OfflinePlayer:" + name)`
+    pub fn offline_player(name: &str) -> Self {
+        Self::name_uuid_from_bytes(format!("OfflinePlayer:{}", name).as_bytes())
+    }
+
+    /// The 4 ints NBT uses to store a UUID (`IntArray`), most significant first
+    pub fn from_int_array(ints: [i32; 4]) -> Self {
+        UUID(
+            ints.iter()
+                .fold(0u128, |acc, &int| (acc << 32) | int as u32 as u128),
+        )
+    }
+
+    pub fn to_int_array(&self) -> [i32; 4] {
+        [
+            (self.0 >> 96) as i32,
+            (self.0 >> 64) as i32,
+            (self.0 >> 32) as i32,
+            self.0 as i32,
+        ]
+    }
+
+    /// Reads a UUID stored as an NBT `IntArray` of length 4
+    pub fn from_nbt(tag: &nbt::Tag) -> Option<Self> {
+        let ints: [i32; 4] = tag.as_int_array()?.try_into().ok()?;
+        Some(Self::from_int_array(ints))
+    }
+
+    /// Parses the undashed form used by the Mojang API (`069a79f444e94726a5befca90e38aaf5`)
+    pub fn parse_simple(s: &str) -> Result<Self, UUIDParseError> {
+        if s.len() != 32 {
+            return Err(UUIDParseError);
+        }
+        let bytes: [u8; 16] = hex::decode(s)?.try_into().map_err(|_| UUIDParseError)?;
+        Ok(UUID::from_bytes(bytes))
+    }
+
+    /// The undashed form used by the Mojang API
+    pub fn to_simple_string(&self) -> String {
+        hex::encode(self.to_bytes())
+    }
+
+    /// 1 = time based, 2 = DCE security, 3 = name based (MD5), 4 = random, 5 = name based (SHA-1)
+    pub fn version(&self) -> u8 {
+        ((self.0 >> 76) & 0x0f) as u8
+    }
+
+    /// Same as java's `UUID.variant()`:
+    /// 0 = NCS backwards compatibility, 2 = IETF RFC 4122, 6 = Microsoft, 7 = reserved
+    pub fn variant(&self) -> u8 {
+        let bits = (self.0 >> 61) as u8 & 0b111;
+        match bits {
+            0b000..=0b011 => 0,
+            0b100 | 0b101 => 2,
+            other => other,
+        }
+    }
+}
+
+impl From<UUID> for nbt::Tag {
+    fn from(value: UUID) -> Self {
+        nbt::Tag::IntArray(value.to_int_array().to_vec())
+    }
+}
+
 impl Serializable for UUID {
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
         Ok(UUID(buf.read_u128::<BigEndian>()?))
@@ -298,63 +435,78 @@ impl Serializable for UUID {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct UUIDParseError;
 
 impl From<FromHexError> for UUIDParseError {
-    fn from(value: FromHexError) -> Self {
+    fn from(_: FromHexError) -> Self {
         UUIDParseError
     }
 }
 
+/// Accepts both the dashed (`069a79f4-44e9-4726-a5be-fca90e38aaf5`) and the undashed form
 impl std::str::FromStr for UUID {
     type Err = UUIDParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() != 36 {
-            return Err(UUIDParseError);
+        if s.len() == 32 {
+            return UUID::parse_simple(s);
         }
-        let mut parts = hex::decode(&s[..8])?;
-        parts.extend_from_slice(&hex::decode(&s[9..13])?);
-        parts.extend_from_slice(&hex::decode(&s[14..18])?);
-        parts.extend_from_slice(&hex::decode(&s[19..23])?);
-        parts.extend_from_slice(&hex::decode(&s[24..36])?);
-        let mut value = 0u128;
-        for i in 0..16 {
-            value |= (parts[i] as u128) << (120 - i * 8);
+        if s.len() != 36 || [8, 13, 18, 23].iter().any(|&i| s.as_bytes()[i] != b'-') {
+            return Err(UUIDParseError);
         }
-        Ok(UUID(value))
+        UUID::parse_simple(&s.replace('-', ""))
     }
 }
 
 impl Display for UUID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut val = hex::encode(self.0.to_be_bytes());
-        val.insert(9, '-');
-        val.insert(14, '-');
-        val.insert(19, '-');
-        val.insert(24, '-');
+        let mut val = self.to_simple_string();
+        val.insert(8, '-');
+        val.insert(13, '-');
+        val.insert(18, '-');
+        val.insert(23, '-');
         write!(f, "{}", val)
     }
 }
 
-#[derive(Debug)]
+impl serde::Serialize for UUID {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for UUID {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| serde::de::Error::custom(format!("invalid uuid: {}", s)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PrefixedArray<V: Serializable> {
     pub data: Vec<V>,
 }
 
 impl<V: Serializable> PrefixedArray<V> {
-    fn new(data: Vec<V>) -> Self {
+    pub fn new(data: Vec<V>) -> Self {
         PrefixedArray { data }
     }
 }
 
-impl<V: Serializable> Serializable for PrefixedArray<V> {
+impl<V: Serializable + visit::Visit> visit::Visit for PrefixedArray<V> {
+    fn visit(&self, visitor: &mut dyn visit::Visitor) {
+        V::visit_slice(&self.data, visitor);
+    }
+}
+
+impl<V: Serializable + fmt::Debug> Serializable for PrefixedArray<V> {
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
-        let len = VarInt::read_from(buf)?.into_len();
+        let len = span::field("length", || VarInt::read_from(buf))?.into_len();
 
         let mut data: Vec<V> = Vec::with_capacity(len);
-        for _ in 0..len {
-            data.push(Serializable::read_from(buf)?);
+        for i in 0..len {
+            data.push(span::element(i, || V::read_from(buf))?);
         }
 
         Ok(PrefixedArray { data })
@@ -370,10 +522,10 @@ impl<V: Serializable> Serializable for PrefixedArray<V> {
     }
 }
 
-impl<T: Serializable> Serializable for Option<T> {
+impl<T: Serializable + fmt::Debug> Serializable for Option<T> {
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
-        if bool::read_from(buf)? {
-            Ok(Some(Serializable::read_from(buf)?))
+        if span::field("present", || bool::read_from(buf))? {
+            Ok(Some(span::field("value", || T::read_from(buf))?))
         } else {
             Ok(None)
         }
@@ -402,7 +554,7 @@ impl Serializable for Vec<u8> {
     }
 }
 
-type JsonTextComponent = serde_json::Value;
+use text::JsonTextComponent;
 type TextComponent = nbt::Tag;
 
 impl Serializable for i32 {
@@ -464,30 +616,83 @@ impl Serializable for f64 {
     }
 }
 
+/// A rotation in steps of 1/256 of a full turn.
 /// Use `Angle::to_radians()` to use the angle, its raw value is not accessible
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Angle(i8);
 
 impl Angle {
-    fn from_radians(rad: f32) -> Self {
-        let val = rad * (256. / 360.);
-        Angle(val as i8)
+    pub fn from_radians(rad: f32) -> Self {
+        Self::from_steps(rad * (256. / std::f32::consts::TAU))
+    }
+    pub fn to_radians(&self) -> f32 {
+        self.0 as f32 * (std::f32::consts::TAU / 256.)
     }
-    fn to_radians(&self) -> f32 {
+    pub fn from_degrees(deg: f32) -> Self {
+        Self::from_steps(deg * (256. / 360.))
+    }
+    /// Between -180 and 180
+    pub fn to_degrees(&self) -> f32 {
         self.0 as f32 * (360. / 256.)
     }
+    fn from_steps(steps: f32) -> Self {
+        // wraps around instead of saturating, 200° is -160°
+        Angle(steps.round() as i32 as i8)
+    }
 }
 
-#[derive(Debug)]
+impl Serializable for Angle {
+    fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
+        Ok(Angle(i8::read_from(buf)?))
+    }
+    fn write_to<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
+        self.0.write_to(buf)
+    }
+}
+
+impl visit::Visit for Angle {
+    fn visit(&self, visitor: &mut dyn visit::Visitor) {
+        visitor.value(visit::Value::Angle(self.to_degrees()));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Position {
     x: i32,
     y: i32,
     z: i32,
 }
 
+impl Position {
+    /// x and z are 26 bit, y 12 bit signed integers on the wire, other bits are dropped
+    pub fn new(x: i32, y: i32, z: i32) -> Self {
+        Position { x, y, z }
+    }
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+    pub fn z(&self) -> i32 {
+        self.z
+    }
+}
+
+impl visit::Visit for Position {
+    fn visit(&self, visitor: &mut dyn visit::Visitor) {
+        visitor.value(visit::Value::Position {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+        });
+    }
+}
+
 impl Serializable for Position {
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
-        let val = buf.read_u64::<BigEndian>()?;
+        // shifting the signed value extends the sign of each coordinate
+        let val = buf.read_i64::<BigEndian>()?;
         let x: i32 = (val >> 38) as i32;
         let y: i32 = ((val << 52) >> 52) as i32;
         let z: i32 = ((val << 26) >> 38) as i32;
@@ -523,14 +728,14 @@ impl Serializable for () {
     }
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct Vec3<T: Serializable> {
     x: T,
     y: T,
     z: T,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct Vec4<T: Serializable> {
     x: T,
     y: T,
@@ -538,7 +743,7 @@ pub struct Vec4<T: Serializable> {
     w: T,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
 pub enum IdSet {
     ByTag { tag_name: Identifier },
     IdArray(Vec<VarInt>),
@@ -577,17 +782,17 @@ impl Serializable for IdSet {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
 pub enum IdOrX<T: Serializable> {
     Id(VarInt),
     X(T),
 }
 
-impl<T: Serializable> Serializable for IdOrX<T> {
+impl<T: Serializable + fmt::Debug> Serializable for IdOrX<T> {
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
-        let id = VarInt::read_from(buf)?;
+        let id = span::field("id", || VarInt::read_from(buf))?;
         if id.0 == 0 {
-            Ok(IdOrX::X(T::read_from(buf)?))
+            Ok(IdOrX::X(span::field("value", || T::read_from(buf))?))
         } else {
             Ok(IdOrX::Id(VarInt(id.0 - 1)))
         }
@@ -624,11 +829,17 @@ impl Lengthable for i8 {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct StaticLenBytes<const L: usize> {
     data: Vec<u8>,
 }
 
+impl<const L: usize> visit::Visit for StaticLenBytes<L> {
+    fn visit(&self, visitor: &mut dyn visit::Visitor) {
+        visitor.value(visit::Value::Bytes(&self.data));
+    }
+}
+
 impl<const L: usize> Serializable for StaticLenBytes<L> {
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
         let mut data: Vec<u8> = Vec::with_capacity(L);
@@ -659,12 +870,17 @@ impl Serializable for u32 {
     }
 }
 
-impl<A: Serializable, B: Serializable, C: Serializable> Serializable for (A, B, C) {
+impl<A, B, C> Serializable for (A, B, C)
+where
+    A: Serializable + fmt::Debug,
+    B: Serializable + fmt::Debug,
+    C: Serializable + fmt::Debug,
+{
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
         Ok((
-            Serializable::read_from(buf)?,
-            Serializable::read_from(buf)?,
-            Serializable::read_from(buf)?,
+            span::field("0", || A::read_from(buf))?,
+            span::field("1", || B::read_from(buf))?,
+            span::field("2", || C::read_from(buf))?,
         ))
     }
     fn write_to<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
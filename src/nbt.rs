@@ -1,13 +1,54 @@
+//! NBT, the tag format of the network protocol and of the game's files.
+//!
+//! - network NBT (`Serializable`, `Tag::read_network`): type byte and payload, the root has no
+//!   name
+//! - named NBT (`Tag::read_named`): type byte, name and payload, the format of files
+//! - NBT files (`NbtFile`): named NBT, usually gzip compressed (`level.dat`, player data)
+//! - SNBT (`Tag::from_snbt`, `Display`): the text form of commands and data packs
+//! - NBT paths (`Path`): `Items[0].tag.display.Name`, to get and change parts of a tag
+//! - serde (`from_tag`, `to_tag`): Rust structs to and from tags
+//! - JSON (`Tag::to_json`, `Tag::to_typed_json`): vanilla's lossy mapping or one keeping all types
+
 use std::{
-    collections::HashMap,
-    io::{self, Read},
+    fmt::Display,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
 };
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+};
+use indexmap::IndexMap;
+
+use crate::{
+    Error, Serializable, span,
+    visit::{Value, Visit, Visitor},
+};
 
-use crate::{Error, Serializable};
+mod de;
+mod json;
+mod list;
+mod path;
+mod ser;
+mod snbt;
 
-#[derive(Debug)]
+pub use de::{from_reader, from_tag};
+pub use json::JsonError;
+pub use list::{List, TagError};
+pub use path::{Path, PathNode};
+pub use ser::{byte_array, int_array, long_array, to_tag, to_writer};
+pub use snbt::SnbtError;
+
+/// Most compounds and lists nested in each other, the same limit as vanilla's parser. Deeper
+/// input is an error instead of overflowing the stack.
+const MAX_DEPTH: usize = 512;
+/// Elements allocated up front for an array, longer ones grow as they are read rather than
+/// trusting a length from the input
+const MAX_PREALLOCATED: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Tag {
     End,
     Byte(i8),
@@ -18,19 +59,20 @@ pub enum Tag {
     Double(f64),
     ByteArray(Vec<u8>), // prefix i32
     String(String),     // prefix u16
-    List(Vec<Tag>),     // prefixed by type id (i8) and length i32 if empty list, type id can be END
-    Compound(HashMap<String, Tag>),
+    List(List),         // prefixed by type id (i8) and length i32 if empty list, type id can be END
+    /// Keeps the order entries were read or put in
+    Compound(IndexMap<String, Tag>),
     IntArray(Vec<i32>),
     LongArray(Vec<i64>),
 }
 
 impl Tag {
     pub fn new_compound() -> Tag {
-        Tag::Compound(HashMap::new())
+        Tag::Compound(IndexMap::new())
     }
 
     pub fn new_list() -> Tag {
-        Tag::List(Vec::new())
+        Tag::List(List::new())
     }
 
     pub fn get(&self, name: &str) -> Option<&Tag> {
@@ -40,7 +82,24 @@ impl Tag {
         }
     }
 
-    /// WILL panic if this is not a compound
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Tag> {
+        match self {
+            Tag::Compound(val) => val.get_mut(name),
+            _ => None,
+        }
+    }
+
+    /// The entry converted to `T`, `None` if missing or of another type
+    pub fn get_as<'a, T: TryFrom<&'a Tag>>(&'a self, name: &str) -> Option<T> {
+        self.get(name).and_then(|tag| T::try_from(tag).ok())
+    }
+
+    /// The entry converted to `T`, `default` if missing or of another type
+    pub fn get_or<'a, T: TryFrom<&'a Tag>>(&'a self, name: &str, default: T) -> T {
+        self.get_as(name).unwrap_or(default)
+    }
+
+    /// WILL panic if this is not a compound, see `insert`. Replacing an entry keeps its position.
     pub fn put(&mut self, name: &str, tag: Tag) {
         match *self {
             Tag::Compound(ref mut val) => val.insert(name.to_owned(), tag),
@@ -48,6 +107,69 @@ impl Tag {
         };
     }
 
+    /// Puts an entry into a compound and returns the one it replaced, which keeps its position
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        tag: impl Into<Tag>,
+    ) -> Result<Option<Tag>, TagError> {
+        match self {
+            Tag::Compound(val) => Ok(val.insert(name.into(), tag.into())),
+            _ => Err(TagError::NotACompound(self.type_name())),
+        }
+    }
+
+    /// Removes an entry of a compound, the entries after it move up
+    pub fn remove(&mut self, name: &str) -> Option<Tag> {
+        match self {
+            Tag::Compound(val) => val.shift_remove(name),
+            _ => None,
+        }
+    }
+
+    /// Merges the entries of the compound `other` into this compound, nested compounds entry by
+    /// entry, like `/data merge`
+    pub fn merge(&mut self, other: &Tag) -> Result<(), TagError> {
+        let Tag::Compound(other) = other else {
+            return Err(TagError::NotACompound(other.type_name()));
+        };
+        let Tag::Compound(val) = self else {
+            return Err(TagError::NotACompound(self.type_name()));
+        };
+        for (key, tag) in other {
+            match val.get_mut(key) {
+                Some(existing) if existing.is_compound() && tag.is_compound() => {
+                    existing.merge(tag)?
+                }
+                _ => {
+                    val.insert(key.clone(), tag.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends to a list, which must be empty or hold tags of the same type
+    pub fn push(&mut self, tag: impl Into<Tag>) -> Result<(), TagError> {
+        match self {
+            Tag::List(list) => list.push(tag),
+            _ => Err(TagError::NotAList(self.type_name())),
+        }
+    }
+
+    /// Sorts the entries of this and all nested compounds by key, the canonical order for
+    /// comparing or hashing encoded NBT
+    pub fn sort_keys(&mut self) {
+        match self {
+            Tag::Compound(val) => {
+                val.sort_unstable_keys();
+                val.values_mut().for_each(Tag::sort_keys);
+            }
+            Tag::List(val) => val.iter_mut().for_each(Tag::sort_keys),
+            _ => {}
+        }
+    }
+
     pub fn is_compound(&self) -> bool {
         matches!(*self, Tag::Compound(_))
     }
@@ -115,7 +237,14 @@ impl Tag {
         }
     }
 
-    pub fn as_compound(&self) -> Option<&HashMap<String, Tag>> {
+    pub fn as_list_mut(&mut self) -> Option<&mut List> {
+        match self {
+            Tag::List(val) => Some(val),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&IndexMap<String, Tag>> {
         match *self {
             Tag::Compound(ref val) => Some(val),
             _ => None,
@@ -136,6 +265,25 @@ impl Tag {
         }
     }
 
+    /// The name of the tag type, as in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Tag::End => "End",
+            Tag::Byte(_) => "Byte",
+            Tag::Short(_) => "Short",
+            Tag::Int(_) => "Int",
+            Tag::Long(_) => "Long",
+            Tag::Float(_) => "Float",
+            Tag::Double(_) => "Double",
+            Tag::ByteArray(_) => "ByteArray",
+            Tag::String(_) => "String",
+            Tag::List(_) => "List",
+            Tag::Compound(_) => "Compound",
+            Tag::IntArray(_) => "IntArray",
+            Tag::LongArray(_) => "LongArray",
+        }
+    }
+
     fn internal_id(&self) -> i8 {
         match self {
             Tag::End => 0,
@@ -154,72 +302,8 @@ impl Tag {
         }
     }
 
-    fn read_type<R: io::Read>(id: u8, buf: &mut R) -> Result<Tag, Error> {
-        // println!("readtype {}", id);
-        match id {
-            0 => Ok(Tag::End),
-            1 => Ok(Tag::Byte(buf.read_i8()?)),
-            2 => Ok(Tag::Short(buf.read_i16::<BigEndian>()?)),
-            3 => Ok(Tag::Int(buf.read_i32::<BigEndian>()?)),
-            4 => Ok(Tag::Long(buf.read_i64::<BigEndian>()?)),
-            5 => Ok(Tag::Float(buf.read_f32::<BigEndian>()?)),
-            6 => Ok(Tag::Double(buf.read_f64::<BigEndian>()?)),
-            7 => Ok(Tag::ByteArray({
-                let len: i32 = Serializable::read_from(buf)?;
-                let mut data = Vec::with_capacity(len as usize);
-                buf.take(len as u64).read_to_end(&mut data)?;
-                data
-            })),
-            8 => Ok(Tag::String(read_string(buf)?)),
-            9 => {
-                let mut l = Vec::new();
-                let ty = buf.read_u8()?;
-                let len: i32 = Serializable::read_from(buf)?;
-                for _ in 0..len {
-                    l.push(Tag::read_type(ty, buf)?);
-                }
-                Ok(Tag::List(l))
-            }
-            10 => {
-                let mut c = Tag::new_compound();
-                loop {
-                    let ty = buf.read_u8()?;
-                    if ty == 0 {
-                        break;
-                    }
-                    let name: String = read_string(buf)?;
-                    c.put(&name[..], Tag::read_type(ty, buf)?);
-                }
-                Ok(c)
-            }
-            11 => Ok(Tag::IntArray({
-                let len: i32 = Serializable::read_from(buf)?;
-                let mut data = Vec::with_capacity(len as usize);
-                for _ in 0..len {
-                    data.push(buf.read_i32::<BigEndian>()?);
-                }
-                data
-            })),
-            12 => Ok(Tag::LongArray({
-                let len: i32 = Serializable::read_from(buf)?;
-                let mut data = Vec::with_capacity(len as usize);
-                for _ in 0..len {
-                    data.push(buf.read_i64::<BigEndian>()?);
-                }
-                data
-            })),
-            _ => Err(Error::SerializeError("invalid tag".to_owned())),
-        }
-    }
-}
-
-impl Serializable for Tag {
-    fn read_from<R: io::Read>(buf: &mut R) -> Result<Tag, Error> {
-        let ty = buf.read_u8()?;
-        Tag::read_type(ty, buf)
-    }
-
-    fn write_to<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
+    /// Writes the value without its type byte, the counterpart of `read_type`
+    fn write_type<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
         match *self {
             Tag::End => {}
             Tag::Byte(val) => buf.write_i8(val)?,
@@ -234,6 +318,9 @@ impl Serializable for Tag {
             }
             Tag::String(ref val) => write_string(buf, val)?,
             Tag::List(ref val) => {
+                if let Some(err) = val.mismatch() {
+                    return Err(Error::SerializeError(err.to_string()));
+                }
                 if val.is_empty() {
                     buf.write_i8(0)?;
                     buf.write_i32::<BigEndian>(0)?;
@@ -241,7 +328,7 @@ impl Serializable for Tag {
                     buf.write_i8(val[0].internal_id())?;
                     buf.write_i32::<BigEndian>(val.len() as i32)?;
                     for e in val {
-                        e.write_to(buf)?;
+                        e.write_type(buf)?;
                     }
                 }
             }
@@ -249,7 +336,7 @@ impl Serializable for Tag {
                 for (k, v) in val {
                     v.internal_id().write_to(buf)?;
                     write_string(buf, k)?;
-                    v.write_to(buf)?;
+                    v.write_type(buf)?;
                 }
                 buf.write_u8(0)?;
             }
@@ -268,18 +355,358 @@ impl Serializable for Tag {
         }
         Result::Ok(())
     }
+
+    /// `depth` is the number of compounds and lists around this tag
+    fn read_type<R: io::Read>(id: u8, buf: &mut R, depth: usize) -> Result<Tag, Error> {
+        // println!("readtype {}", id);
+        match id {
+            0 => Ok(Tag::End),
+            1 => Ok(Tag::Byte(buf.read_i8()?)),
+            2 => Ok(Tag::Short(buf.read_i16::<BigEndian>()?)),
+            3 => Ok(Tag::Int(buf.read_i32::<BigEndian>()?)),
+            4 => Ok(Tag::Long(buf.read_i64::<BigEndian>()?)),
+            5 => Ok(Tag::Float(buf.read_f32::<BigEndian>()?)),
+            6 => Ok(Tag::Double(buf.read_f64::<BigEndian>()?)),
+            7 => Ok(Tag::ByteArray({
+                let len = read_array_len(buf)?;
+                let mut data = Vec::with_capacity(len.min(MAX_PREALLOCATED));
+                buf.take(len as u64).read_to_end(&mut data)?;
+                if data.len() != len {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+                data
+            })),
+            8 => Ok(Tag::String(read_string(buf)?)),
+            9 => Tag::read_list(buf, depth),
+            10 => Tag::read_compound(buf, depth),
+            11 => Ok(Tag::IntArray({
+                let len = read_array_len(buf)?;
+                let mut data = Vec::with_capacity(len.min(MAX_PREALLOCATED));
+                for _ in 0..len {
+                    data.push(buf.read_i32::<BigEndian>()?);
+                }
+                data
+            })),
+            12 => Ok(Tag::LongArray({
+                let len = read_array_len(buf)?;
+                let mut data = Vec::with_capacity(len.min(MAX_PREALLOCATED));
+                for _ in 0..len {
+                    data.push(buf.read_i64::<BigEndian>()?);
+                }
+                data
+            })),
+            _ => Err(Error::SerializeError("invalid tag".to_owned())),
+        }
+    }
+
+    fn read_list<R: io::Read>(buf: &mut R, depth: usize) -> Result<Tag, Error> {
+        check_depth(depth)?;
+        let mut l = Vec::new();
+        let ty = span::field("type", || Ok(buf.read_u8()?))?;
+        let len: i32 = span::field("length", || i32::read_from(buf))?;
+        if ty == 0 && len > 0 {
+            return Err(Error::SerializeError(
+                "NBT list of End tags isn't empty".to_owned(),
+            ));
+        }
+        for i in 0..len.max(0) as usize {
+            l.push(span::element(i, || Tag::read_type(ty, buf, depth + 1))?);
+        }
+        Ok(Tag::List(List::from_vec_unchecked(l)))
+    }
+
+    fn read_compound<R: io::Read>(buf: &mut R, depth: usize) -> Result<Tag, Error> {
+        check_depth(depth)?;
+        let mut c = IndexMap::new();
+        loop {
+            let ty = span::field("type", || Ok(buf.read_u8()?))?;
+            if ty == 0 {
+                break;
+            }
+            let name: String = span::field("name", || read_string(buf))?;
+            let tag = span::entry(&name, || Tag::read_type(ty, buf, depth + 1))?;
+            c.insert(name, tag);
+        }
+        Ok(Tag::Compound(c))
+    }
+}
+
+impl Visit for Tag {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(Value::Nbt(self));
+    }
+}
+
+impl Serializable for Tag {
+    fn read_from<R: io::Read>(buf: &mut R) -> Result<Tag, Error> {
+        let ty = span::field("type", || Ok(buf.read_u8()?))?;
+        span::field("value", || Tag::read_type(ty, buf, 0))
+    }
+
+    fn write_to<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
+        self.internal_id().write_to(buf)?;
+        self.write_type(buf)
+    }
+}
+
+impl Tag {
+    /// Reads network NBT: a type byte and the payload, the root has no name
+    pub fn read_network<R: io::Read>(buf: &mut R) -> Result<Tag, Error> {
+        Tag::read_from(buf)
+    }
+
+    pub fn write_network<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
+        self.write_to(buf)
+    }
+
+    /// Network NBT with compound entries sorted by key, see `sort_keys`
+    pub fn write_sorted<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
+        let mut sorted = self.clone();
+        sorted.sort_keys();
+        sorted.write_to(buf)
+    }
+
+    /// Reads named NBT: a type byte, the root's name and the payload. An `End` root has no name.
+    pub fn read_named<R: io::Read>(buf: &mut R) -> Result<(String, Tag), Error> {
+        let ty = span::field("type", || Ok(buf.read_u8()?))?;
+        if ty == 0 {
+            return Ok((String::new(), Tag::End));
+        }
+        let name = span::field("name", || read_string(buf))?;
+        let tag = span::field("value", || Tag::read_type(ty, buf, 0))?;
+        Ok((name, tag))
+    }
+
+    pub fn write_named<W: io::Write>(&self, buf: &mut W, name: &str) -> Result<(), Error> {
+        self.internal_id().write_to(buf)?;
+        if let Tag::End = self {
+            return Ok(());
+        }
+        write_string(buf, name)?;
+        self.write_type(buf)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SerdeError {
+    #[error("{0}")]
+    Message(String),
+    #[error("{0}")]
+    Nbt(#[from] Error),
+}
+
+impl serde::ser::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError::Message(msg.to_string())
+    }
 }
 
+impl serde::de::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError::Message(msg.to_string())
+    }
+}
+
+/// How an NBT file is compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Compression {
+    /// `level.dat`, player data and most other files
+    #[default]
+    Gzip,
+    /// Chunks in region files
+    Zlib,
+    None,
+}
+
+/// A file holding one named NBT root, e.g. `level.dat`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NbtFile {
+    /// Usually empty
+    pub name: String,
+    pub root: Tag,
+    pub compression: Compression,
+}
+
+impl NbtFile {
+    /// Gzip compressed, with an empty root name
+    pub fn new(root: Tag) -> Self {
+        NbtFile {
+            name: String::new(),
+            root,
+            compression: Compression::Gzip,
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_owned();
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Error> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Detects the compression from the first bytes
+    pub fn read<R: io::Read>(mut reader: R) -> Result<Self, Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let compression = match data[..] {
+            [0x1f, 0x8b, ..] => Compression::Gzip,
+            // deflate with a 32K window, any level
+            [0x78, b, ..] if (0x7800 | b as u16).is_multiple_of(31) => Compression::Zlib,
+            _ => Compression::None,
+        };
+        let data = match compression {
+            Compression::Gzip => decompress(GzDecoder::new(&data[..]))?,
+            Compression::Zlib => decompress(ZlibDecoder::new(&data[..]))?,
+            Compression::None => data,
+        };
+
+        let mut buf = &data[..];
+        let (name, root) = Tag::read_named(&mut buf)?;
+        if !buf.is_empty() {
+            return Err(Error::SerializeError(format!(
+                "{} bytes after the NBT root",
+                buf.len()
+            )));
+        }
+        Ok(NbtFile {
+            name,
+            root,
+            compression,
+        })
+    }
+
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn write<W: io::Write>(&self, writer: W) -> Result<(), Error> {
+        let level = flate2::Compression::default();
+        match self.compression {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(writer, level);
+                self.root.write_named(&mut encoder, &self.name)?;
+                encoder.finish()?;
+            }
+            Compression::Zlib => {
+                let mut encoder = ZlibEncoder::new(writer, level);
+                self.root.write_named(&mut encoder, &self.name)?;
+                encoder.finish()?;
+            }
+            Compression::None => {
+                let mut writer = writer;
+                self.root.write_named(&mut writer, &self.name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn decompress<R: io::Read>(mut decoder: R) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    decoder.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Writes a string as Java's modified UTF-8, prefixed by its length in bytes (u16)
 pub fn write_string<W: io::Write>(buf: &mut W, s: &str) -> Result<(), Error> {
-    let data = s.as_bytes();
-    (data.len() as i16).write_to(buf)?;
-    buf.write_all(data).map_err(|v| v.into())
+    let data = encode_mutf8(s);
+    let len: u16 = data.len().try_into().map_err(|_| {
+        Error::SerializeError(format!("NBT string is too long: {} bytes", data.len()))
+    })?;
+    len.write_to(buf)?;
+    buf.write_all(&data)?;
+    Ok(())
 }
 
 pub fn read_string<R: io::Read>(buf: &mut R) -> Result<String, Error> {
-    let len: i16 = buf.read_i16::<BigEndian>()?;
-    let mut bytes = Vec::<u8>::new();
-    buf.take(len as u64).read_to_end(&mut bytes)?;
-    let ret = String::from_utf8(bytes).unwrap();
-    Result::Ok(ret)
+    let len = buf.read_u16::<BigEndian>()?;
+    let mut bytes = vec![0; len as usize];
+    buf.read_exact(&mut bytes)?;
+    decode_mutf8(&bytes)
+}
+
+fn check_depth(depth: usize) -> Result<(), Error> {
+    if depth >= MAX_DEPTH {
+        return Err(Error::SerializeError(format!(
+            "NBT nested deeper than {} levels",
+            MAX_DEPTH
+        )));
+    }
+    Ok(())
+}
+
+/// The length prefix of an array tag
+fn read_array_len<R: io::Read>(buf: &mut R) -> Result<usize, Error> {
+    let len = i32::read_from(buf)?;
+    usize::try_from(len)
+        .map_err(|_| Error::SerializeError(format!("negative NBT array length {}", len)))
+}
+
+/// Java's modified UTF-8: NUL is `0xC0 0x80` and characters outside the BMP are a surrogate
+/// pair of 3 byte sequences (CESU-8) instead of one 4 byte sequence
+pub fn encode_mutf8(s: &str) -> Vec<u8> {
+    if s.bytes().all(|b| b != 0 && b < 0x80) {
+        return s.as_bytes().to_vec();
+    }
+    let mut data = Vec::with_capacity(s.len() + 2);
+    for unit in s.encode_utf16() {
+        match unit {
+            0x01..=0x7f => data.push(unit as u8),
+            0x00 | 0x80..=0x7ff => {
+                data.push(0xc0 | (unit >> 6) as u8);
+                data.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                data.push(0xe0 | (unit >> 12) as u8);
+                data.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                data.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    data
+}
+
+pub fn decode_mutf8(data: &[u8]) -> Result<String, Error> {
+    if data.iter().all(|b| *b < 0x80) {
+        return Ok(String::from_utf8(data.to_vec())?);
+    }
+    let invalid = |i: usize| Error::SerializeError(format!("invalid modified UTF-8 at byte {}", i));
+    let mut units = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        let continuation = |j: usize| match data.get(j) {
+            Some(b) if b & 0xc0 == 0x80 => Ok((b & 0x3f) as u16),
+            _ => Err(invalid(i)),
+        };
+        let b = data[i];
+        match b {
+            0x00..=0x7f => {
+                units.push(b as u16);
+                i += 1;
+            }
+            0xc0..=0xdf => {
+                units.push(((b & 0x1f) as u16) << 6 | continuation(i + 1)?);
+                i += 2;
+            }
+            0xe0..=0xef => {
+                units.push(
+                    ((b & 0x0f) as u16) << 12 | continuation(i + 1)? << 6 | continuation(i + 2)?,
+                );
+                i += 3;
+            }
+            _ => return Err(invalid(i)),
+        }
+    }
+    String::from_utf16(&units)
+        .map_err(|_| Error::SerializeError("unpaired surrogate in NBT string".to_owned()))
 }
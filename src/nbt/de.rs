@@ -0,0 +1,264 @@
+//! `Deserialize` from a `Tag`, the counterpart of `ser`.
+//!
+//! Numbers convert to any Rust number type they fit in, bytes to `bool`, arrays to sequences
+//! (and `ByteArray` to bytes for `serde_bytes`), strings to unit variants and single entry
+//! compounds to other variants. Missing compound entries are `None`.
+
+use std::io;
+
+use indexmap::IndexMap;
+use serde::{
+    Deserialize,
+    de::{
+        self, DeserializeOwned, IntoDeserializer, Visitor,
+        value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer},
+    },
+    forward_to_deserialize_any,
+};
+
+use super::{
+    List, SerdeError, Tag,
+    ser::{BYTE_ARRAY, INT_ARRAY, LONG_ARRAY},
+};
+
+pub fn from_tag<T: DeserializeOwned>(tag: Tag) -> Result<T, SerdeError> {
+    T::deserialize(tag)
+}
+
+/// Reads network NBT into `T`
+pub fn from_reader<R: io::Read, T: DeserializeOwned>(reader: &mut R) -> Result<T, SerdeError> {
+    from_tag(Tag::read_network(reader)?)
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    /// Only for deserializing `Tag` fields of a struct read by `from_tag`, other formats can't
+    /// keep the NBT types apart
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(TAG, TagVisitor)
+    }
+}
+
+/// Hands the `Tag` itself to `Tag`'s `Deserialize`
+const TAG: &str = "__nbt_tag";
+
+struct TagVisitor;
+
+impl<'de> Visitor<'de> for TagVisitor {
+    type Value = Tag;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an NBT tag")
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Tag, D::Error> {
+        deserializer.deserialize_any(TagVisitor)
+    }
+
+    fn visit_i8<E: de::Error>(self, v: i8) -> Result<Tag, E> {
+        Ok(Tag::Byte(v))
+    }
+
+    fn visit_i16<E: de::Error>(self, v: i16) -> Result<Tag, E> {
+        Ok(Tag::Short(v))
+    }
+
+    fn visit_i32<E: de::Error>(self, v: i32) -> Result<Tag, E> {
+        Ok(Tag::Int(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Tag, E> {
+        Ok(Tag::Long(v))
+    }
+
+    fn visit_f32<E: de::Error>(self, v: f32) -> Result<Tag, E> {
+        Ok(Tag::Float(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Tag, E> {
+        Ok(Tag::Double(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Tag, E> {
+        Ok(Tag::String(v.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Tag, E> {
+        Ok(Tag::String(v))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Tag, A::Error> {
+        let mut list = List::new();
+        while let Some(tag) = seq.next_element::<Tag>()? {
+            list.push(tag).map_err(de::Error::custom)?;
+        }
+        Ok(Tag::List(list))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Tag, A::Error> {
+        let mut compound = IndexMap::new();
+        while let Some((key, tag)) = map.next_entry::<String, Tag>()? {
+            // an array from `deserialize_newtype_struct`
+            let elements = tag.as_list().unwrap_or_default().iter();
+            match key.as_str() {
+                BYTE_ARRAY => {
+                    return Ok(Tag::ByteArray(
+                        elements.filter_map(Tag::as_byte).map(|v| v as u8).collect(),
+                    ));
+                }
+                INT_ARRAY => return Ok(Tag::IntArray(elements.filter_map(Tag::as_int).collect())),
+                LONG_ARRAY => {
+                    return Ok(Tag::LongArray(elements.filter_map(Tag::as_long).collect()));
+                }
+                _ => {
+                    compound.insert(key, tag);
+                }
+            }
+        }
+        Ok(Tag::Compound(compound))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for Tag {
+    type Deserializer = Tag;
+
+    fn into_deserializer(self) -> Tag {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Tag {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            Tag::End => Err(SerdeError::Message("unexpected End tag".to_owned())),
+            Tag::Byte(v) => visitor.visit_i8(v),
+            Tag::Short(v) => visitor.visit_i16(v),
+            Tag::Int(v) => visitor.visit_i32(v),
+            Tag::Long(v) => visitor.visit_i64(v),
+            Tag::Float(v) => visitor.visit_f32(v),
+            Tag::Double(v) => visitor.visit_f64(v),
+            Tag::ByteArray(v) => {
+                visitor.visit_seq(SeqDeserializer::new(v.into_iter().map(ArrayByte)))
+            }
+            Tag::String(v) => visitor.visit_string(v),
+            Tag::List(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter())),
+            Tag::Compound(v) => visitor.visit_map(MapDeserializer::new(v.into_iter())),
+            Tag::IntArray(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter())),
+            Tag::LongArray(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter())),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            Tag::Byte(v) => visitor.visit_bool(v != 0),
+            tag => tag.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            Tag::ByteArray(v) => visitor.visit_byte_buf(v),
+            tag => tag.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            Tag::End => visitor.visit_none(),
+            tag => visitor.visit_some(tag),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        // a `Tag` field, arrays would turn into lists as sequences
+        let array = match (name, self) {
+            (TAG, Tag::ByteArray(v)) => (
+                BYTE_ARRAY,
+                v.into_iter().map(|b| Tag::Byte(b as i8)).collect(),
+            ),
+            (TAG, Tag::IntArray(v)) => (INT_ARRAY, v.into_iter().map(Tag::Int).collect()),
+            (TAG, Tag::LongArray(v)) => (LONG_ARRAY, v.into_iter().map(Tag::Long).collect()),
+            (_, tag) => return visitor.visit_newtype_struct(tag),
+        };
+        let (token, elements) = array;
+        visitor.visit_map(MapDeserializer::new(std::iter::once((
+            token,
+            Tag::List(List::from_vec_unchecked(elements)),
+        ))))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self {
+            Tag::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Tag::Compound(v) if v.len() == 1 => visitor.visit_enum(MapAccessDeserializer::new(
+                MapDeserializer::new(v.into_iter()),
+            )),
+            _ => Err(SerdeError::Message(
+                "expected a string or a compound with one entry for an enum".to_owned(),
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// An element of a `ByteArray`, an `i8` unless a `u8` is asked for, which gets the same bits
+struct ArrayByte(u8);
+
+impl<'de> IntoDeserializer<'de, SerdeError> for ArrayByte {
+    type Deserializer = ArrayByte;
+
+    fn into_deserializer(self) -> ArrayByte {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ArrayByte {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_i8(self.0 as i8)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_u8(self.0)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum
+        identifier ignored_any
+    }
+}
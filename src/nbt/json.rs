@@ -0,0 +1,304 @@
+//! Tags to and from `serde_json::Value`.
+//!
+//! `to_json`/`from_json` is vanilla's lossy mapping of text components and data pack files:
+//! numbers shrink to the smallest type that holds them, lists of integers become arrays and lists
+//! of mixed types wrap their elements as `{"": value}`. `to_typed_json`/`from_typed_json` keeps
+//! every type, each tag is an object with its type as the only key: `{"byte": 1}`,
+//! `{"compound": {"a": {"int_array": [1, 2]}}}`.
+
+use indexmap::IndexMap;
+use serde_json::{Map, Number, Value};
+use thiserror::Error;
+
+use super::{List, Tag};
+
+/// Where typed JSON doesn't describe a tag
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} at '{pointer}'")]
+pub struct JsonError {
+    /// JSON pointer to the value, `/a/0` for the first element of the entry `a`
+    pub pointer: String,
+    pub message: String,
+}
+
+impl Tag {
+    /// Vanilla's JSON for the tag: numbers, arrays and objects without their NBT types. `End`
+    /// and floats that aren't finite are `null`.
+    pub fn to_json(&self) -> Value {
+        match self {
+            Tag::End => Value::Null,
+            Tag::Byte(val) => (*val).into(),
+            Tag::Short(val) => (*val).into(),
+            Tag::Int(val) => (*val).into(),
+            Tag::Long(val) => (*val).into(),
+            Tag::Float(val) => float(*val).into(),
+            Tag::Double(val) => Number::from_f64(*val).into(),
+            Tag::ByteArray(val) => val.iter().map(|b| *b as i8).collect(),
+            Tag::String(val) => val.as_str().into(),
+            Tag::List(val) => val.iter().map(|tag| unwrap(tag).to_json()).collect(),
+            Tag::Compound(val) => val
+                .iter()
+                .map(|(key, tag)| (key.clone(), tag.to_json()))
+                .collect(),
+            Tag::IntArray(val) => val.as_slice().into(),
+            Tag::LongArray(val) => val.as_slice().into(),
+        }
+    }
+
+    /// Vanilla's tag for JSON, e.g. `1` is a `Byte`, `1.5` a `Float`, `true` the byte `1` and
+    /// `[1, 2]` a `ByteArray`. `null` is `End` and left out of objects and arrays.
+    pub fn from_json(json: &Value) -> Tag {
+        match json {
+            Value::Null => Tag::End,
+            Value::Bool(val) => Tag::from(*val),
+            Value::Number(val) => number(val),
+            Value::String(val) => Tag::String(val.clone()),
+            Value::Array(val) => list(
+                val.iter()
+                    .filter(|json| !json.is_null())
+                    .map(Tag::from_json),
+            ),
+            Value::Object(val) => val
+                .iter()
+                .filter(|(_, json)| !json.is_null())
+                .map(|(key, json)| (key.as_str(), Tag::from_json(json)))
+                .collect(),
+        }
+    }
+
+    /// Lossless JSON with the type of every tag, read back by `from_typed_json`. Floats that
+    /// aren't finite are the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    pub fn to_typed_json(&self) -> Value {
+        let value = match self {
+            Tag::End => Value::Null,
+            Tag::Byte(val) => (*val).into(),
+            Tag::Short(val) => (*val).into(),
+            Tag::Int(val) => (*val).into(),
+            Tag::Long(val) => (*val).into(),
+            Tag::Float(val) => float(*val).map_or_else(|| non_finite(*val as f64), Value::from),
+            Tag::Double(val) => {
+                Number::from_f64(*val).map_or_else(|| non_finite(*val), Value::from)
+            }
+            Tag::ByteArray(val) => val.iter().map(|b| *b as i8).collect(),
+            Tag::String(val) => val.as_str().into(),
+            Tag::List(val) => val.iter().map(Tag::to_typed_json).collect(),
+            Tag::Compound(val) => val
+                .iter()
+                .map(|(key, tag)| (key.clone(), tag.to_typed_json()))
+                .collect(),
+            Tag::IntArray(val) => val.as_slice().into(),
+            Tag::LongArray(val) => val.as_slice().into(),
+        };
+        let mut typed = Map::new();
+        typed.insert(type_key(self).to_owned(), value);
+        Value::Object(typed)
+    }
+
+    pub fn from_typed_json(json: &Value) -> Result<Tag, JsonError> {
+        typed(json, &mut String::new())
+    }
+}
+
+fn type_key(tag: &Tag) -> &'static str {
+    match tag {
+        Tag::End => "end",
+        Tag::Byte(_) => "byte",
+        Tag::Short(_) => "short",
+        Tag::Int(_) => "int",
+        Tag::Long(_) => "long",
+        Tag::Float(_) => "float",
+        Tag::Double(_) => "double",
+        Tag::ByteArray(_) => "byte_array",
+        Tag::String(_) => "string",
+        Tag::List(_) => "list",
+        Tag::Compound(_) => "compound",
+        Tag::IntArray(_) => "int_array",
+        Tag::LongArray(_) => "long_array",
+    }
+}
+
+/// The shortest decimal that reads back as the same `f32`, `0.1` rather than `0.10000000149…`
+fn float(val: f32) -> Option<Number> {
+    Number::from_f64(val.to_string().parse().ok()?)
+}
+
+fn non_finite(val: f64) -> Value {
+    match val {
+        f64::INFINITY => "Infinity".into(),
+        f64::NEG_INFINITY => "-Infinity".into(),
+        _ => "NaN".into(),
+    }
+}
+
+fn number(val: &Number) -> Tag {
+    if let Some(val) = val.as_i64() {
+        if let Ok(val) = i8::try_from(val) {
+            Tag::Byte(val)
+        } else if let Ok(val) = i16::try_from(val) {
+            Tag::Short(val)
+        } else if let Ok(val) = i32::try_from(val) {
+            Tag::Int(val)
+        } else {
+            Tag::Long(val)
+        }
+    } else {
+        let val = val.as_f64().unwrap_or(f64::NAN);
+        if val as f32 as f64 == val {
+            Tag::Float(val as f32)
+        } else {
+            Tag::Double(val)
+        }
+    }
+}
+
+/// Lists of one integer type are arrays, mixed lists wrap their elements
+fn list(elements: impl Iterator<Item = Tag>) -> Tag {
+    let elements: Vec<Tag> = elements.collect();
+    match elements.first() {
+        Some(Tag::Byte(_)) if elements.iter().all(|tag| matches!(tag, Tag::Byte(_))) => {
+            Tag::ByteArray(
+                elements
+                    .iter()
+                    .filter_map(Tag::as_byte)
+                    .map(|b| b as u8)
+                    .collect(),
+            )
+        }
+        Some(Tag::Int(_)) if elements.iter().all(|tag| matches!(tag, Tag::Int(_))) => {
+            Tag::IntArray(elements.iter().filter_map(Tag::as_int).collect())
+        }
+        Some(Tag::Long(_)) if elements.iter().all(|tag| matches!(tag, Tag::Long(_))) => {
+            Tag::LongArray(elements.iter().filter_map(Tag::as_long).collect())
+        }
+        Some(first)
+            if elements
+                .iter()
+                .any(|tag| tag.type_name() != first.type_name()) =>
+        {
+            Tag::List(List::from_vec_unchecked(
+                elements.into_iter().map(wrap).collect(),
+            ))
+        }
+        _ => Tag::List(List::from_vec_unchecked(elements)),
+    }
+}
+
+/// Wraps an element of a mixed list as `{"": value}`, compounds only if they'd look wrapped
+fn wrap(tag: Tag) -> Tag {
+    match tag.as_compound() {
+        Some(val) if val.len() != 1 || !val.contains_key("") => tag,
+        _ => Tag::from_iter([("", tag)]),
+    }
+}
+
+/// The element a mixed list wrapped as `{"": value}`, vanilla unwraps them when reading
+fn unwrap(tag: &Tag) -> &Tag {
+    match tag.as_compound() {
+        Some(val) if val.len() == 1 => val.get("").unwrap_or(tag),
+        _ => tag,
+    }
+}
+
+fn typed(json: &Value, pointer: &mut String) -> Result<Tag, JsonError> {
+    let error = |pointer: &str, message: &str| JsonError {
+        pointer: pointer.to_owned(),
+        message: message.to_owned(),
+    };
+    let (key, value) = match json.as_object().map(|val| val.iter().collect::<Vec<_>>()) {
+        Some(entries) if entries.len() == 1 => entries[0],
+        _ => {
+            return Err(error(
+                pointer,
+                "expected an object with the type as its only key",
+            ));
+        }
+    };
+    let integer = |min: i64, max: i64| {
+        value
+            .as_i64()
+            .filter(|val| (min..=max).contains(val))
+            .ok_or_else(|| error(pointer, &format!("expected an integer for {}", key)))
+    };
+    let floating = || match value {
+        Value::String(val) if val == "NaN" => Ok(f64::NAN),
+        Value::String(val) if val == "Infinity" => Ok(f64::INFINITY),
+        Value::String(val) if val == "-Infinity" => Ok(f64::NEG_INFINITY),
+        _ => value
+            .as_f64()
+            .ok_or_else(|| error(pointer, &format!("expected a number for {}", key))),
+    };
+    let array = |min: i64, max: i64| -> Result<Vec<i64>, JsonError> {
+        let elements = value
+            .as_array()
+            .ok_or_else(|| error(pointer, &format!("expected an array for {}", key)))?;
+        elements
+            .iter()
+            .enumerate()
+            .map(|(i, val)| {
+                val.as_i64()
+                    .filter(|val| (min..=max).contains(val))
+                    .ok_or_else(|| error(&format!("{}/{}", pointer, i), "integer out of range"))
+            })
+            .collect()
+    };
+    let tag = match key.as_str() {
+        "end" if value.is_null() => Tag::End,
+        "byte" => Tag::Byte(integer(i8::MIN.into(), i8::MAX.into())? as i8),
+        "short" => Tag::Short(integer(i16::MIN.into(), i16::MAX.into())? as i16),
+        "int" => Tag::Int(integer(i32::MIN.into(), i32::MAX.into())? as i32),
+        "long" => Tag::Long(integer(i64::MIN, i64::MAX)?),
+        "float" => Tag::Float(floating()? as f32),
+        "double" => Tag::Double(floating()?),
+        "string" => Tag::String(
+            value
+                .as_str()
+                .ok_or_else(|| error(pointer, "expected a string"))?
+                .to_owned(),
+        ),
+        "byte_array" => Tag::ByteArray(
+            array(i8::MIN.into(), i8::MAX.into())?
+                .into_iter()
+                .map(|b| b as u8)
+                .collect(),
+        ),
+        "int_array" => Tag::IntArray(
+            array(i32::MIN.into(), i32::MAX.into())?
+                .into_iter()
+                .map(|val| val as i32)
+                .collect(),
+        ),
+        "long_array" => Tag::LongArray(array(i64::MIN, i64::MAX)?),
+        "list" => {
+            let elements = value
+                .as_array()
+                .ok_or_else(|| error(pointer, "expected an array for list"))?;
+            let mut list = List::new();
+            for (i, element) in elements.iter().enumerate() {
+                let len = pointer.len();
+                pointer.push_str(&format!("/{}", i));
+                let tag = typed(element, pointer)?;
+                list.push(tag)
+                    .map_err(|err| error(pointer, &err.to_string()))?;
+                pointer.truncate(len);
+            }
+            Tag::List(list)
+        }
+        "compound" => {
+            let entries = value
+                .as_object()
+                .ok_or_else(|| error(pointer, "expected an object for compound"))?;
+            let mut compound = IndexMap::new();
+            for (key, element) in entries {
+                let len = pointer.len();
+                // JSON pointer escapes
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                compound.insert(key.clone(), typed(element, pointer)?);
+                pointer.truncate(len);
+            }
+            Tag::Compound(compound)
+        }
+        _ => return Err(error(pointer, &format!("unknown type '{}'", key))),
+    };
+    Ok(tag)
+}
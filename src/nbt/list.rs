@@ -0,0 +1,360 @@
+//! `List`, a list tag whose elements all have the same type, and the conversions into `Tag`.
+
+use std::{collections::HashMap, ops::Deref, slice};
+
+use indexmap::IndexMap;
+
+use super::Tag;
+
+/// What went wrong editing a tag
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TagError {
+    #[error("expected a compound, found {0}")]
+    NotACompound(&'static str),
+    #[error("expected a list, found {0}")]
+    NotAList(&'static str),
+    #[error("a list of {list} can't hold {element}")]
+    ListType {
+        list: &'static str,
+        element: &'static str,
+    },
+    #[error("a path can't replace or remove the root")]
+    PathRoot,
+    #[error("expected {expected}, found {found}")]
+    WrongType {
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+/// The elements of a list tag, all of one type. An empty list takes the type of the first
+/// element pushed.
+///
+/// Elements can be changed in place with `get_mut` and `iter_mut`, giving one another type makes
+/// writing the list fail.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct List(Vec<Tag>);
+
+impl List {
+    pub fn new() -> List {
+        List(Vec::new())
+    }
+
+    /// The type name of the elements, `None` while empty
+    pub fn element_type(&self) -> Option<&'static str> {
+        self.0.first().map(Tag::type_name)
+    }
+
+    pub fn push(&mut self, tag: impl Into<Tag>) -> Result<(), TagError> {
+        let tag = tag.into();
+        self.check(&tag)?;
+        self.0.push(tag);
+        Ok(())
+    }
+
+    /// Inserts at `index`, panics if `index > len` like `Vec::insert`
+    pub fn insert(&mut self, index: usize, tag: impl Into<Tag>) -> Result<(), TagError> {
+        let tag = tag.into();
+        self.check(&tag)?;
+        self.0.insert(index, tag);
+        Ok(())
+    }
+
+    /// Replaces the element at `index` and returns the old one, `None` if out of bounds
+    pub fn set(&mut self, index: usize, tag: impl Into<Tag>) -> Result<Option<Tag>, TagError> {
+        let tag = tag.into();
+        if self.0.len() > 1 || (self.0.len() == 1 && index != 0) {
+            self.check(&tag)?;
+        }
+        Ok(self
+            .0
+            .get_mut(index)
+            .map(|element| std::mem::replace(element, tag)))
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Tag> {
+        (index < self.0.len()).then(|| self.0.remove(index))
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Tag> {
+        self.0.get_mut(index)
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, Tag> {
+        self.0.iter_mut()
+    }
+
+    pub fn into_vec(self) -> Vec<Tag> {
+        self.0
+    }
+
+    /// For readers that already know all elements have one type
+    pub(super) fn from_vec_unchecked(elements: Vec<Tag>) -> List {
+        List(elements)
+    }
+
+    /// The first element whose type differs from the first, checked again before writing
+    pub(super) fn mismatch(&self) -> Option<TagError> {
+        let first = self.0.first()?;
+        self.0.iter().find_map(|tag| {
+            (tag.internal_id() != first.internal_id()).then(|| TagError::ListType {
+                list: first.type_name(),
+                element: tag.type_name(),
+            })
+        })
+    }
+
+    fn check(&self, tag: &Tag) -> Result<(), TagError> {
+        match self.0.first() {
+            Some(first) if first.internal_id() != tag.internal_id() => Err(TagError::ListType {
+                list: first.type_name(),
+                element: tag.type_name(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Deref for List {
+    type Target = [Tag];
+
+    fn deref(&self) -> &[Tag] {
+        &self.0
+    }
+}
+
+impl TryFrom<Vec<Tag>> for List {
+    type Error = TagError;
+
+    fn try_from(elements: Vec<Tag>) -> Result<List, TagError> {
+        let list = List(elements);
+        match list.mismatch() {
+            Some(err) => Err(err),
+            None => Ok(list),
+        }
+    }
+}
+
+impl IntoIterator for List {
+    type Item = Tag;
+    type IntoIter = std::vec::IntoIter<Tag>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a List {
+    type Item = &'a Tag;
+    type IntoIter = slice::Iter<'a, Tag>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl From<List> for Tag {
+    fn from(list: List) -> Tag {
+        Tag::List(list)
+    }
+}
+
+macro_rules! from_number {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for Tag {
+                fn from(val: $ty) -> Tag {
+                    Tag::$variant(val)
+                }
+            }
+
+            impl TryFrom<&Tag> for $ty {
+                type Error = TagError;
+
+                fn try_from(tag: &Tag) -> Result<$ty, TagError> {
+                    match *tag {
+                        Tag::$variant(val) => Ok(val),
+                        _ => Err(TagError::WrongType {
+                            expected: stringify!($variant),
+                            found: tag.type_name(),
+                        }),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_number! {
+    i8 => Byte,
+    i16 => Short,
+    i32 => Int,
+    i64 => Long,
+    f32 => Float,
+    f64 => Double,
+}
+
+macro_rules! from_vec {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl From<Vec<$ty>> for Tag {
+                fn from(val: Vec<$ty>) -> Tag {
+                    Tag::List(List(val.into_iter().map(Tag::from).collect()))
+                }
+            }
+        )*
+    };
+}
+
+// `Vec<u8>`, `Vec<i32>` and `Vec<i64>` are arrays, lists of them and mixed `Vec<Tag>`s go
+// through `List`
+from_vec!(i8, i16, f32, f64, bool, String, &str);
+
+impl From<Vec<i32>> for Tag {
+    fn from(val: Vec<i32>) -> Tag {
+        Tag::IntArray(val)
+    }
+}
+
+impl From<Vec<i64>> for Tag {
+    fn from(val: Vec<i64>) -> Tag {
+        Tag::LongArray(val)
+    }
+}
+
+impl From<Vec<u8>> for Tag {
+    fn from(val: Vec<u8>) -> Tag {
+        Tag::ByteArray(val)
+    }
+}
+
+impl From<bool> for Tag {
+    fn from(val: bool) -> Tag {
+        Tag::Byte(val as i8)
+    }
+}
+
+impl TryFrom<&Tag> for bool {
+    type Error = TagError;
+
+    fn try_from(tag: &Tag) -> Result<bool, TagError> {
+        i8::try_from(tag).map(|val| val != 0)
+    }
+}
+
+impl From<String> for Tag {
+    fn from(val: String) -> Tag {
+        Tag::String(val)
+    }
+}
+
+impl TryFrom<&Tag> for String {
+    type Error = TagError;
+
+    fn try_from(tag: &Tag) -> Result<String, TagError> {
+        <&str>::try_from(tag).map(str::to_owned)
+    }
+}
+
+impl From<&str> for Tag {
+    fn from(val: &str) -> Tag {
+        Tag::String(val.to_owned())
+    }
+}
+
+impl<'a> TryFrom<&'a Tag> for &'a str {
+    type Error = TagError;
+
+    fn try_from(tag: &'a Tag) -> Result<&'a str, TagError> {
+        tag.as_str().ok_or(TagError::WrongType {
+            expected: "String",
+            found: tag.type_name(),
+        })
+    }
+}
+
+impl<V: Into<Tag>> From<IndexMap<String, V>> for Tag {
+    fn from(val: IndexMap<String, V>) -> Tag {
+        Tag::Compound(val.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+/// Entries are in the map's iteration order
+impl<V: Into<Tag>> From<HashMap<String, V>> for Tag {
+    fn from(val: HashMap<String, V>) -> Tag {
+        Tag::Compound(val.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+impl<K: Into<String>, V: Into<Tag>> FromIterator<(K, V)> for Tag {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Tag {
+        Tag::Compound(
+            iter.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Builds a `Tag` from SNBT-like syntax: `{}` for compounds with string literal keys, `[]` for
+/// lists and anything else through `Tag::from`.
+///
+/// ```
+/// use mc_rust_protocol::nbt;
+///
+/// let health = 20.0f32;
+/// let tag = nbt!({
+///     "id": "minecraft:player",
+///     "Health": health,
+///     "Pos": [1.5f64, 64.0f64, -3.5f64],
+///     "Inventory": [{ "Slot": 0i8, "count": 1i8 }],
+/// });
+/// assert_eq!(tag.get_or("Health", 0.0f32), 20.0);
+/// ```
+///
+/// Panics if the elements of a list have different types.
+#[macro_export]
+macro_rules! nbt {
+    (@compound $compound:ident ()) => {};
+    (@compound $compound:ident () $key:literal : $($rest:tt)*) => {
+        $crate::nbt!(@entry $compound $key () $($rest)*)
+    };
+    (@entry $compound:ident $key:literal ($($value:tt)+) , $($rest:tt)*) => {
+        $compound.push(($key.into(), $crate::nbt!($($value)+)));
+        $crate::nbt!(@compound $compound () $($rest)*)
+    };
+    (@entry $compound:ident $key:literal ($($value:tt)+)) => {
+        $compound.push(($key.into(), $crate::nbt!($($value)+)));
+    };
+    (@entry $compound:ident $key:literal ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::nbt!(@entry $compound $key ($($value)* $next) $($rest)*)
+    };
+
+    (@list $list:ident ()) => {};
+    (@list $list:ident ($($value:tt)+) , $($rest:tt)*) => {
+        $list.push($crate::nbt!($($value)+)).expect("nbt! list elements must have one type");
+        $crate::nbt!(@list $list () $($rest)*)
+    };
+    (@list $list:ident ($($value:tt)+)) => {
+        $list.push($crate::nbt!($($value)+)).expect("nbt! list elements must have one type");
+    };
+    (@list $list:ident ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::nbt!(@list $list ($($value)* $next) $($rest)*)
+    };
+
+    ({ $($body:tt)* }) => {{
+        // the entries, then the compound built from them
+        let mut compound: ::std::vec::Vec<(::std::string::String, $crate::nbt::Tag)> =
+            ::std::vec::Vec::new();
+        $crate::nbt!(@compound compound () $($body)*);
+        $crate::nbt::Tag::Compound(compound.into_iter().collect())
+    }};
+    ([ $($body:tt)* ]) => {{
+        let mut list = $crate::nbt::List::new();
+        $crate::nbt!(@list list () $($body)*);
+        $crate::nbt::Tag::List(list)
+    }};
+    ($value:expr) => {
+        $crate::nbt::Tag::from($value)
+    };
+}
@@ -0,0 +1,408 @@
+//! NBT paths, e.g. `Items[0].tag.display.Name` or `Inventory[{Slot:1b}].count`, the syntax of
+//! `/data` and the `minecraft:nbt_path` command argument.
+//!
+//! Like vanilla, a compound pattern matches a tag that has at least its entries, and a list
+//! pattern a list with a matching element for each of its elements. Indexes and `[]` only look
+//! into lists, not arrays.
+
+use std::{fmt, mem, str::FromStr};
+
+use super::{
+    List, Tag, TagError,
+    snbt::{self, SnbtError},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    nodes: Vec<PathNode>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathNode {
+    /// `{Health:20f}`, only at the start: the root if it matches
+    MatchRoot(Tag),
+    /// `Items`
+    Key(String),
+    /// `Items{id:"minecraft:stone"}`, the entry if it matches
+    MatchKey(String, Tag),
+    /// `[]`, every element
+    AllElements,
+    /// `[0]`, negative indexes count from the end
+    Index(i32),
+    /// `[{Slot:1b}]`, the elements that match
+    MatchElement(Tag),
+}
+
+impl Path {
+    pub fn parse(path: &str) -> Result<Path, SnbtError> {
+        let error = |position, message: &str| SnbtError {
+            position,
+            message: message.to_owned(),
+        };
+        let mut nodes = Vec::new();
+        let mut pos = 0;
+        loop {
+            let rest = &path[pos..];
+            let node = match rest.chars().next() {
+                Some('{') if nodes.is_empty() => {
+                    let (pattern, end) = snbt::compound_at(path, pos)?;
+                    pos = end;
+                    PathNode::MatchRoot(pattern)
+                }
+                Some('[') => {
+                    pos += 1;
+                    let node = match path[pos..].chars().next() {
+                        Some('{') => {
+                            let (pattern, end) = snbt::compound_at(path, pos)?;
+                            pos = end;
+                            PathNode::MatchElement(pattern)
+                        }
+                        Some(']') => PathNode::AllElements,
+                        _ => {
+                            let len = path[pos..]
+                                .find(|c: char| c != '-' && !c.is_ascii_digit())
+                                .unwrap_or(path.len() - pos);
+                            let index = path[pos..pos + len]
+                                .parse()
+                                .map_err(|_| error(pos, "expected an index"))?;
+                            pos += len;
+                            PathNode::Index(index)
+                        }
+                    };
+                    if !path[pos..].starts_with(']') {
+                        return Err(error(pos, "expected ']'"));
+                    }
+                    pos += 1;
+                    node
+                }
+                _ => {
+                    let key = match rest.chars().next() {
+                        Some('"' | '\'') => {
+                            let (key, end) = snbt::quoted_at(path, pos)?;
+                            pos = end;
+                            key
+                        }
+                        _ => {
+                            let len = rest.find(|c| !is_key_char(c)).unwrap_or(rest.len());
+                            if len == 0 {
+                                return Err(error(pos, "expected a key"));
+                            }
+                            pos += len;
+                            rest[..len].to_owned()
+                        }
+                    };
+                    if path[pos..].starts_with('{') {
+                        let (pattern, end) = snbt::compound_at(path, pos)?;
+                        pos = end;
+                        PathNode::MatchKey(key, pattern)
+                    } else {
+                        PathNode::Key(key)
+                    }
+                }
+            };
+            nodes.push(node);
+            match path[pos..].chars().next() {
+                None => return Ok(Path { nodes }),
+                Some('[' | '{') => {}
+                Some('.') => pos += 1,
+                Some(_) => return Err(error(pos, "expected '.' or '['")),
+            }
+        }
+    }
+
+    pub fn nodes(&self) -> &[PathNode] {
+        &self.nodes
+    }
+
+    /// The tags the path points to, in order
+    pub fn get<'a>(&self, root: &'a Tag) -> Vec<&'a Tag> {
+        self.nodes.iter().fold(vec![root], |tags, node| {
+            tags.into_iter()
+                .flat_map(|tag| node.children(tag))
+                .collect()
+        })
+    }
+
+    pub fn get_mut<'a>(&self, root: &'a mut Tag) -> Vec<&'a mut Tag> {
+        parents_mut(&self.nodes, root)
+    }
+
+    /// Sets every tag the path points to, creating missing compounds and lists on the way.
+    /// Returns how many tags changed.
+    pub fn set(&self, root: &mut Tag, value: &Tag) -> Result<usize, TagError> {
+        let (last, parents) = self.nodes.split_last().expect("paths aren't empty");
+        let mut changed = 0;
+        for parent in parents_or_create(parents, root, &last.create()) {
+            changed += last.set(parent, value)?;
+        }
+        Ok(changed)
+    }
+
+    /// Removes every tag the path points to, returns how many
+    pub fn remove(&self, root: &mut Tag) -> Result<usize, TagError> {
+        let (last, parents) = self.nodes.split_last().expect("paths aren't empty");
+        let mut removed = 0;
+        for parent in parents_mut(parents, root) {
+            removed += last.remove(parent)?;
+        }
+        Ok(removed)
+    }
+
+    /// Merges `compound` into every compound the path points to, creating it if missing.
+    /// Returns how many tags changed.
+    pub fn merge(&self, root: &mut Tag, compound: &Tag) -> Result<usize, TagError> {
+        let mut changed = 0;
+        for tag in parents_or_create(&self.nodes, root, &Tag::new_compound()) {
+            let before = tag.clone();
+            tag.merge(compound)?;
+            changed += usize::from(*tag != before);
+        }
+        Ok(changed)
+    }
+}
+
+impl FromStr for Path {
+    type Err = SnbtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Path::parse(s)
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, node) in self.nodes.iter().enumerate() {
+            match node {
+                PathNode::MatchRoot(pattern) => write!(f, "{}", pattern)?,
+                PathNode::Key(key) | PathNode::MatchKey(key, _) => {
+                    if i > 0 {
+                        f.write_str(".")?;
+                    }
+                    if !key.is_empty() && key.chars().all(is_key_char) {
+                        f.write_str(key)?;
+                    } else {
+                        snbt::write_string(f, key)?;
+                    }
+                    if let PathNode::MatchKey(_, pattern) = node {
+                        write!(f, "{}", pattern)?;
+                    }
+                }
+                PathNode::AllElements => f.write_str("[]")?,
+                PathNode::Index(index) => write!(f, "[{}]", index)?,
+                PathNode::MatchElement(pattern) => write!(f, "[{}]", pattern)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PathNode {
+    fn children<'a>(&self, tag: &'a Tag) -> Vec<&'a Tag> {
+        match (self, tag) {
+            (PathNode::MatchRoot(pattern), tag) if matches(pattern, tag) => vec![tag],
+            (PathNode::Key(key), tag) => tag.get(key).into_iter().collect(),
+            (PathNode::MatchKey(key, pattern), tag) => tag
+                .get(key)
+                .filter(|tag| matches(pattern, tag))
+                .into_iter()
+                .collect(),
+            (PathNode::AllElements, Tag::List(list)) => list.iter().collect(),
+            (PathNode::Index(index), Tag::List(list)) => resolve(*index, list.len())
+                .map(|i| &list[i])
+                .into_iter()
+                .collect(),
+            (PathNode::MatchElement(pattern), Tag::List(list)) => {
+                list.iter().filter(|tag| matches(pattern, tag)).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn children_mut<'a>(&self, tag: &'a mut Tag) -> Vec<&'a mut Tag> {
+        match (self, tag) {
+            (PathNode::MatchRoot(pattern), tag) if matches(pattern, tag) => vec![tag],
+            (PathNode::Key(key), tag) => tag.get_mut(key).into_iter().collect(),
+            (PathNode::MatchKey(key, pattern), tag) => tag
+                .get_mut(key)
+                .filter(|tag| matches(pattern, tag))
+                .into_iter()
+                .collect(),
+            (PathNode::AllElements, Tag::List(list)) => list.iter_mut().collect(),
+            (PathNode::Index(index), Tag::List(list)) => match resolve(*index, list.len()) {
+                Some(i) => list.get_mut(i).into_iter().collect(),
+                None => Vec::new(),
+            },
+            (PathNode::MatchElement(pattern), Tag::List(list)) => list
+                .iter_mut()
+                .filter(|tag| matches(pattern, tag))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Like `children_mut`, but adds `create()` for a missing entry and patterns that match
+    /// nothing, as vanilla does before setting
+    fn children_or_create<'a>(&self, tag: &'a mut Tag, create: &Tag) -> Vec<&'a mut Tag> {
+        match (self, tag) {
+            (PathNode::Key(key), Tag::Compound(val)) => {
+                vec![val.entry(key.clone()).or_insert_with(|| create.clone())]
+            }
+            (PathNode::MatchKey(key, pattern), Tag::Compound(val)) => {
+                let child = val.entry(key.clone()).or_insert_with(|| pattern.clone());
+                if matches(pattern, child) {
+                    vec![child]
+                } else {
+                    Vec::new()
+                }
+            }
+            (PathNode::AllElements, Tag::List(list)) => {
+                if list.is_empty() {
+                    // a wrong type leaves the list empty, nothing to set then
+                    let _ = list.push(create.clone());
+                }
+                list.iter_mut().collect()
+            }
+            (PathNode::MatchElement(pattern), Tag::List(list)) => {
+                if !list.iter().any(|tag| matches(pattern, tag)) {
+                    let _ = list.push(pattern.clone());
+                }
+                list.iter_mut()
+                    .filter(|tag| matches(pattern, tag))
+                    .collect()
+            }
+            (node, tag) => node.children_mut(tag),
+        }
+    }
+
+    /// What to create for this node to look into
+    fn create(&self) -> Tag {
+        match self {
+            PathNode::MatchRoot(_) | PathNode::Key(_) | PathNode::MatchKey(..) => {
+                Tag::new_compound()
+            }
+            PathNode::AllElements | PathNode::Index(_) | PathNode::MatchElement(_) => {
+                Tag::new_list()
+            }
+        }
+    }
+
+    /// Sets this node's children of `parent` to `value`, returns how many changed
+    fn set(&self, parent: &mut Tag, value: &Tag) -> Result<usize, TagError> {
+        Ok(match (self, parent) {
+            (PathNode::MatchRoot(_), _) => return Err(TagError::PathRoot),
+            (PathNode::Key(key), Tag::Compound(val)) => {
+                usize::from(val.insert(key.clone(), value.clone()).as_ref() != Some(value))
+            }
+            (PathNode::MatchKey(key, pattern), Tag::Compound(val)) => match val.get_mut(key) {
+                Some(child) if matches(pattern, child) && child != value => {
+                    *child = value.clone();
+                    1
+                }
+                _ => 0,
+            },
+            (PathNode::AllElements, Tag::List(list)) if list.is_empty() => {
+                list.push(value.clone())?;
+                1
+            }
+            (PathNode::AllElements, Tag::List(list)) => {
+                let changed = list.iter().filter(|tag| *tag != value).count();
+                *list = List::from_vec_unchecked(vec![value.clone(); list.len()]);
+                changed
+            }
+            (PathNode::Index(index), Tag::List(list)) => match resolve(*index, list.len()) {
+                Some(i) => usize::from(list.set(i, value.clone())?.as_ref() != Some(value)),
+                None => 0,
+            },
+            (PathNode::MatchElement(pattern), Tag::List(list)) => {
+                let mut changed = 0;
+                for i in 0..list.len() {
+                    if matches(pattern, &list[i]) && list[i] != *value {
+                        list.set(i, value.clone())?;
+                        changed += 1;
+                    }
+                }
+                changed
+            }
+            _ => 0,
+        })
+    }
+
+    /// Removes this node's children of `parent`, returns how many
+    fn remove(&self, parent: &mut Tag) -> Result<usize, TagError> {
+        Ok(match (self, parent) {
+            (PathNode::MatchRoot(_), _) => return Err(TagError::PathRoot),
+            (PathNode::Key(key), parent) => usize::from(parent.remove(key).is_some()),
+            (PathNode::MatchKey(key, pattern), parent)
+                if parent.get(key).is_some_and(|tag| matches(pattern, tag)) =>
+            {
+                parent.remove(key);
+                1
+            }
+            (PathNode::AllElements, Tag::List(list)) => mem::take(list).len(),
+            (PathNode::Index(index), Tag::List(list)) => {
+                resolve(*index, list.len()).map_or(0, |i| usize::from(list.remove(i).is_some()))
+            }
+            (PathNode::MatchElement(pattern), Tag::List(list)) => {
+                let len = list.len();
+                let kept = mem::take(list)
+                    .into_iter()
+                    .filter(|tag| !matches(pattern, tag))
+                    .collect();
+                *list = List::from_vec_unchecked(kept);
+                len - list.len()
+            }
+            _ => 0,
+        })
+    }
+}
+
+fn parents_mut<'a>(nodes: &[PathNode], root: &'a mut Tag) -> Vec<&'a mut Tag> {
+    nodes.iter().fold(vec![root], |tags, node| {
+        tags.into_iter()
+            .flat_map(|tag| node.children_mut(tag))
+            .collect()
+    })
+}
+
+/// Follows `nodes`, creating what the next node needs, and `last` after the last one
+fn parents_or_create<'a>(nodes: &[PathNode], root: &'a mut Tag, last: &Tag) -> Vec<&'a mut Tag> {
+    let mut tags = vec![root];
+    for (i, node) in nodes.iter().enumerate() {
+        let create = nodes
+            .get(i + 1)
+            .map_or_else(|| last.clone(), PathNode::create);
+        tags = tags
+            .into_iter()
+            .flat_map(|tag| node.children_or_create(tag, &create))
+            .collect();
+    }
+    tags
+}
+
+/// Whether `tag` has everything in `pattern`, vanilla's partial match
+fn matches(pattern: &Tag, tag: &Tag) -> bool {
+    match (pattern, tag) {
+        (Tag::Compound(pattern), Tag::Compound(_)) => pattern
+            .iter()
+            .all(|(key, pattern)| tag.get(key).is_some_and(|tag| matches(pattern, tag))),
+        (Tag::List(pattern), Tag::List(list)) if pattern.is_empty() => list.is_empty(),
+        (Tag::List(pattern), Tag::List(list)) => pattern
+            .iter()
+            .all(|pattern| list.iter().any(|tag| matches(pattern, tag))),
+        _ => pattern == tag,
+    }
+}
+
+fn resolve(index: i32, len: usize) -> Option<usize> {
+    let index = if index < 0 {
+        len.checked_sub(index.unsigned_abs() as usize)?
+    } else {
+        index as usize
+    };
+    (index < len).then_some(index)
+}
+
+/// Vanilla allows anything but whitespace, quotes, brackets, braces and dots
+fn is_key_char(c: char) -> bool {
+    !c.is_whitespace() && !"\"'[]{}.".contains(c)
+}
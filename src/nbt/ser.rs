@@ -0,0 +1,984 @@
+//! `Serialize` into a `Tag`.
+//!
+//! Structs and maps are compounds, sequences are lists, `bool` is a byte, `None` fields are left
+//! out, unit variants are strings and other variants a compound with the variant name as key.
+//! Lists of numbers are `List`s unless the field uses `byte_array`, `int_array` or
+//! `long_array`.
+
+use std::io;
+
+use indexmap::IndexMap;
+use serde::{
+    Serialize,
+    ser::{self, Impossible},
+};
+
+use super::{List, SerdeError, Tag};
+
+pub(super) const BYTE_ARRAY: &str = "__nbt_byte_array";
+pub(super) const INT_ARRAY: &str = "__nbt_int_array";
+pub(super) const LONG_ARRAY: &str = "__nbt_long_array";
+
+/// `None` gives `Tag::End`
+pub fn to_tag<T: Serialize + ?Sized>(value: &T) -> Result<Tag, SerdeError> {
+    value.serialize(TagSerializer)
+}
+
+/// Writes `value` as network NBT
+pub fn to_writer<W: io::Write, T: Serialize + ?Sized>(
+    writer: &mut W,
+    value: &T,
+) -> Result<(), SerdeError> {
+    to_tag(value)?.write_network(writer)?;
+    Ok(())
+}
+
+impl Serialize for Tag {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ser::{SerializeMap, SerializeSeq};
+
+        match self {
+            Tag::End => serializer.serialize_none(),
+            Tag::Byte(v) => serializer.serialize_i8(*v),
+            Tag::Short(v) => serializer.serialize_i16(*v),
+            Tag::Int(v) => serializer.serialize_i32(*v),
+            Tag::Long(v) => serializer.serialize_i64(*v),
+            Tag::Float(v) => serializer.serialize_f32(*v),
+            Tag::Double(v) => serializer.serialize_f64(*v),
+            Tag::ByteArray(v) => {
+                let v: Vec<i8> = v.iter().map(|b| *b as i8).collect();
+                serializer.serialize_newtype_struct(BYTE_ARRAY, &v)
+            }
+            Tag::String(v) => serializer.serialize_str(v),
+            Tag::List(v) => {
+                let mut seq = serializer.serialize_seq(Some(v.len()))?;
+                for tag in v {
+                    seq.serialize_element(tag)?;
+                }
+                seq.end()
+            }
+            Tag::Compound(v) => {
+                let mut map = serializer.serialize_map(Some(v.len()))?;
+                for (key, tag) in v {
+                    map.serialize_entry(key, tag)?;
+                }
+                map.end()
+            }
+            Tag::IntArray(v) => serializer.serialize_newtype_struct(INT_ARRAY, v),
+            Tag::LongArray(v) => serializer.serialize_newtype_struct(LONG_ARRAY, v),
+        }
+    }
+}
+
+macro_rules! array_module {
+    ($name:ident, $token:ident, $doc:literal) => {
+        #[doc = $doc]
+        pub mod $name {
+            use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+            pub fn serialize<T: Serialize + ?Sized, S: Serializer>(
+                value: &T,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                serializer.serialize_newtype_struct(super::$token, value)
+            }
+
+            pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<T, D::Error> {
+                T::deserialize(deserializer)
+            }
+        }
+    };
+}
+
+array_module!(
+    byte_array,
+    BYTE_ARRAY,
+    "`#[serde(with = \"nbt::byte_array\")]` on a sequence of `i8` or `u8` writes a `ByteArray`"
+);
+array_module!(
+    int_array,
+    INT_ARRAY,
+    "`#[serde(with = \"nbt::int_array\")]` on a sequence of `i32` writes an `IntArray`"
+);
+array_module!(
+    long_array,
+    LONG_ARRAY,
+    "`#[serde(with = \"nbt::long_array\")]` on a sequence of `i64` writes a `LongArray`"
+);
+
+fn unsupported(what: &str) -> SerdeError {
+    SerdeError::Message(format!("{} can't be written as NBT", what))
+}
+
+fn out_of_range(value: impl std::fmt::Display) -> SerdeError {
+    SerdeError::Message(format!("{} doesn't fit its NBT type", value))
+}
+
+/// `{variant: value}`
+fn variant_tag(variant: &str, value: Tag) -> Tag {
+    Tag::Compound(IndexMap::from([(variant.to_owned(), value)]))
+}
+
+struct TagSerializer;
+
+impl ser::Serializer for TagSerializer {
+    type Ok = Tag;
+    type Error = SerdeError;
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = VariantSerializer<ListSerializer>;
+    type SerializeMap = CompoundSerializer;
+    type SerializeStruct = CompoundSerializer;
+    type SerializeStructVariant = VariantSerializer<CompoundSerializer>;
+
+    fn serialize_bool(self, v: bool) -> Result<Tag, SerdeError> {
+        Ok(Tag::Byte(v as i8))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Tag, SerdeError> {
+        Ok(Tag::Byte(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Tag, SerdeError> {
+        Ok(Tag::Short(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Tag, SerdeError> {
+        Ok(Tag::Int(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Tag, SerdeError> {
+        Ok(Tag::Long(v))
+    }
+
+    // unsigned numbers use the signed type of the same size, when they fit
+    fn serialize_u8(self, v: u8) -> Result<Tag, SerdeError> {
+        Ok(Tag::Byte(v.try_into().map_err(|_| out_of_range(v))?))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Tag, SerdeError> {
+        Ok(Tag::Short(v.try_into().map_err(|_| out_of_range(v))?))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Tag, SerdeError> {
+        Ok(Tag::Int(v.try_into().map_err(|_| out_of_range(v))?))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Tag, SerdeError> {
+        Ok(Tag::Long(v.try_into().map_err(|_| out_of_range(v))?))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Tag, SerdeError> {
+        Ok(Tag::Float(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Tag, SerdeError> {
+        Ok(Tag::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Tag, SerdeError> {
+        Ok(Tag::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Tag, SerdeError> {
+        Ok(Tag::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Tag, SerdeError> {
+        Ok(Tag::ByteArray(v.to_vec()))
+    }
+
+    /// `End` is left out of compounds
+    fn serialize_none(self) -> Result<Tag, SerdeError> {
+        Ok(Tag::End)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Tag, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Tag, SerdeError> {
+        Ok(Tag::new_compound())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Tag, SerdeError> {
+        Ok(Tag::new_compound())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Tag, SerdeError> {
+        Ok(Tag::String(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Tag, SerdeError> {
+        if name == BYTE_ARRAY {
+            return Ok(Tag::ByteArray(value.serialize(ByteArraySerializer)?));
+        }
+        let tag = value.serialize(self)?;
+        let elements = || match &tag {
+            Tag::List(elements) => Ok(elements),
+            _ => Err(SerdeError::Message(format!(
+                "expected a sequence for {}",
+                name
+            ))),
+        };
+        let wrong_type = || SerdeError::Message(format!("wrong element type for {}", name));
+        Ok(match name {
+            INT_ARRAY => Tag::IntArray(
+                elements()?
+                    .iter()
+                    .map(|tag| tag.as_int().ok_or_else(wrong_type))
+                    .collect::<Result<_, _>>()?,
+            ),
+            LONG_ARRAY => Tag::LongArray(
+                elements()?
+                    .iter()
+                    .map(|tag| tag.as_long().ok_or_else(wrong_type))
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => tag,
+        })
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Tag, SerdeError> {
+        Ok(variant_tag(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<ListSerializer, SerdeError> {
+        Ok(ListSerializer(List::new()))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ListSerializer, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<ListSerializer, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<CompoundSerializer, SerdeError> {
+        Ok(CompoundSerializer {
+            entries: IndexMap::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<CompoundSerializer, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+struct ListSerializer(List);
+
+impl ser::SerializeSeq for ListSerializer {
+    type Ok = Tag;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let tag = to_tag(value)?;
+        if tag == Tag::End {
+            return Err(unsupported("None in a sequence"));
+        }
+        self.0
+            .push(tag)
+            .map_err(|err| SerdeError::Message(err.to_string()))
+    }
+
+    fn end(self) -> Result<Tag, SerdeError> {
+        Ok(Tag::List(self.0))
+    }
+}
+
+impl ser::SerializeTuple for ListSerializer {
+    type Ok = Tag;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Tag, SerdeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for ListSerializer {
+    type Ok = Tag;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Tag, SerdeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct CompoundSerializer {
+    entries: IndexMap<String, Tag>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for CompoundSerializer {
+    type Ok = Tag;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| SerdeError::Message("map value without a key".to_owned()))?;
+        match to_tag(value)? {
+            // `None`
+            Tag::End => {}
+            tag => {
+                self.entries.insert(key, tag);
+            }
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Tag, SerdeError> {
+        Ok(Tag::Compound(self.entries))
+    }
+}
+
+impl ser::SerializeStruct for CompoundSerializer {
+    type Ok = Tag;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        ser::SerializeMap::serialize_entry(self, key, value)
+    }
+
+    fn end(self) -> Result<Tag, SerdeError> {
+        Ok(Tag::Compound(self.entries))
+    }
+}
+
+/// `{variant: inner}`
+struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<ListSerializer> {
+    type Ok = Tag;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Tag, SerdeError> {
+        let list = ser::SerializeSeq::end(self.inner)?;
+        Ok(variant_tag(self.variant, list))
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<CompoundSerializer> {
+    type Ok = Tag;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Tag, SerdeError> {
+        let compound = ser::SerializeStruct::end(self.inner)?;
+        Ok(variant_tag(self.variant, compound))
+    }
+}
+
+/// The content of a `ByteArray`, `u8` elements keep their bits rather than having to fit an `i8`
+struct ByteArraySerializer;
+
+impl ser::Serializer for ByteArraySerializer {
+    type Ok = Vec<u8>;
+    type Error = SerdeError;
+    type SerializeSeq = ByteArrayElements;
+    type SerializeTuple = ByteArrayElements;
+    type SerializeTupleStruct = ByteArrayElements;
+    type SerializeTupleVariant = Impossible<Vec<u8>, SerdeError>;
+    type SerializeMap = Impossible<Vec<u8>, SerdeError>;
+    type SerializeStruct = Impossible<Vec<u8>, SerdeError>;
+    type SerializeStructVariant = Impossible<Vec<u8>, SerdeError>;
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Vec<u8>, SerdeError> {
+        Ok(v.to_vec())
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ByteArrayElements, SerdeError> {
+        Ok(ByteArrayElements(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ByteArrayElements, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<ByteArrayElements, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Vec<u8>, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_none(self) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_unit(self) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Vec<u8>, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        Err(unsupported("a non-sequence ByteArray"))
+    }
+}
+
+struct ByteArrayElements(Vec<u8>);
+
+impl ser::SerializeSeq for ByteArrayElements {
+    type Ok = Vec<u8>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.0.push(value.serialize(ByteSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Vec<u8>, SerdeError> {
+        Ok(self.0)
+    }
+}
+
+impl ser::SerializeTuple for ByteArrayElements {
+    type Ok = Vec<u8>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Vec<u8>, SerdeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for ByteArrayElements {
+    type Ok = Vec<u8>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Vec<u8>, SerdeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// An element of a `ByteArray`, `i8` or `u8`
+struct ByteSerializer;
+
+impl ser::Serializer for ByteSerializer {
+    type Ok = u8;
+    type Error = SerdeError;
+    type SerializeSeq = Impossible<u8, SerdeError>;
+    type SerializeTuple = Impossible<u8, SerdeError>;
+    type SerializeTupleStruct = Impossible<u8, SerdeError>;
+    type SerializeTupleVariant = Impossible<u8, SerdeError>;
+    type SerializeMap = Impossible<u8, SerdeError>;
+    type SerializeStruct = Impossible<u8, SerdeError>;
+    type SerializeStructVariant = Impossible<u8, SerdeError>;
+
+    fn serialize_i8(self, v: i8) -> Result<u8, SerdeError> {
+        Ok(v as u8)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<u8, SerdeError> {
+        Ok(v)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<u8, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_char(self, _v: char) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_none(self) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_unit(self) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<u8, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        Err(unsupported("a non-byte ByteArray element"))
+    }
+}
+
+/// Compound keys are strings
+struct KeySerializer;
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = SerdeError;
+    type SerializeSeq = Impossible<String, SerdeError>;
+    type SerializeTuple = Impossible<String, SerdeError>;
+    type SerializeTupleStruct = Impossible<String, SerdeError>;
+    type SerializeTupleVariant = Impossible<String, SerdeError>;
+    type SerializeMap = Impossible<String, SerdeError>;
+    type SerializeStruct = Impossible<String, SerdeError>;
+    type SerializeStructVariant = Impossible<String, SerdeError>;
+
+    fn serialize_str(self, v: &str) -> Result<String, SerdeError> {
+        Ok(v.to_owned())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, SerdeError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, SerdeError> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_none(self) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_unit(self) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        Err(unsupported("a non-string compound key"))
+    }
+}
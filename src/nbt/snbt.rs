@@ -0,0 +1,590 @@
+//! SNBT, e.g. `{Count:1b,tag:{Damage:5s},list:[I;1,2,3]}`.
+//!
+//! The parser follows vanilla's grammar: type suffixes (`1b`, `2s`, `3L`, `4f`, `5d`, `6ub`),
+//! hex and binary integers (`0xff`, `0b101`), `_` between digits, `true`/`false` as bytes,
+//! typed arrays (`[B;1b,2b]`), single or double quoted strings with escapes, trailing commas.
+//! Lists must hold a single type, as in network NBT.
+
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use indexmap::IndexMap;
+use thiserror::Error;
+
+use super::{List, MAX_DEPTH, Tag};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} at position {position}")]
+pub struct SnbtError {
+    /// Byte offset into the input
+    pub position: usize,
+    pub message: String,
+}
+
+impl Tag {
+    pub fn from_snbt(snbt: &str) -> Result<Tag, SnbtError> {
+        let mut parser = Parser {
+            snbt,
+            pos: 0,
+            depth: 0,
+        };
+        let tag = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < snbt.len() {
+            return Err(parser.error("trailing data"));
+        }
+        Ok(tag)
+    }
+
+    /// Compact SNBT on one line, the same as `to_string()`
+    pub fn to_snbt(&self) -> String {
+        self.to_string()
+    }
+
+    /// Indented SNBT, the same as `format!("{:#}", tag)`
+    pub fn to_snbt_pretty(&self) -> String {
+        format!("{:#}", self)
+    }
+}
+
+impl FromStr for Tag {
+    type Err = SnbtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Tag::from_snbt(s)
+    }
+}
+
+/// SNBT, e.g. `{id:"minecraft:stone",count:1b}`, in the compounds' order (`sort_keys` first for
+/// sorted output). `{:#}` indents compounds and lists of them by 4 spaces.
+impl Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let indent = f.alternate().then_some(0);
+        write_snbt(f, self, indent)
+    }
+}
+
+const INDENT: &str = "    ";
+
+/// `indent` is the current depth when pretty printing
+fn write_snbt(f: &mut fmt::Formatter<'_>, tag: &Tag, indent: Option<usize>) -> fmt::Result {
+    fn write_array<T: Display>(
+        f: &mut fmt::Formatter<'_>,
+        prefix: &str,
+        suffix: &str,
+        values: &[T],
+        pretty: bool,
+    ) -> fmt::Result {
+        write!(f, "[{};", prefix)?;
+        for (i, val) in values.iter().enumerate() {
+            match (i, pretty) {
+                (0, false) => {}
+                (0, true) => f.write_str(" ")?,
+                (_, false) => f.write_str(",")?,
+                (_, true) => f.write_str(", ")?,
+            }
+            write!(f, "{}{}", val, suffix)?;
+        }
+        f.write_str("]")
+    }
+
+    // entries on their own lines, or separated by `,` when not pretty printing
+    fn write_entries<T>(
+        f: &mut fmt::Formatter<'_>,
+        entries: &[T],
+        indent: Option<usize>,
+        mut write_entry: impl FnMut(&mut fmt::Formatter<'_>, &T, Option<usize>) -> fmt::Result,
+    ) -> fmt::Result {
+        let inner = indent.map(|depth| depth + 1);
+        for (i, entry) in entries.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            if let Some(depth) = inner {
+                writeln!(f)?;
+                f.write_str(&INDENT.repeat(depth))?;
+            }
+            write_entry(f, entry, inner)?;
+        }
+        if let Some(depth) = indent
+            && !entries.is_empty()
+        {
+            writeln!(f)?;
+            f.write_str(&INDENT.repeat(depth))?;
+        }
+        Ok(())
+    }
+
+    let pretty = indent.is_some();
+    match tag {
+        Tag::End => Ok(()),
+        Tag::Byte(val) => write!(f, "{}b", val),
+        Tag::Short(val) => write!(f, "{}s", val),
+        Tag::Int(val) => write!(f, "{}", val),
+        Tag::Long(val) => write!(f, "{}L", val),
+        Tag::Float(val) => write!(f, "{}f", val),
+        Tag::Double(val) => write!(f, "{}d", val),
+        Tag::ByteArray(val) => {
+            let val: Vec<i8> = val.iter().map(|b| *b as i8).collect();
+            write_array(f, "B", "b", &val, pretty)
+        }
+        Tag::String(val) => write_string(f, val),
+        Tag::List(val) => {
+            // numbers and strings stay on one line
+            let nested = val
+                .iter()
+                .any(|tag| matches!(tag, Tag::List(_) | Tag::Compound(_)));
+            f.write_str("[")?;
+            if nested {
+                write_entries(f, val, indent, write_snbt)?;
+            } else {
+                for (i, tag) in val.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(if pretty { ", " } else { "," })?;
+                    }
+                    write_snbt(f, tag, indent)?;
+                }
+            }
+            f.write_str("]")
+        }
+        Tag::Compound(val) => {
+            let entries: Vec<_> = val.iter().collect();
+            f.write_str("{")?;
+            write_entries(f, &entries, indent, |f, (name, tag), indent| {
+                let bare = !name.is_empty() && name.chars().all(is_unquoted_char);
+                if bare {
+                    f.write_str(name)?;
+                } else {
+                    write_string(f, name)?;
+                }
+                f.write_str(if indent.is_some() { ": " } else { ":" })?;
+                write_snbt(f, tag, indent)
+            })?;
+            f.write_str("}")
+        }
+        Tag::IntArray(val) => write_array(f, "I", "", val, pretty),
+        Tag::LongArray(val) => write_array(f, "L", "L", val, pretty),
+    }
+}
+
+/// Quotes with `"`, or with `'` when that saves escaping, like vanilla
+pub(super) fn write_string(f: &mut fmt::Formatter<'_>, val: &str) -> fmt::Result {
+    let quote = match val.chars().find(|c| *c == '"' || *c == '\'') {
+        Some('"') => '\'',
+        _ => '"',
+    };
+    write!(f, "{}", quote)?;
+    for c in val.chars() {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            '\r' => f.write_str("\\r")?,
+            c if c == quote => write!(f, "\\{}", c)?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "{}", quote)
+}
+
+/// Parses the compound at byte `pos`, returning it and the position after it
+pub(super) fn compound_at(snbt: &str, pos: usize) -> Result<(Tag, usize), SnbtError> {
+    let mut parser = Parser {
+        snbt,
+        pos,
+        depth: 0,
+    };
+    let tag = parser.nested(Parser::compound)?;
+    Ok((tag, parser.pos))
+}
+
+/// Parses the quoted string at byte `pos`, returning it and the position after it
+pub(super) fn quoted_at(snbt: &str, pos: usize) -> Result<(String, usize), SnbtError> {
+    let mut parser = Parser {
+        snbt,
+        pos,
+        depth: 0,
+    };
+    let s = parser.quoted()?;
+    Ok((s, parser.pos))
+}
+
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_-.+".contains(c)
+}
+
+struct Parser<'a> {
+    snbt: &'a str,
+    pos: usize,
+    /// Compounds and lists the parser is in
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> SnbtError {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at(&self, position: usize, message: &str) -> SnbtError {
+        SnbtError {
+            position,
+            message: message.to_owned(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.snbt[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SnbtError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            _ => Err(self.error(&format!("expected '{}'", expected))),
+        }
+    }
+
+    /// Parses a compound or list one level deeper
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<Tag, SnbtError>,
+    ) -> Result<Tag, SnbtError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(&format!("nested deeper than {} levels", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let tag = parse(self);
+        self.depth -= 1;
+        tag
+    }
+
+    fn value(&mut self) -> Result<Tag, SnbtError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.nested(Self::compound),
+            Some('[') => {
+                let rest = &self.snbt[self.pos + 1..];
+                match rest.as_bytes() {
+                    [b'B' | b'I' | b'L', b';', ..] => self.array(),
+                    _ => self.nested(Self::list),
+                }
+            }
+            Some('"' | '\'') => Ok(Tag::String(self.quoted()?)),
+            Some(_) => {
+                let start = self.pos;
+                let token = self.unquoted();
+                if token.is_empty() {
+                    return Err(self.error("expected a value"));
+                }
+                literal(token).map_err(|message| self.error_at(start, &message))
+            }
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn unquoted(&mut self) -> &str {
+        let start = self.pos;
+        while self.peek().is_some_and(is_unquoted_char) {
+            self.next();
+        }
+        &self.snbt[start..self.pos]
+    }
+
+    fn quoted(&mut self) -> Result<String, SnbtError> {
+        let start = self.pos;
+        let quote = self.next();
+        let mut s = String::new();
+        loop {
+            let escape = self.pos;
+            match self.next() {
+                None => return Err(self.error_at(start, "unterminated string")),
+                Some(c) if Some(c) == quote => return Ok(s),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some(c @ ('\\' | '"' | '\'')) => c,
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('s') => ' ',
+                        Some('t') => '\t',
+                        Some('x') => self.code_point(2, escape)?,
+                        Some('u') => self.code_point(4, escape)?,
+                        Some('U') => self.code_point(8, escape)?,
+                        _ => return Err(self.error_at(escape, "invalid escape sequence")),
+                    };
+                    s.push(c);
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn code_point(&mut self, digits: usize, escape: usize) -> Result<char, SnbtError> {
+        let hex = self
+            .snbt
+            .get(self.pos..self.pos + digits)
+            .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error_at(escape, "invalid escape sequence"))?;
+        self.pos += digits;
+        u32::from_str_radix(hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error_at(escape, "invalid code point"))
+    }
+
+    /// Elements separated by `,`, a trailing `,` is fine
+    fn elements(
+        &mut self,
+        end: char,
+        mut element: impl FnMut(&mut Self) -> Result<(), SnbtError>,
+    ) -> Result<(), SnbtError> {
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(end) {
+                self.next();
+                return Ok(());
+            }
+            element(self)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {}
+                Some(c) if c == end => {}
+                _ => return Err(self.error(&format!("expected ',' or '{}'", end))),
+            }
+            if self.next() == Some(end) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn compound(&mut self) -> Result<Tag, SnbtError> {
+        self.expect('{')?;
+        let mut compound = IndexMap::new();
+        self.elements('}', |parser| {
+            let key = match parser.peek() {
+                Some('"' | '\'') => parser.quoted()?,
+                _ => match parser.unquoted() {
+                    "" => return Err(parser.error("expected a key")),
+                    key => key.to_owned(),
+                },
+            };
+            parser.expect(':')?;
+            let tag = parser.value()?;
+            compound.insert(key, tag);
+            Ok(())
+        })?;
+        Ok(Tag::Compound(compound))
+    }
+
+    fn list(&mut self) -> Result<Tag, SnbtError> {
+        self.expect('[')?;
+        let mut list = List::new();
+        self.elements(']', |parser| {
+            let start = parser.pos;
+            let tag = parser.value()?;
+            list.push(tag)
+                .map_err(|err| parser.error_at(start, &err.to_string()))
+        })?;
+        Ok(Tag::List(list))
+    }
+
+    fn array(&mut self) -> Result<Tag, SnbtError> {
+        self.expect('[')?;
+        let ty = self.next();
+        self.expect(';')?;
+        let (min, max) = match ty {
+            Some('B') => (i8::MIN as i64, u8::MAX as i64),
+            Some('I') => (i32::MIN as i64, u32::MAX as i64),
+            _ => (i64::MIN, i64::MAX),
+        };
+        let mut values: Vec<i64> = Vec::new();
+        self.elements(']', |parser| {
+            let start = parser.pos;
+            let value = match parser.value()? {
+                Tag::Byte(val) => val as i64,
+                Tag::Short(val) => val as i64,
+                Tag::Int(val) => val as i64,
+                Tag::Long(val) => val,
+                _ => return Err(parser.error_at(start, "expected an integer")),
+            };
+            if value < min || value > max {
+                return Err(parser.error_at(start, "number out of range"));
+            }
+            values.push(value);
+            Ok(())
+        })?;
+        Ok(match ty {
+            Some('B') => Tag::ByteArray(values.into_iter().map(|v| v as u8).collect()),
+            Some('I') => Tag::IntArray(values.into_iter().map(|v| v as i32).collect()),
+            _ => Tag::LongArray(values),
+        })
+    }
+}
+
+/// A number, `true`/`false`, or else an unquoted string
+fn literal(token: &str) -> Result<Tag, String> {
+    match token {
+        "true" => return Ok(Tag::Byte(1)),
+        "false" => return Ok(Tag::Byte(0)),
+        _ => {}
+    }
+    if let Some(tag) = integer(token)? {
+        return Ok(tag);
+    }
+    if let Some(tag) = float(token) {
+        return Ok(tag);
+    }
+    Ok(Tag::String(token.to_owned()))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum IntType {
+    Byte,
+    Short,
+    Int,
+    Long,
+}
+
+/// `None` when the token isn't an integer, an error when it is one that doesn't fit its type
+fn integer(token: &str) -> Result<Option<Tag>, String> {
+    let (negative, rest) = match token.as_bytes().first() {
+        Some(b'-') => (true, &token[1..]),
+        Some(b'+') => (false, &token[1..]),
+        _ => (false, token),
+    };
+    let (radix, digits) = match rest.get(..2) {
+        Some("0x" | "0X") => (16, &rest[2..]),
+        Some("0b" | "0B") if rest[2..].starts_with(['0', '1']) => (2, &rest[2..]),
+        _ => (10, rest),
+    };
+
+    let (ty, signed, digits) = int_suffix(digits, radix == 16);
+    let valid = |c: char| c == '_' || c.is_digit(radix);
+    if digits.is_empty()
+        || !digits.chars().all(valid)
+        || digits.starts_with('_')
+        || digits.ends_with('_')
+    {
+        return Ok(None);
+    }
+
+    let digits = digits.replace('_', "");
+    let magnitude = u64::from_str_radix(&digits, radix).map_err(|_| "number out of range")?;
+    let bits = match ty {
+        IntType::Byte => 8,
+        IntType::Short => 16,
+        IntType::Int => 32,
+        IntType::Long => 64,
+    };
+    // decimal is signed unless `u`, hex and binary can use the unsigned range unless `s`
+    let unsigned = signed.map_or(radix != 10, |signed| !signed);
+    let value = if negative {
+        if signed == Some(false) {
+            return Err("unsigned number can't be negative".to_owned());
+        }
+        if magnitude > 1 << (bits - 1) {
+            return Err("number out of range".to_owned());
+        }
+        (magnitude as i64).wrapping_neg()
+    } else {
+        let max = if unsigned {
+            u64::MAX >> (64 - bits)
+        } else {
+            u64::MAX >> (65 - bits)
+        };
+        if magnitude > max {
+            return Err("number out of range".to_owned());
+        }
+        // unsigned values wrap into the signed type, `255ub` is -1
+        magnitude as i64
+    };
+    Ok(Some(match ty {
+        IntType::Byte => Tag::Byte(value as i8),
+        IntType::Short => Tag::Short(value as i16),
+        IntType::Int => Tag::Int(value as i32),
+        IntType::Long => Tag::Long(value),
+    }))
+}
+
+/// Type and signedness (`u` or `s`) suffixes, and the digits before them
+fn int_suffix(digits: &str, hex: bool) -> (IntType, Option<bool>, &str) {
+    let Some(last) = digits.chars().next_back() else {
+        return (IntType::Int, None, digits);
+    };
+    let ty = match last {
+        'b' | 'B' => IntType::Byte,
+        's' | 'S' => IntType::Short,
+        'i' | 'I' => IntType::Int,
+        'l' | 'L' => IntType::Long,
+        _ => return (IntType::Int, None, digits),
+    };
+    let rest = &digits[..digits.len() - 1];
+    let signed = match rest.chars().next_back() {
+        Some('u' | 'U') => Some(false),
+        Some('s' | 'S') => Some(true),
+        _ => None,
+    };
+    // `b` is a hex digit, a hex byte needs the signedness: `0x1fsb`
+    if hex && ty == IntType::Byte && signed.is_none() {
+        return (IntType::Int, None, digits);
+    }
+    match signed {
+        Some(_) => (ty, signed, &rest[..rest.len() - 1]),
+        None => (ty, None, rest),
+    }
+}
+
+/// `1.5`, `.5`, `1.`, `1e3`, with an optional `f` or `d` suffix, a double without one.
+/// Integers with a suffix (`1f`, `2d`) are floats too.
+fn float(token: &str) -> Option<Tag> {
+    let (body, is_float) = match token.char_indices().next_back()? {
+        (i, 'f' | 'F') => (&token[..i], true),
+        (i, 'd' | 'D') => (&token[..i], false),
+        _ => (token, false),
+    };
+    let unsigned = body.strip_prefix(['+', '-']).unwrap_or(body);
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(i) => (&unsigned[..i], Some(&unsigned[i + 1..])),
+        None => (unsigned, None),
+    };
+    let digits = |s: &str| {
+        !s.starts_with('_')
+            && !s.ends_with('_')
+            && s.chars().all(|c| c == '_' || c.is_ascii_digit())
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int.is_empty() && frac.is_empty() || !digits(int) || !digits(frac) {
+        return None;
+    }
+    if let Some(exponent) = exponent {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        if exponent.is_empty() || !digits(exponent) {
+            return None;
+        }
+    }
+    let body = body.replace('_', "");
+    Some(if is_float {
+        Tag::Float(body.parse().ok()?)
+    } else {
+        Tag::Double(body.parse().ok()?)
+    })
+}
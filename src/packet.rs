@@ -7,8 +7,8 @@ use crate::slot::PaintingVariant;
 use crate::slot::Slot;
 
 use super::*;
-use macros::Serializable;
 use macros::get_entry;
+use macros::{Serializable, Visit};
 
 macro_rules! state_packets {
     (
@@ -30,7 +30,7 @@ macro_rules! state_packets {
                     use packet::*;
                     use slot::*;
 
-                    #[derive(Serializable, Debug)]
+                    #[derive(Serializable, Debug, Clone, PartialEq)]
                     $(#[$attr])*
                     pub struct $packet {
                         $($(#[$fattr])* pub $field:$ty,)*
@@ -45,11 +45,55 @@ macro_rules! state_packets {
             }
         )+
 
-        #[derive(Debug)]
+        #[derive(Debug, Clone, PartialEq)]
         pub enum Packet {
             $($($($packet($dir::$state::$packet),)*)+)+
         }
 
+        impl Packet {
+            /// Name of the packet's struct, e.g. `"ClientboundKeepAlivePlay"`
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($($(Packet::$packet(_) => stringify!($packet),)*)+)+
+                }
+            }
+
+            /// State the packet is sent in
+            pub fn state(&self) -> State {
+                match self {
+                    $($($(Packet::$packet(_) => State::$stateName,)*)+)+
+                }
+            }
+
+            pub fn direction(&self) -> Direction {
+                match self {
+                    $($($(Packet::$packet(_) => Direction::$dirName,)*)+)+
+                }
+            }
+
+            pub fn id(&self) -> i32 {
+                match self {
+                    $($($(Packet::$packet(_) => $dir::$state::$packet::ID,)*)+)+
+                }
+            }
+
+            /// Writes the packet ID followed by the packet data, ready for `NetworkEncoder::write_packet`
+            pub fn write<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
+                match self {
+                    $($($(Packet::$packet(p) => p.write(buf),)*)+)+
+                }
+            }
+        }
+
+        /// Visits the packet's struct
+        impl visit::Visit for Packet {
+            fn visit(&self, visitor: &mut dyn visit::Visitor) {
+                match self {
+                    $($($(Packet::$packet(p) => p.visit(visitor),)*)+)+
+                }
+            }
+        }
+
         pub fn packet_by_id<R: io::Read>(state: State, dir: Direction, id: i32, buf: &mut R) -> Result<Packet, Error> {
             Ok(match dir {
                 $(
@@ -68,7 +112,7 @@ macro_rules! state_packets {
     };
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum State {
     Handshake,
     Status,
@@ -77,7 +121,7 @@ pub enum State {
     Play,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Visit)]
 pub enum Direction {
     Serverbound,
     Clientbound,
@@ -948,12 +992,13 @@ state_packets! {
             }
             RecipeBookSettings "recipe_book_settings" {
                 crafting_recipe_book_open bool
-                smelting_recipe_filter_active bool
+                crafting_recipe_filter_active bool
                 smelting_recipe_book_open bool
-                blast_furnace_recipe_filter_active bool
+                smelting_recipe_filter_active bool
                 blast_furnace_recipe_book_open bool
-                smoker_recipe_filter_active bool
+                blast_furnace_recipe_filter_active bool
                 smoker_recipe_book_open bool
+                smoker_recipe_filter_active bool
             }
             RemoveEntities "remove_entities" {
                 entity_ids PrefixedArray<VarInt>
@@ -1039,7 +1084,7 @@ state_packets! {
                 carried_item Slot
             }
             SetDefaultSpawnPosition "set_default_spawn_position" {
-                dimension_name VarInt
+                dimension_name Identifier
                 location Position
                 yaw f32
                 pitch f32
@@ -1234,7 +1279,7 @@ state_packets! {
     }
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(u8, 1)]
 pub enum Intent {
     Status,
@@ -1247,63 +1292,83 @@ impl From<Intent> for State {
         match val {
             Intent::Status => State::Status,
             Intent::Login => State::Login,
-            Intent::Transfer => State::Configuration,
+            Intent::Transfer => State::Login,
+        }
+    }
+}
+
+impl Packet {
+    /// The state the sending side is in after sending this packet, for the packets that
+    /// switch states. Both directions switch independently, e.g. clientbound packets are in
+    /// the configuration state right after `LoginSuccess`, serverbound ones only after
+    /// `LoginAcknowledged`.
+    pub fn state_transition(&self) -> Option<State> {
+        match self {
+            Packet::Handshake(p) => Some(p.intent.into()),
+            Packet::LoginSuccess(_)
+            | Packet::LoginAcknowledged(_)
+            | Packet::StartConfiguration(_)
+            | Packet::AcknowledgeConfiguration(_) => Some(State::Configuration),
+            Packet::FinishConfiguration(_) | Packet::AcknowledgeFinishConfiguration(_) => {
+                Some(State::Play)
+            }
+            _ => None,
         }
     }
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct ProfileProperty {
     pub name: String,
     pub value: String,
     pub signature: Option<String>,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct RegistryEntry {
     pub entry_id: Identifier,
     pub data: Option<nbt::Tag>,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct Tags {
     pub registry: Identifier,
     pub tags: PrefixedArray<Tag>,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct Tag {
     pub tag_name: Identifier,
     pub entries: PrefixedArray<VarInt>,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct KnownPack {
     pub namespace: String,
     pub id: String,
     pub version: String,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct ReportDetail {
     pub title: String,
     pub description: String,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct ServerLink {
     pub label: LinkLabel,
     pub url: String,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(bool, 0)]
 pub enum LinkLabel {
     TextComponent(TextComponent),
     Label(LinkLabelEnum),
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum LinkLabelEnum {
     BugReport,
@@ -1318,7 +1383,7 @@ pub enum LinkLabelEnum {
     Announcements,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ChatMode {
     Enabled,
@@ -1326,7 +1391,7 @@ pub enum ChatMode {
     Hidden,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[bitfields(u8)]
 pub struct SkinParts {
     pub cape: bool,
@@ -1338,14 +1403,14 @@ pub struct SkinParts {
     pub hat: bool,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum MainHand {
     Left,
     Right,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ParticleStatus {
     All,
@@ -1353,7 +1418,7 @@ pub enum ParticleStatus {
     Minimal,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ResourcePackResult {
     SuccessfullyDownloaded,
@@ -1366,7 +1431,7 @@ pub enum ResourcePackResult {
     Discarded,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(u8, 0)]
 pub enum Animation {
     SwingMainArm,
@@ -1377,13 +1442,13 @@ pub enum Animation {
     MagicCriticalEffect,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct StatisticEntry {
     pub statistic: Statistic,
     pub value: VarInt,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum Statistic {
     Mined { block: VarInt },
@@ -1397,7 +1462,7 @@ pub enum Statistic {
     Custom(CustomStatistic),
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum CustomStatistic {
     LeaveGame,
@@ -1477,7 +1542,7 @@ pub enum CustomStatistic {
     InteractWithSmithingTable,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum BossAction {
     Add {
@@ -1503,7 +1568,7 @@ pub enum BossAction {
     },
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ColorId {
     Pink,
@@ -1515,7 +1580,7 @@ pub enum ColorId {
     White,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum DivisionType {
     NoDivision,
@@ -1525,7 +1590,7 @@ pub enum DivisionType {
     TwentyNotches,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[bitfields(u8)]
 pub struct BossActionFlags {
     pub should_darken_sky: bool,
@@ -1533,7 +1598,7 @@ pub struct BossActionFlags {
     pub create_fog: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(u8, 0)]
 pub enum Difficulty {
     Peaceful,
@@ -1542,20 +1607,20 @@ pub enum Difficulty {
     Hard,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct ChunkBiomeData {
     pub chunk_x: i32,
     pub chunk_z: i32,
     pub data: LenPrefixedBytes<VarInt>,
 }
 
-#[derive(Serializable, Debug)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct CommandSuggestionMatch {
     pub command_match: String,
     pub tooltip: Option<TextComponent>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Visit)]
 pub struct Node {
     // read directly
     pub children: PrefixedArray<VarInt>,
@@ -1569,8 +1634,8 @@ pub struct Node {
 impl Serializable for Node {
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
         // read buf
-        let flags = buf.read_u8()?;
-        let children: PrefixedArray<VarInt> = Serializable::read_from(buf)?;
+        let flags = span::field("flags", || Ok(buf.read_u8()?))?;
+        let children = span::field("children", || PrefixedArray::<VarInt>::read_from(buf))?;
 
         // flags
         let node_type = flags & 0x03;
@@ -1581,19 +1646,19 @@ impl Serializable for Node {
 
         // read buf
         let redirect_node = has_redirect
-            .then(|| Serializable::read_from(buf))
+            .then(|| span::field("redirect_node", || VarInt::read_from(buf)))
             .transpose()?;
 
         let node_info = match node_type {
             0 => NodeInfo::Root,
             1 => NodeInfo::Literal {
-                name: Serializable::read_from(buf)?,
+                name: span::field("name", || String::read_from(buf))?,
             },
             2 => NodeInfo::Argument {
-                name: Serializable::read_from(buf)?,
-                parser: Serializable::read_from(buf)?,
+                name: span::field("name", || String::read_from(buf))?,
+                parser: span::field("parser", || Serializable::read_from(buf))?,
                 suggestions_type: has_suggestions_type
-                    .then(|| Serializable::read_from(buf))
+                    .then(|| span::field("suggestions_type", || Serializable::read_from(buf)))
                     .transpose()?,
             },
             3 => NodeInfo::Root,
@@ -1668,7 +1733,7 @@ impl Serializable for Node {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Visit)]
 pub enum NodeInfo {
     Root,
     Literal {
@@ -1681,7 +1746,7 @@ pub enum NodeInfo {
     },
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum Parser {
     BrigadierBool,
@@ -1707,6 +1772,7 @@ pub enum Parser {
     MinecraftMessage,
     MinecraftNbtCompoundTag,
     MinecraftNbtTag,
+    /// Parsed by `nbt::Path`
     MinecraftNbtPath,
     MinecraftObjective,
     MinecraftObjectiveCriteria,
@@ -1768,7 +1834,7 @@ impl Bounded for i64 {
     const MAX: Self = Self::MAX;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
 pub struct BrigadierNumOptions<T: Serializable + Bounded + PartialEq + Copy> {
     pub min: T,
     pub max: T,
@@ -1817,7 +1883,7 @@ impl<T: Serializable + Bounded + PartialEq + Copy> Serializable for BrigadierNum
     }
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum BrigadierStringOptions {
     SingleWord,
@@ -1825,50 +1891,50 @@ pub enum BrigadierStringOptions {
     GreedyPhrase,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[bitfields(u8)]
 pub struct MinecraftEntityOptions {
     pub single_entity: bool,
     pub player_only: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[bitfields(u8)]
 pub struct MinecraftScoreHolderOptions {
     pub multiple: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct MinecraftTimeOptions {
     pub min: i32,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct MinecraftResourceOrTagOptions {
     pub registry: Identifier,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct MinecraftResourceOrTagKeyOptions {
     pub registry: Identifier,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct MinecraftResourceOptions {
     pub registry: Identifier,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct MinecraftResourceKeyOptions {
     pub registry: Identifier,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct MinecraftResourceSelectorOptions {
     pub registry: Identifier,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ChatSuggestionAction {
     Add,
@@ -1876,26 +1942,26 @@ pub enum ChatSuggestionAction {
     Set,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum DebugSampleType {
     TickTime,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct ChatType {
     pub chat: ChatTypeDecorations,
     pub narration: ChatTypeDecorations,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct ChatTypeDecorations {
     pub translartion_key: String,
     pub parameters: PrefixedArray<ChatTypeParameters>,
     pub style: nbt::Tag,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ChatTypeParameters {
     Sender,
@@ -1903,7 +1969,7 @@ pub enum ChatTypeParameters {
     Content,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum Particle {
     AngryVillager,
@@ -2064,7 +2130,7 @@ pub enum Particle {
     Firefly,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
 pub struct ColorARGBI32 {
     pub a: u8,
     pub r: u8,
@@ -2091,7 +2157,7 @@ impl Serializable for ColorARGBI32 {
     }
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum VibrationData {
     Block {
@@ -2103,14 +2169,14 @@ pub enum VibrationData {
     },
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct ChunkData {
     pub heightmaps: PrefixedArray<HeightMap>,
     pub data: LenPrefixedBytes<VarInt>,
     pub block_entities: PrefixedArray<BlockEntity>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct BlockEntity {
     pub packed_xz: PackedXZ,
     pub y: i16,
@@ -2118,12 +2184,27 @@ pub struct BlockEntity {
     pub data: nbt::Tag,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
 pub struct PackedXZ {
     x: u8,
     z: u8,
 }
 
+impl PackedXZ {
+    /// Coordinates within the chunk, 0 to 15
+    pub fn new(x: u8, z: u8) -> Self {
+        PackedXZ { x, z }
+    }
+
+    pub fn x(&self) -> u8 {
+        self.x
+    }
+
+    pub fn z(&self) -> u8 {
+        self.z
+    }
+}
+
 impl Serializable for PackedXZ {
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
         let packed_xz = buf.read_u8()?;
@@ -2138,13 +2219,13 @@ impl Serializable for PackedXZ {
     }
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct HeightMap {
     pub ty: VarInt,
     pub data: PrefixedArray<i64>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct LightData {
     pub sky_light_mask: BitSet,
     pub block_light_mask: BitSet,
@@ -2154,13 +2235,13 @@ pub struct LightData {
     pub block_light_arrays: PrefixedArray<LenPrefixedBytes<VarInt>>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct DeathInfo {
     pub death_dimension_name: Identifier,
     pub death_location: Position,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct MapIcon {
     pub ty: VarInt,
     pub x: i8,
@@ -2169,7 +2250,7 @@ pub struct MapIcon {
     pub display_name: Option<TextComponent>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
 pub enum MapColorPatch {
     NoColumns,
     HasColumns {
@@ -2218,7 +2299,7 @@ impl Serializable for MapColorPatch {
     }
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct MerchantTrade {
     pub input_item_1: TradeItem,
     pub output_item: Slot,
@@ -2232,14 +2313,14 @@ pub struct MerchantTrade {
     pub demand: i32,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct TradeItem {
     pub item_id: VarInt,
     pub item_count: VarInt,
     pub components: PrefixedArray<Component>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct MinecartStep {
     pub pos: Vec3<f64>,
     pub velocity: Vec3<f64>,
@@ -2248,14 +2329,14 @@ pub struct MinecartStep {
     pub weight: f32,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum Hand {
     Main,
     Offhand,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum RecipeDisplay {
     CraftingShapeless {
@@ -2292,7 +2373,7 @@ pub enum RecipeDisplay {
     },
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum SlotDisplay {
     Empty,
@@ -2316,7 +2397,7 @@ pub enum SlotDisplay {
     },
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[bitfields(u8)]
 pub struct PlayerAbilitiesFlags {
     pub invulnerable: bool,
@@ -2325,7 +2406,7 @@ pub struct PlayerAbilitiesFlags {
     pub creative_mode: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ChatMessageFilterType {
     PassThrough,
@@ -2333,7 +2414,7 @@ pub enum ChatMessageFilterType {
     PartiallyFiltered { filter_type_bits: BitSet },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Visit)]
 pub struct PlayersActionsData {
     // pub actions: FixedBitSet<1>,
     // LEN PREFIXED by varint
@@ -2344,54 +2425,60 @@ pub struct PlayersActionsData {
 impl Serializable for PlayersActionsData {
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
         // this specific enum has 8 variants
-        let actions = FixedBitSet::<8>::read_from(buf)?;
+        let actions = span::field("actions", || FixedBitSet::<8>::read_from(buf))?;
 
-        let len = VarInt::read_from(buf)?.into_len();
+        let len = span::field("length", || VarInt::read_from(buf))?.into_len();
 
         let mut players: Vec<PlayerActions> = Vec::with_capacity(len);
-        for _ in 0..len {
-            let uuid = UUID::read_from(buf)?;
-            let mut player_actions: Vec<PlayerAction> = Vec::new();
-            for i in 0..8 {
-                if actions.get(i) {
-                    let action = match i {
-                        0 => PlayerAction::AddPlayer {
-                            name: Serializable::read_from(buf)?,
-                            properties: Serializable::read_from(buf)?,
-                        },
-                        1 => PlayerAction::InitializeChat {
-                            data: Serializable::read_from(buf)?,
-                        },
-
-                        2 => PlayerAction::UpdateGamemode {
-                            gamemode: Serializable::read_from(buf)?,
-                        },
-                        3 => PlayerAction::UpdateListed {
-                            listed: Serializable::read_from(buf)?,
-                        },
-                        4 => PlayerAction::UpdateLatency {
-                            ping: Serializable::read_from(buf)?,
-                        },
-                        5 => PlayerAction::UpdateDisplayName {
-                            display_name: Serializable::read_from(buf)?,
-                        },
-                        6 => PlayerAction::UpdateListPriority {
-                            priority: Serializable::read_from(buf)?,
-                        },
-
-                        7 => PlayerAction::UpdateHat {
-                            visible: Serializable::read_from(buf)?,
-                        },
-                        _ => unreachable!(),
-                    };
-                    player_actions.push(action);
+        for player in 0..len {
+            players.push(span::element(player, || {
+                let uuid = span::field("uuid", || UUID::read_from(buf))?;
+                let mut player_actions: Vec<PlayerAction> = Vec::new();
+                for i in 0..8 {
+                    if actions.get(i) {
+                        let action = match i {
+                            0 => PlayerAction::AddPlayer {
+                                name: span::field("name", || Serializable::read_from(buf))?,
+                                properties: span::field("properties", || {
+                                    Serializable::read_from(buf)
+                                })?,
+                            },
+                            1 => PlayerAction::InitializeChat {
+                                data: span::field("data", || Serializable::read_from(buf))?,
+                            },
+
+                            2 => PlayerAction::UpdateGamemode {
+                                gamemode: span::field("gamemode", || Serializable::read_from(buf))?,
+                            },
+                            3 => PlayerAction::UpdateListed {
+                                listed: span::field("listed", || Serializable::read_from(buf))?,
+                            },
+                            4 => PlayerAction::UpdateLatency {
+                                ping: span::field("ping", || Serializable::read_from(buf))?,
+                            },
+                            5 => PlayerAction::UpdateDisplayName {
+                                display_name: span::field("display_name", || {
+                                    Serializable::read_from(buf)
+                                })?,
+                            },
+                            6 => PlayerAction::UpdateListPriority {
+                                priority: span::field("priority", || Serializable::read_from(buf))?,
+                            },
+
+                            7 => PlayerAction::UpdateHat {
+                                visible: span::field("visible", || Serializable::read_from(buf))?,
+                            },
+                            _ => unreachable!(),
+                        };
+                        player_actions.push(action);
+                    }
                 }
-            }
 
-            players.push(PlayerActions {
-                uuid,
-                player_actions,
-            });
+                Ok(PlayerActions {
+                    uuid,
+                    player_actions,
+                })
+            })?);
         }
 
         Ok(PlayersActionsData {
@@ -2401,32 +2488,30 @@ impl Serializable for PlayersActionsData {
     fn write_to<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
         // this specific enum has 8 variants
         let mut actions = FixedBitSet::<8>::new();
-
-        for player_actions in &self.players_actions {
-            for player_action in &player_actions.player_actions {
-                let index = match player_action {
-                    PlayerAction::AddPlayer {
-                        name: _,
-                        properties: _,
-                    } => 0,
-                    PlayerAction::InitializeChat { data: _ } => 1,
-                    PlayerAction::UpdateDisplayName { display_name: _ } => 2,
-                    PlayerAction::UpdateGamemode { gamemode: _ } => 3,
-                    PlayerAction::UpdateHat { visible: _ } => 4,
-                    PlayerAction::UpdateLatency { ping: _ } => 5,
-                    PlayerAction::UpdateListPriority { priority: _ } => 6,
-                    PlayerAction::UpdateListed { listed: _ } => 7,
-                };
-                actions.set(index);
-            }
+        let mut mask = 0u8;
+        for player_action in self.players_actions.iter().flat_map(|p| &p.player_actions) {
+            actions.set(player_action.index() as u64);
+            mask |= 1 << player_action.index();
         }
 
         actions.write_to(buf)?;
         let len = self.players_actions.len();
         VarInt::from_len(len).write_to(buf)?;
         for player_actions in &self.players_actions {
+            // every player has the actions of the bitset, in the order of their bits
+            let mut sorted: Vec<&PlayerAction> = player_actions.player_actions.iter().collect();
+            sorted.sort_by_key(|action| action.index());
+            let player_mask = sorted
+                .iter()
+                .fold(0u8, |player_mask, action| player_mask | 1 << action.index());
+            if player_mask != mask || sorted.len() != mask.count_ones() as usize {
+                return Err(Error::SerializeError(
+                    "all players must have the same actions, once each".to_owned(),
+                ));
+            }
+
             player_actions.uuid.write_to(buf)?;
-            for player_action in &player_actions.player_actions {
+            for player_action in sorted {
                 match player_action {
                     PlayerAction::AddPlayer { name, properties } => {
                         name.write_to(buf)?;
@@ -2454,13 +2539,13 @@ impl Serializable for PlayersActionsData {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Visit)]
 pub struct PlayerActions {
     pub uuid: UUID,
     pub player_actions: Vec<PlayerAction>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Visit)]
 pub enum PlayerAction {
     AddPlayer {
         name: String,
@@ -2489,7 +2574,23 @@ pub enum PlayerAction {
     },
 }
 
-#[derive(Debug, Serializable)]
+impl PlayerAction {
+    /// Bit of the action in `PlayersActionsData`'s bitset, also the order actions are sent in
+    fn index(&self) -> u8 {
+        match self {
+            PlayerAction::AddPlayer { .. } => 0,
+            PlayerAction::InitializeChat { .. } => 1,
+            PlayerAction::UpdateGamemode { .. } => 2,
+            PlayerAction::UpdateListed { .. } => 3,
+            PlayerAction::UpdateLatency { .. } => 4,
+            PlayerAction::UpdateDisplayName { .. } => 5,
+            PlayerAction::UpdateListPriority { .. } => 6,
+            PlayerAction::UpdateHat { .. } => 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct InitializeChatData {
     pub chat_session_id: UUID,
     pub public_key_expire_time: i64,
@@ -2497,20 +2598,20 @@ pub struct InitializeChatData {
     pub public_key_signature: LenPrefixedBytes<VarInt>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum FeetEyes {
     Feet,
     Eyes,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct LookAtEntityInfo {
     pub entity_id: VarInt,
     pub feet_eyes: FeetEyes,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[bitfields(i32)]
 pub struct TeleportFlags {
     pub relative_x: bool,
@@ -2524,7 +2625,7 @@ pub struct TeleportFlags {
     pub rotate_velocity_accoridng_to_rotation: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct Recipe {
     pub recipe_id: VarInt,
     pub display: RecipeDisplay,
@@ -2534,21 +2635,21 @@ pub struct Recipe {
     pub flags: RecipeFlags,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[bitfields(u8)]
 pub struct RecipeFlags {
     pub show_notification: bool,
     pub highlight_as_new: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[bitfields(u8)]
 pub struct DataKept {
     pub keep_atributes: bool,
     pub keep_metadata: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Visit)]
 pub struct EntityMetadata(Vec<EntityMetadatum>);
 
 impl Serializable for EntityMetadata {
@@ -2578,13 +2679,13 @@ impl Serializable for EntityMetadata {
     }
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct EntityMetadatum {
     pub index: u8,
     pub value: EntityMetadatumValue,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum EntityMetadatumValue {
     // 0
@@ -2632,13 +2733,13 @@ pub enum EntityMetadatumValue {
     ResolvableProfile(ResolvableProfile),
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct GlobalPosition {
     pub identifier: Identifier,
     pub position: Position,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Visit)]
 pub struct EntityEquipment {
     pub equipment: Vec<EquipmentEntry>,
 }
@@ -2648,11 +2749,9 @@ impl Serializable for EntityEquipment {
         let mut equipment: Vec<EquipmentEntry> = Vec::new();
 
         loop {
-            let slot = u8::read_from(buf)?;
-            if slot & 0x80 == 0 {
-                return Ok(EntityEquipment { equipment });
-            }
-
+            // the top bit is set on every entry but the last
+            let byte = u8::read_from(buf)?;
+            let slot = byte & 0x7f;
             let equipment_slot = match slot {
                 0 => EquipmentSlot::MainHand,
                 1 => EquipmentSlot::Offhand,
@@ -2675,35 +2774,43 @@ impl Serializable for EntityEquipment {
                 slot: equipment_slot,
                 item,
             });
+            if byte & 0x80 == 0 {
+                return Ok(EntityEquipment { equipment });
+            }
         }
     }
 
     fn write_to<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
-        for equipment_entry in &self.equipment {
-            match equipment_entry.slot {
-                EquipmentSlot::MainHand => buf.write_i8(0)?,
-                EquipmentSlot::Offhand => buf.write_i8(1)?,
-                EquipmentSlot::Boots => buf.write_i8(2)?,
-                EquipmentSlot::Leggings => buf.write_i8(3)?,
-                EquipmentSlot::Chestplate => buf.write_i8(4)?,
-                EquipmentSlot::Helmet => buf.write_i8(5)?,
-                EquipmentSlot::Body => buf.write_i8(6)?,
-            }
+        for (i, equipment_entry) in self.equipment.iter().enumerate() {
+            let slot: u8 = match equipment_entry.slot {
+                EquipmentSlot::MainHand => 0,
+                EquipmentSlot::Offhand => 1,
+                EquipmentSlot::Boots => 2,
+                EquipmentSlot::Leggings => 3,
+                EquipmentSlot::Chestplate => 4,
+                EquipmentSlot::Helmet => 5,
+                EquipmentSlot::Body => 6,
+            };
+            let more = if i + 1 < self.equipment.len() {
+                0x80
+            } else {
+                0
+            };
+            buf.write_u8(slot | more)?;
 
             equipment_entry.item.write_to(buf)?;
         }
-        buf.write_u8(0x80)?;
         Ok(())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Visit)]
 pub struct EquipmentEntry {
     pub slot: EquipmentSlot,
     pub item: Slot,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
 pub enum EquipmentSlot {
     MainHand,
     Offhand,
@@ -2714,7 +2821,7 @@ pub enum EquipmentSlot {
     Body,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(i8, 0)]
 pub enum ObjectiveMode {
     Create(ObjectiveData),
@@ -2722,21 +2829,21 @@ pub enum ObjectiveMode {
     UpdateDisplayText(ObjectiveData),
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct ObjectiveData {
     pub objective_value: TextComponent,
     pub ty: ObjectiveType,
     pub number_format: Option<ObjectiveNumberFormat>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ObjectiveType {
     Integer,
     Hearts,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ObjectiveNumberFormat {
     Blank,
@@ -2744,7 +2851,7 @@ pub enum ObjectiveNumberFormat {
     Fixed { content: TextComponent },
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(i8, 0)]
 pub enum TeamMethod {
     Create {
@@ -2757,7 +2864,7 @@ pub enum TeamMethod {
     RemoveEntities(PrefixedArray<String>),
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct TeamInfo {
     pub team_display_name: TextComponent,
     pub friendly_flags: TeamFriendlyFlags,
@@ -2768,14 +2875,14 @@ pub struct TeamInfo {
     pub team_suffix: TextComponent,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[bitfields(u8)]
 pub struct TeamFriendlyFlags {
     pub allow_friendly_fire: bool,
     pub can_see_invisible_players: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
 pub struct StopSoundData {
     pub source: Option<VarInt>,
     pub sound: Option<Identifier>,
@@ -2815,13 +2922,13 @@ impl Serializable for StopSoundData {
     }
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct AdvancementMapping {
     pub key: Identifier,
     pub value: Advancement,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct Advancement {
     pub parent_id: Option<Identifier>,
     pub display_data: Option<AdvancementDisplay>,
@@ -2829,7 +2936,7 @@ pub struct Advancement {
     pub sends_telemetry_data: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct AdvancementDisplay {
     pub title: TextComponent,
     pub description: TextComponent,
@@ -2840,7 +2947,7 @@ pub struct AdvancementDisplay {
     pub y_coord: f32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
 pub struct AdvancementDisplayFlags {
     pub flags: i32,
     pub background_texture: Option<Identifier>,
@@ -2868,75 +2975,75 @@ impl Serializable for AdvancementDisplayFlags {
     }
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct ProgressMapping {
     pub key: Identifier,
     pub value: AdvancementProgress,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct AdvancementProgress {
     pub crtieria: PrefixedArray<AdvancementProgressCriterion>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct AdvancementProgressCriterion {
     pub identifier: Identifier,
     ///number of milliseconds since January 1, 1970, 00:00:00 GMT
     pub date_of_achieving: Option<i64>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct EntityProperty {
     pub id: VarInt,
     pub value: f64,
     pub modifiers: PrefixedArray<ModifierData>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct ModifierData {
     pub id: Identifier,
     pub amount: f64,
     pub operation: i8,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct PropertySet {
     pub id: Identifier,
     pub items: PrefixedArray<VarInt>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct StonecutterRecipe {
     pub ingredients: IdSet,
     pub slot_display: SlotDisplay,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct ReigstryToTags {
     pub registry: Identifier,
     pub tags: PrefixedArray<Tag>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct CustomReportDetail {
     pub title: String,
     pub description: String,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct ArgumentSignature {
     pub argument_name: String,
     pub signature: StaticLenBytes<256>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct ChangedSlot {
     pub slot_number: i16,
     pub slot_data: HashedSlot,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum InteractionType {
     Interact,
@@ -2944,14 +3051,14 @@ pub enum InteractionType {
     InteractAt { target: Vec3<f32>, hand: Hand },
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[bitfields(u8)]
 pub struct MovePlayerFlags {
     pub on_ground: bool,
     pub pushing_against_wall: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[bitfields(u8)]
 pub struct PlayerInput {
     pub forward: bool,
@@ -2963,21 +3070,21 @@ pub struct PlayerInput {
     pub sprint: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum SeenAdvancementsAction {
     OpenedTab { tab_id: Identifier },
     ClosedScreen,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct GameProfile {
     pub uuid: UUID,
     pub username: String,
     pub properties: PrefixedArray<ProfileProperty>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum DebugSubscriptionUpdate {
     DedicatedServerTickTime,
@@ -2998,7 +3105,7 @@ pub enum DebugSubscriptionUpdate {
     GameEvent(GameEventDebugData),
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct DebugBeeData {
     pub hive_pos: Option<Position>,
     pub flower_pos: Option<Position>,
@@ -3006,7 +3113,7 @@ pub struct DebugBeeData {
     pub blacklisted_hives: PrefixedArray<Position>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct VillagerBrianDebugData {
     pub name: String,
     pub profession: String,
@@ -3024,20 +3131,20 @@ pub struct VillagerBrianDebugData {
     pub potential_pois: PrefixedArray<Position>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct BreezeDebugData {
     pub attack_target: Option<VarInt>,
     pub jump_target: Option<VarInt>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct GoalSelectorDebugData {
     pub priority: VarInt,
     pub is_running: bool,
     pub name: String,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct EntityPathDebugData {
     pub reached: bool,
     pub next_block_index: i32,
@@ -3049,7 +3156,7 @@ pub struct EntityPathDebugData {
     pub max_node_distance: f32,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct DebugPathNode {
     pub pos: Vec3<i32>,
     pub walked_distance: f32,
@@ -3059,12 +3166,12 @@ pub struct DebugPathNode {
     pub f: f32,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct EntityBlockIntersectionDebugData {
     pub id: VarInt, // TODO: enum
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct BeeHiveDebugData {
     pub ty: VarInt,
     pub occupant_count: VarInt,
@@ -3072,59 +3179,59 @@ pub struct BeeHiveDebugData {
     pub sedated: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct PoiDebugData {
     pub position: Position,
     pub ty: VarInt,
     pub free_ticket_count: VarInt,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct RedstoneWireOrientationDebugData {
     pub id: VarInt,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct RaidDebugData {
     pub positions: PrefixedArray<Position>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct StructureDebugData {
     pub structures: PrefixedArray<DebugStructureInfo>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct DebugStructureInfo {
     pub bounding_box_min: Position,
     pub bounding_box_max: Position,
     pub pieces: PrefixedArray<StructurePiece>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct StructurePiece {
     pub bounding_box_min: Position,
     pub bounding_box_max: Position,
     pub is_start: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct GameEventListenerDebugData {
     pub listener_radius: VarInt,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct NeighborUpdateDebugData {
     pub position: Position,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct GameEventDebugData {
     pub event: VarInt,
     pub pos: Vec3<f64>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct BlockParticleAlternative {
     pub particle: Particle,
     pub scaling: f32,
@@ -3132,7 +3239,7 @@ pub struct BlockParticleAlternative {
     pub weight: VarInt,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct ResolvableProfile {
     pub unpack: ResolvableProfileUnpack,
     // TODO: for the following 4 wikivg says "Optional", not "Prefixed Optional". Investigate
@@ -3142,7 +3249,7 @@ pub struct ResolvableProfile {
     pub model: Option<VarInt>, // TODO: enum
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ResolvableProfileUnpack {
     Partial {
@@ -3153,14 +3260,14 @@ pub enum ResolvableProfileUnpack {
     Complete(GameProfile),
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(bool, 0)]
 pub enum XorY<X: Serializable, Y: Serializable> {
     Y(Y),
     X(X),
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum WaypointData {
     Empty,
@@ -3169,9 +3276,9 @@ pub enum WaypointData {
     Azimuth { angle: f32 },
 }
 
-// todo: gotta rename this to a normal name, also check if it actually works cause i never tried it
-#[derive(Debug)]
-pub struct LpVec3(Vec3<f64>);
+/// Velocity packed into 6 bytes: a scale and three 15 bit components between -1 and 1
+#[derive(Debug, Clone, PartialEq, Visit)]
+pub struct LpVec3(pub Vec3<f64>);
 
 impl LpVec3 {
     fn clamp(val: f64, min: f64, max: f64) -> f64 {
@@ -3184,8 +3291,9 @@ impl LpVec3 {
         val
     }
 
-    fn has_fast_marker_bit(max_directional_velocity: u32) -> bool {
-        return (max_directional_velocity & 4) == 4;
+    /// The scale doesn't fit in the 2 bits of the first byte and continues in a VarInt
+    fn has_continuation_bit(first_byte: u8) -> bool {
+        (first_byte & 4) == 4
     }
 
     fn clamp_value(value: f64) -> f64 {
@@ -3197,7 +3305,15 @@ impl LpVec3 {
     }
 
     fn abs_max(a: f64, b: f64) -> f64 {
-        if a.abs() > b.abs() { a } else { b }
+        a.abs().max(b.abs())
+    }
+
+    fn pack(value: f64) -> u64 {
+        ((value * 0.5 + 0.5) * 32766.0).round() as u64
+    }
+
+    fn unpack(value: u64) -> f64 {
+        ((value & 32767) as f64).min(32766.0) * 2.0 / 32766.0 - 1.0
     }
 }
 
@@ -3216,14 +3332,14 @@ impl Serializable for LpVec3 {
         let l = buf.read_u32::<BigEndian>()?;
         let m: u64 = (l as u64) << 16 | (j as u64) << 8 | i as u64;
         let mut n: u64 = i as u64 & 3;
-        if Self::has_fast_marker_bit(i as u32) {
+        if Self::has_continuation_bit(i) {
             n |= (VarInt::read_from(buf)?.0 as u64 & 4294967295u64) << 2;
         }
 
         Ok(Self(Vec3 {
-            x: (m >> 3) as f64 * n as f64,
-            y: (m >> 18) as f64 * n as f64,
-            z: (m >> 33) as f64 * n as f64,
+            x: Self::unpack(m >> 3) * n as f64,
+            y: Self::unpack(m >> 18) * n as f64,
+            z: Self::unpack(m >> 33) * n as f64,
         }))
     }
     fn write_to<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
@@ -3237,9 +3353,9 @@ impl Serializable for LpVec3 {
             let l = g.ceil() as u64;
             let bl = (l & 3u64) != l;
             let m: u64 = if bl { l & 3u64 | 4u64 } else { l };
-            let n = ((d / l as f64) as u64) << 3;
-            let o = ((e / l as f64) as u64) << 18;
-            let p = ((f / l as f64) as u64) << 33;
+            let n = Self::pack(d / l as f64) << 3;
+            let o = Self::pack(e / l as f64) << 18;
+            let p = Self::pack(f / l as f64) << 33;
             let q: u64 = m | n | o | p;
             buf.write_u8(q as u8)?;
             buf.write_u8((q >> 8) as u8)?;
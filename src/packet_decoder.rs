@@ -67,7 +67,12 @@ impl<R: Read> NetworkDecoder<R> {
             return Err(PacketDecodeError::OutOfBounds);
         }
 
-        let mut bounded_reader = (&mut self.reader).take(packet_len);
+        // the whole body, a stream cut off inside the packet is an `UnexpectedEof`
+        let mut body = vec![0; packet_len as usize];
+        self.reader
+            .read_exact(&mut body)
+            .map_err(crate::Error::from)?;
+        let mut bounded_reader = &body[..];
 
         let mut reader = if let Some(threshold) = self.compression {
             let decompressed_length = VarInt::read_from(&mut bounded_reader)?;
@@ -84,7 +84,7 @@ impl<W: Write> NetworkEncoder<W> {
     /// -   `Data Length`: (Only present in compressed packets) The length of the uncompressed `Packet ID` and `Data`.
     /// -   `Packet ID`: The ID of the packet.
     /// -   `Data`: The packet's data.
-    pub async fn write_packet(&mut self, packet_data: &[u8]) -> Result<(), PacketEncodeError> {
+    pub fn write_packet(&mut self, packet_data: &[u8]) -> Result<(), PacketEncodeError> {
         let data_len = packet_data.len();
         if data_len > MAX_PACKET_DATA_SIZE {
             return Err(PacketEncodeError::TooLong(data_len));
@@ -189,7 +189,7 @@ impl<W: Write> NetworkEncoder<W> {
                 .write_to(&mut self.writer)
                 .map_err(|err| PacketEncodeError::Message(err.to_string()))?;
             self.writer
-                .write_all(&packet_data)
+                .write_all(packet_data)
                 .map_err(|err| PacketEncodeError::Message(err.to_string()))?;
         }
 
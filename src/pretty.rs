@@ -0,0 +1,454 @@
+//! Compact, human-readable rendering of packets and other visitable values.
+//!
+//! `Debug` prints everything, which makes packets like `ChunkDataAndUpdateLight` unreadable.
+//! [`pretty`] prints positions as `x,y,z`, angles in degrees, text components as their plain
+//! text, NBT as SNBT and bytes as a hex preview, and cuts long values off. Use `{:#}` for one
+//! field per line.
+
+use std::fmt::{self, Display, Write};
+
+use crate::{
+    nbt::Tag,
+    packet::Packet,
+    visit::{Value, Visit, Visitor},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct PrettyOptions {
+    /// Nesting depth below which structs and sequences are shown as `{…}`/`[…]`
+    pub max_depth: usize,
+    /// Elements shown per sequence
+    pub max_items: usize,
+    /// Bytes shown in the hex preview of byte arrays
+    pub max_bytes: usize,
+    /// Characters shown of a string, SNBT or JSON value
+    pub max_len: usize,
+}
+
+impl PrettyOptions {
+    pub fn new() -> Self {
+        PrettyOptions {
+            max_depth: 8,
+            max_items: 16,
+            max_bytes: 16,
+            max_len: 120,
+        }
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Displays the wrapped value, see the module docs
+pub struct Pretty<'a, T: Visit + ?Sized> {
+    value: &'a T,
+    options: PrettyOptions,
+}
+
+pub fn pretty<T: Visit + ?Sized>(value: &T) -> Pretty<'_, T> {
+    Pretty {
+        value,
+        options: PrettyOptions::new(),
+    }
+}
+
+impl<T: Visit + ?Sized> Pretty<'_, T> {
+    pub fn with_options(mut self, options: PrettyOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl<T: Visit + ?Sized> Display for Pretty<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut builder = TreeBuilder {
+            options: self.options,
+            stack: Vec::new(),
+            root: None,
+        };
+        self.value.visit(&mut builder);
+        let Some(root) = builder.root else {
+            return Ok(());
+        };
+        let mut out = String::new();
+        root.render(&mut out, f.alternate().then_some(0));
+        f.write_str(&out)
+    }
+}
+
+/// `LoginSuccess { profile: { uuid: 069a79f4-44e9-4726-a5be-fca90e38aaf5, … } }`
+impl Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&pretty(self), f)
+    }
+}
+
+enum Node {
+    Leaf(String),
+    /// Structs and enum variants. Only the root struct and variants are named, tuple-like ones
+    /// have fields named `0`, `1`, …
+    Fields {
+        name: Option<&'static str>,
+        fields: Vec<(String, Node)>,
+    },
+    Seq {
+        items: Vec<Node>,
+        more: usize,
+    },
+    /// Nested deeper than `max_depth`
+    Elided(&'static str),
+}
+
+impl Node {
+    /// `indent` is the current indentation when every field goes on its own line
+    fn render(&self, out: &mut String, indent: Option<usize>) {
+        match self {
+            Node::Leaf(val) => out.push_str(val),
+            Node::Elided(val) => out.push_str(val),
+            Node::Fields { name, fields } => {
+                if let Some(name) = name {
+                    out.push_str(name);
+                    if fields.is_empty() {
+                        return;
+                    }
+                } else if fields.is_empty() {
+                    out.push_str("{}");
+                    return;
+                }
+                let tuple = fields.iter().all(|(key, _)| key.parse::<usize>().is_ok());
+                if tuple {
+                    out.push('(');
+                    for (i, (_, node)) in fields.iter().enumerate() {
+                        if i > 0 {
+                            out.push_str(", ");
+                        }
+                        node.render(out, indent);
+                    }
+                    out.push(')');
+                    return;
+                }
+                if name.is_some() {
+                    out.push(' ');
+                }
+                match indent {
+                    Some(indent) => {
+                        out.push_str("{\n");
+                        for (key, node) in fields {
+                            push_indent(out, indent + 1);
+                            let _ = write!(out, "{}: ", key);
+                            node.render(out, Some(indent + 1));
+                            out.push_str(",\n");
+                        }
+                        push_indent(out, indent);
+                        out.push('}');
+                    }
+                    None => {
+                        out.push_str("{ ");
+                        for (i, (key, node)) in fields.iter().enumerate() {
+                            if i > 0 {
+                                out.push_str(", ");
+                            }
+                            let _ = write!(out, "{}: ", key);
+                            node.render(out, None);
+                        }
+                        out.push_str(" }");
+                    }
+                }
+            }
+            Node::Seq { items, more } => {
+                // sequences of plain values stay on one line
+                let nested = items.iter().any(|item| !matches!(item, Node::Leaf(_)));
+                let indent = indent.filter(|_| nested);
+                out.push('[');
+                for (i, node) in items.iter().enumerate() {
+                    match indent {
+                        Some(indent) => {
+                            out.push('\n');
+                            push_indent(out, indent + 1);
+                        }
+                        None if i > 0 => out.push_str(", "),
+                        None => {}
+                    }
+                    node.render(out, indent.map(|indent| indent + 1));
+                    if indent.is_some() {
+                        out.push(',');
+                    }
+                }
+                if *more > 0 {
+                    match indent {
+                        Some(indent) => {
+                            out.push('\n');
+                            push_indent(out, indent + 1);
+                        }
+                        None if !items.is_empty() => out.push_str(", "),
+                        None => {}
+                    }
+                    let _ = write!(out, "… {} more", more);
+                }
+                if let Some(indent) = indent {
+                    out.push('\n');
+                    push_indent(out, indent);
+                }
+                out.push(']');
+            }
+        }
+    }
+}
+
+fn push_indent(out: &mut String, indent: usize) {
+    for _ in 0..indent {
+        out.push_str("    ");
+    }
+}
+
+struct Frame {
+    node: Node,
+    /// Field name the node goes in, for fields of structs
+    key: String,
+    /// Past `max_depth` or `max_items`: events are dropped until the frame ends
+    hidden: bool,
+}
+
+struct TreeBuilder {
+    options: PrettyOptions,
+    stack: Vec<Frame>,
+    root: Option<Node>,
+}
+
+impl TreeBuilder {
+    fn hidden(&self) -> bool {
+        self.stack.last().is_some_and(|frame| frame.hidden)
+    }
+
+    /// Whether the next value is an element past `max_items`, which only gets counted
+    fn over_limit(&mut self) -> bool {
+        let max_items = self.options.max_items;
+        match self.stack.last_mut() {
+            Some(Frame {
+                node: Node::Seq { items, more },
+                ..
+            }) if items.len() >= max_items => {
+                *more += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn push(&mut self, node: Node) {
+        match self.stack.last_mut() {
+            Some(Frame {
+                node: Node::Fields { fields, .. },
+                key,
+                ..
+            }) => fields.push((std::mem::take(key), node)),
+            Some(Frame {
+                node: Node::Seq { items, .. },
+                ..
+            }) => items.push(node),
+            Some(_) => {}
+            None => self.root = Some(node),
+        }
+    }
+
+    fn begin(&mut self, node: Node) {
+        let hidden =
+            self.hidden() || self.over_limit() || self.stack.len() >= self.options.max_depth;
+        self.stack.push(Frame {
+            node,
+            key: String::new(),
+            hidden,
+        });
+    }
+
+    fn truncate(&self, val: String) -> String {
+        if val.chars().count() <= self.options.max_len {
+            return val;
+        }
+        let mut short: String = val.chars().take(self.options.max_len).collect();
+        short.push('…');
+        short
+    }
+
+    fn leaf(&self, value: Value<'_>) -> String {
+        match value {
+            Value::None => "None".to_owned(),
+            Value::Bool(val) => val.to_string(),
+            Value::I64(val) => val.to_string(),
+            Value::U64(val) => val.to_string(),
+            Value::F32(val) => val.to_string(),
+            Value::F64(val) => val.to_string(),
+            Value::Str(val) => self.truncate(format!("{:?}", val)),
+            Value::Bytes(bytes) => {
+                let mut hex = bytes
+                    .iter()
+                    .take(self.options.max_bytes)
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if bytes.len() > self.options.max_bytes {
+                    hex.push_str(" …");
+                }
+                if bytes.is_empty() {
+                    "<0 bytes>".to_owned()
+                } else {
+                    format!("<{} bytes: {}>", bytes.len(), hex)
+                }
+            }
+            Value::Uuid(uuid) => uuid.to_string(),
+            Value::Json(json) => match json_plain_text(json) {
+                Some(text) => self.truncate(format!("{:?}", text)),
+                None => self.truncate(json.to_string()),
+            },
+            Value::Position { x, y, z } => format!("{},{},{}", x, y, z),
+            Value::Angle(degrees) => format!("{}°", degrees),
+            Value::Nbt(tag) => match nbt_plain_text(tag) {
+                Some(text) => self.truncate(format!("{:?}", text)),
+                None => self.truncate(tag.to_string()),
+            },
+        }
+    }
+}
+
+impl Visitor for TreeBuilder {
+    fn value(&mut self, value: Value<'_>) {
+        if self.hidden() || self.over_limit() {
+            return;
+        }
+        let leaf = self.leaf(value);
+        self.push(Node::Leaf(leaf));
+    }
+
+    fn begin_struct(&mut self, name: &'static str) {
+        let name = self.stack.is_empty().then_some(name);
+        self.begin(Node::Fields {
+            name,
+            fields: Vec::new(),
+        });
+    }
+
+    fn begin_variant(&mut self, _name: &'static str, variant: &'static str) {
+        self.begin(Node::Fields {
+            name: Some(variant),
+            fields: Vec::new(),
+        });
+    }
+
+    fn begin_seq(&mut self, _len: usize) {
+        self.begin(Node::Seq {
+            items: Vec::new(),
+            more: 0,
+        });
+    }
+
+    fn field(&mut self, name: &str) {
+        if let Some(frame) = self.stack.last_mut() {
+            frame.key = name.to_owned();
+        }
+    }
+
+    fn end(&mut self) {
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+        if self.hidden() {
+            return;
+        }
+        if !frame.hidden {
+            self.push(frame.node);
+            return;
+        }
+        // hidden because of the depth limit, elements past `max_items` were already counted
+        let past_limit = self.stack.last().is_some_and(|parent| {
+            matches!(&parent.node, Node::Seq { items, .. } if items.len() >= self.options.max_items)
+        });
+        if !past_limit {
+            let elided = match frame.node {
+                Node::Seq { .. } => "[…]",
+                _ => "{…}",
+            };
+            self.push(Node::Elided(elided));
+        }
+    }
+}
+
+/// Text of an NBT text component: a compound with `text` or `translate`, with its `extra`
+fn nbt_plain_text(tag: &Tag) -> Option<String> {
+    fn append(tag: &Tag, out: &mut String) {
+        match tag {
+            Tag::String(text) => out.push_str(text),
+            Tag::Compound(_) => {
+                if let Some(text) = tag.get("text").and_then(Tag::as_str) {
+                    out.push_str(text);
+                } else if let Some(key) = tag.get("translate").and_then(Tag::as_str) {
+                    let fallback = tag.get("fallback").and_then(Tag::as_str);
+                    out.push_str(fallback.unwrap_or(key));
+                }
+                for extra in tag.get("extra").and_then(Tag::as_list).unwrap_or_default() {
+                    append(extra, out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if tag.get("text").is_none() && tag.get("translate").is_none() {
+        return None;
+    }
+    let mut out = String::new();
+    append(tag, &mut out);
+    Some(out)
+}
+
+/// Text of a JSON text component, like `nbt_plain_text`
+fn json_plain_text(json: &serde_json::Value) -> Option<String> {
+    fn append(json: &serde_json::Value, out: &mut String) {
+        match json {
+            serde_json::Value::String(text) => out.push_str(text),
+            serde_json::Value::Array(parts) => parts.iter().for_each(|part| append(part, out)),
+            serde_json::Value::Object(object) => {
+                if let Some(text) = object.get("text").and_then(|text| text.as_str()) {
+                    out.push_str(text);
+                } else if let Some(key) = object.get("translate").and_then(|key| key.as_str()) {
+                    let fallback = object.get("fallback").and_then(|text| text.as_str());
+                    out.push_str(fallback.unwrap_or(key));
+                }
+                if let Some(extra) = object.get("extra") {
+                    append(extra, out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let object = json.as_object()?;
+    if !object.contains_key("text") && !object.contains_key("translate") {
+        return None;
+    }
+    let mut out = String::new();
+    append(json, &mut out);
+    Some(out)
+}
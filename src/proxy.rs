@@ -0,0 +1,512 @@
+use std::{
+    io::{self, BufReader, BufWriter},
+    net::{Shutdown, TcpStream},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+};
+
+use rsa::{
+    Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey,
+    pkcs8::{DecodePublicKey, EncodePublicKey},
+    rand_core::{OsRng, RngCore},
+};
+use thiserror::Error;
+
+use crate::{
+    CompressionLevel, CompressionThreshold, LenPrefixedBytes, RawPacket, Serializable, VarInt,
+    packet::{
+        self, Direction, Intent, Packet, PacketType, State,
+        c2s::{handshake::Handshake, login::EncryptionResponse},
+        s2c::login::{EncryptionRequest, SetCompression},
+    },
+    packet_decoder::{NetworkDecoder, PacketDecodeError},
+    packet_encoder::{NetworkEncoder, PacketEncodeError},
+};
+
+type Decoder = NetworkDecoder<BufReader<TcpStream>>;
+type Encoder = NetworkEncoder<BufWriter<TcpStream>>;
+
+/// Compression level used when a leg has compression enabled
+const COMPRESSION_LEVEL: CompressionLevel = 6;
+
+#[derive(Error, Debug)]
+pub enum ProxyError {
+    #[error("Io Error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("failed to decode packet: {0}")]
+    DecodeError(#[from] PacketDecodeError),
+    #[error("failed to encode packet: {0}")]
+    EncodeError(#[from] PacketEncodeError),
+    #[error("serialize error: {0}")]
+    SerializeError(#[from] crate::Error),
+    #[error("rsa error: {0}")]
+    RsaError(#[from] rsa::Error),
+    #[error("invalid public key: {0}")]
+    InvalidKey(String),
+    #[error("the client sent a wrong verify token")]
+    VerifyTokenMismatch,
+    #[error("unexpected packet: {0}")]
+    UnexpectedPacket(String),
+}
+
+/// What happens to an intercepted packet
+#[derive(Debug)]
+pub enum Verdict {
+    /// Forward the packet (the original bytes, unless an earlier interceptor replaced it)
+    Pass,
+    /// Forward this packet instead, the following interceptors see the replacement
+    Replace(Box<Packet>),
+    /// Don't forward anything, the following interceptors aren't called
+    Drop,
+}
+
+pub struct InterceptContext {
+    /// Direction the intercepted packet is travelling in
+    pub direction: Direction,
+    /// State the packet was decoded in
+    pub state: State,
+    injected: Vec<(Direction, Packet)>,
+}
+
+impl InterceptContext {
+    /// Queues a packet, it is sent after the intercepted packet has been forwarded (or dropped)
+    pub fn inject(&mut self, direction: Direction, packet: Packet) {
+        self.injected.push((direction, packet));
+    }
+}
+
+/// Callback that sees every relayed packet, in both directions.
+///
+/// Packets the proxy consumes itself (the upstream leg's `EncryptionRequest` and
+/// `SetCompression`) and packets that fail to decode are never intercepted.
+pub trait Interceptor: Send {
+    fn intercept(&mut self, ctx: &mut InterceptContext, packet: &Packet) -> Verdict;
+}
+
+impl<F: FnMut(&mut InterceptContext, &Packet) -> Verdict + Send> Interceptor for F {
+    fn intercept(&mut self, ctx: &mut InterceptContext, packet: &Packet) -> Verdict {
+        self(ctx, packet)
+    }
+}
+
+/// Sends packets to either side of a running session, from any thread
+#[derive(Clone)]
+pub struct Injector {
+    client: Arc<Mutex<Encoder>>,
+    server: Arc<Mutex<Encoder>>,
+}
+
+impl Injector {
+    /// `Direction::Clientbound` sends to the client, `Direction::Serverbound` to the server
+    pub fn send(&self, direction: Direction, packet: &Packet) -> Result<(), ProxyError> {
+        let mut buf = Vec::new();
+        packet.write(&mut buf)?;
+        self.encoder(direction).lock().unwrap().write_packet(&buf)?;
+        Ok(())
+    }
+
+    fn send_raw(&self, direction: Direction, raw: &RawPacket) -> Result<(), ProxyError> {
+        let mut buf = Vec::new();
+        VarInt(raw.id).write_to(&mut buf)?;
+        buf.extend_from_slice(&raw.payload);
+        self.encoder(direction).lock().unwrap().write_packet(&buf)?;
+        Ok(())
+    }
+
+    fn encoder(&self, direction: Direction) -> &Mutex<Encoder> {
+        match direction {
+            Direction::Clientbound => &self.client,
+            Direction::Serverbound => &self.server,
+        }
+    }
+}
+
+/// Man in the middle proxy for a single client connection.
+///
+/// The client side leg (client <-> proxy) and the server side leg (proxy <-> server) do their
+/// own login: each has its own encryption key and compression threshold, and the proxy
+/// re-encodes every packet for the other leg. Configuration and play traffic (and the rest of
+/// the login) is relayed through the interceptor chain.
+///
+/// NOTE: the proxy can't authenticate with Mojang, so the upstream server has to be in offline
+/// mode and the client leg always sends `should_authenticate: false`
+///
+/// ```ignore
+/// let listener = TcpListener::bind("127.0.0.1:25566")?;
+/// for client in listener.incoming() {
+///     let proxy = Proxy::new("localhost", 25565).interceptor(|ctx: &mut InterceptContext, packet: &Packet| {
+///         println!("{:?} {:?}", ctx.direction, packet);
+///         Verdict::Pass
+///     });
+///     thread::spawn(move || proxy.handle_client(client?));
+/// }
+/// ```
+pub struct Proxy {
+    upstream_host: String,
+    upstream_port: u16,
+    key: Option<Arc<RsaPrivateKey>>,
+    compression_threshold: Option<CompressionThreshold>,
+    interceptors: Vec<Box<dyn Interceptor>>,
+}
+
+impl Proxy {
+    pub fn new(upstream_host: &str, upstream_port: u16) -> Self {
+        Proxy {
+            upstream_host: upstream_host.to_owned(),
+            upstream_port,
+            key: None,
+            compression_threshold: None,
+            interceptors: Vec::new(),
+        }
+    }
+
+    /// Encrypts the client leg with `key`, see `generate_key`.
+    /// The key can be shared between many proxies, generating one is slow.
+    pub fn encryption(mut self, key: Arc<RsaPrivateKey>) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Compresses the client leg
+    pub fn compression_threshold(mut self, threshold: CompressionThreshold) -> Self {
+        self.compression_threshold = Some(threshold);
+        self
+    }
+
+    /// Appends an interceptor to the chain, interceptors run in the order they were added
+    pub fn interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors.push(Box::new(interceptor));
+        self
+    }
+
+    /// Runs the client leg's login, connects upstream and relays until either side disconnects
+    pub fn handle_client(self, client: TcpStream) -> Result<(), ProxyError> {
+        self.connect(client)?.run()
+    }
+
+    /// Runs the client leg's login (up to encryption and compression) and connects upstream,
+    /// without relaying anything yet
+    pub fn connect(self, client: TcpStream) -> Result<ProxySession, ProxyError> {
+        client.set_nodelay(true)?;
+        let mut client_decoder = NetworkDecoder::new(BufReader::new(client.try_clone()?));
+        let mut client_encoder = NetworkEncoder::new(BufWriter::new(client.try_clone()?));
+
+        let handshake = match read(
+            &mut client_decoder,
+            State::Handshake,
+            Direction::Serverbound,
+        )? {
+            Packet::Handshake(handshake) => handshake,
+            other => return Err(ProxyError::UnexpectedPacket(format!("{:?}", other))),
+        };
+        let state = State::from(handshake.intent);
+
+        let login_start = match handshake.intent {
+            Intent::Status => None,
+            Intent::Login | Intent::Transfer => {
+                let login_start = read(&mut client_decoder, State::Login, Direction::Serverbound)?;
+                if !matches!(login_start, Packet::LoginStart(_)) {
+                    return Err(ProxyError::UnexpectedPacket(format!("{:?}", login_start)));
+                }
+                if let Some(key) = &self.key {
+                    encrypt_client(&mut client_decoder, &mut client_encoder, key)?;
+                }
+                if let Some(threshold) = self.compression_threshold {
+                    send(
+                        &mut client_encoder,
+                        &SetCompression {
+                            theshold: VarInt(threshold as i32),
+                        },
+                    )?;
+                    client_encoder.set_compression((threshold, COMPRESSION_LEVEL));
+                    client_decoder.set_compression(threshold);
+                }
+                Some(login_start)
+            }
+        };
+
+        let server = TcpStream::connect((self.upstream_host.as_str(), self.upstream_port))?;
+        server.set_nodelay(true)?;
+        let server_decoder = NetworkDecoder::new(BufReader::new(server.try_clone()?));
+        let mut server_encoder = NetworkEncoder::new(BufWriter::new(server.try_clone()?));
+
+        send(
+            &mut server_encoder,
+            &Handshake {
+                protocol_version: handshake.protocol_version,
+                server_adress: self.upstream_host.clone(),
+                server_port: self.upstream_port,
+                intent: handshake.intent,
+            },
+        )?;
+        if let Some(login_start) = login_start {
+            let mut buf = Vec::new();
+            login_start.write(&mut buf)?;
+            server_encoder.write_packet(&buf)?;
+        }
+
+        Ok(ProxySession {
+            client,
+            server,
+            client_decoder,
+            server_decoder,
+            state,
+            injector: Injector {
+                client: Arc::new(Mutex::new(client_encoder)),
+                server: Arc::new(Mutex::new(server_encoder)),
+            },
+            interceptors: Arc::new(Mutex::new(self.interceptors)),
+        })
+    }
+}
+
+/// Generates a key for `Proxy::encryption`, vanilla servers use 1024 bit keys
+pub fn generate_key() -> Result<RsaPrivateKey, ProxyError> {
+    Ok(RsaPrivateKey::new(&mut OsRng, 1024)?)
+}
+
+/// A connected client and server, ready to relay
+pub struct ProxySession {
+    client: TcpStream,
+    server: TcpStream,
+    client_decoder: Decoder,
+    server_decoder: Decoder,
+    state: State,
+    injector: Injector,
+    interceptors: Arc<Mutex<Vec<Box<dyn Interceptor>>>>,
+}
+
+impl ProxySession {
+    /// Handle for injecting packets while the session runs
+    pub fn injector(&self) -> Injector {
+        self.injector.clone()
+    }
+
+    /// Relays both directions until either side disconnects
+    pub fn run(self) -> Result<(), ProxyError> {
+        let closing = Arc::new(AtomicBool::new(false));
+        let sockets = [self.client, self.server];
+
+        let clientbound = {
+            let relay = Relay {
+                direction: Direction::Clientbound,
+                state: self.state,
+                decoder: self.server_decoder,
+                injector: self.injector.clone(),
+                interceptors: self.interceptors.clone(),
+            };
+            let closing = closing.clone();
+            let sockets = [sockets[0].try_clone()?, sockets[1].try_clone()?];
+            thread::spawn(move || relay.run(&closing, &sockets))
+        };
+        let serverbound = Relay {
+            direction: Direction::Serverbound,
+            state: self.state,
+            decoder: self.client_decoder,
+            injector: self.injector,
+            interceptors: self.interceptors,
+        }
+        .run(&closing, &sockets);
+
+        let clientbound = clientbound.join().expect("clientbound relay panicked");
+        serverbound.and(clientbound)
+    }
+}
+
+/// One direction of a session
+struct Relay {
+    direction: Direction,
+    state: State,
+    decoder: Decoder,
+    injector: Injector,
+    interceptors: Arc<Mutex<Vec<Box<dyn Interceptor>>>>,
+}
+
+impl Relay {
+    fn run(mut self, closing: &AtomicBool, sockets: &[TcpStream; 2]) -> Result<(), ProxyError> {
+        let result = self.relay();
+        // whoever stops first takes the other direction down with it
+        let already_closing = closing.swap(true, Ordering::SeqCst);
+        for socket in sockets {
+            let _ = socket.shutdown(Shutdown::Both);
+        }
+        match result {
+            Err(err) if !already_closing && !is_disconnect(&err) => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    fn relay(&mut self) -> Result<(), ProxyError> {
+        loop {
+            let raw = self.decoder.get_raw_packet()?;
+            let Ok(packet) =
+                packet::packet_by_id(self.state, self.direction, raw.id, &mut &raw.payload[..])
+            else {
+                // unknown to this crate, nothing to intercept but the other side might know it
+                self.injector.send_raw(self.direction, &raw)?;
+                continue;
+            };
+
+            if self.handle_upstream_login(&packet)? {
+                continue;
+            }
+
+            let mut ctx = InterceptContext {
+                direction: self.direction,
+                state: self.state,
+                injected: Vec::new(),
+            };
+            let mut replacement = None;
+            let mut dropped = false;
+            for interceptor in self.interceptors.lock().unwrap().iter_mut() {
+                match interceptor.intercept(&mut ctx, replacement.as_ref().unwrap_or(&packet)) {
+                    Verdict::Pass => {}
+                    Verdict::Replace(p) => replacement = Some(*p),
+                    Verdict::Drop => {
+                        dropped = true;
+                        break;
+                    }
+                }
+            }
+
+            if !dropped {
+                match &replacement {
+                    Some(replacement) => self.injector.send(self.direction, replacement)?,
+                    None => self.injector.send_raw(self.direction, &raw)?,
+                }
+            }
+            for (direction, packet) in &ctx.injected {
+                self.injector.send(*direction, packet)?;
+            }
+
+            // the state follows what the sender sent, not what was forwarded
+            if let Some(state) = packet.state_transition() {
+                self.state = state;
+            }
+        }
+    }
+
+    /// Encryption and compression of the server leg are the proxy's business,
+    /// returns whether the packet was consumed
+    fn handle_upstream_login(&mut self, packet: &Packet) -> Result<bool, ProxyError> {
+        match packet {
+            Packet::EncryptionRequest(request) => {
+                let public_key = RsaPublicKey::from_public_key_der(&request.public_key.data)
+                    .map_err(|err| ProxyError::InvalidKey(err.to_string()))?;
+                let mut shared_secret = [0u8; 16];
+                OsRng.fill_bytes(&mut shared_secret);
+
+                let response = EncryptionResponse {
+                    shared_secret: LenPrefixedBytes::new(public_key.encrypt(
+                        &mut OsRng,
+                        Pkcs1v15Encrypt,
+                        &shared_secret,
+                    )?),
+                    verify_token: LenPrefixedBytes::new(public_key.encrypt(
+                        &mut OsRng,
+                        Pkcs1v15Encrypt,
+                        &request.verify_token.data,
+                    )?),
+                };
+                // hold the lock so nothing gets sent between the response and the cipher switch
+                let mut server = self.injector.server.lock().unwrap();
+                send(&mut server, &response)?;
+                server.set_encryption(&shared_secret);
+                self.decoder.set_encryption(&shared_secret);
+                Ok(true)
+            }
+            Packet::SetCompression(p) => {
+                // a negative threshold disables compression
+                if let Ok(threshold) = CompressionThreshold::try_from(p.theshold.0) {
+                    self.decoder.set_compression(threshold);
+                    self.injector
+                        .server
+                        .lock()
+                        .unwrap()
+                        .set_compression((threshold, COMPRESSION_LEVEL));
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Server side encryption handshake with the client
+fn encrypt_client(
+    decoder: &mut Decoder,
+    encoder: &mut Encoder,
+    key: &RsaPrivateKey,
+) -> Result<(), ProxyError> {
+    let public_key = key
+        .to_public_key()
+        .to_public_key_der()
+        .map_err(|err| ProxyError::InvalidKey(err.to_string()))?;
+    let mut verify_token = [0u8; 4];
+    OsRng.fill_bytes(&mut verify_token);
+
+    send(
+        encoder,
+        &EncryptionRequest {
+            server_id: String::new(),
+            public_key: LenPrefixedBytes::new(public_key.into_vec()),
+            verify_token: LenPrefixedBytes::new(verify_token.to_vec()),
+            should_authenticate: false,
+        },
+    )?;
+
+    let response = match read(decoder, State::Login, Direction::Serverbound)? {
+        Packet::EncryptionResponse(response) => response,
+        other => return Err(ProxyError::UnexpectedPacket(format!("{:?}", other))),
+    };
+    if key.decrypt(Pkcs1v15Encrypt, &response.verify_token.data)? != verify_token {
+        return Err(ProxyError::VerifyTokenMismatch);
+    }
+    let shared_secret: [u8; 16] = key
+        .decrypt(Pkcs1v15Encrypt, &response.shared_secret.data)?
+        .try_into()
+        .map_err(|_| ProxyError::InvalidKey("shared secret must be 16 bytes".to_owned()))?;
+
+    encoder.set_encryption(&shared_secret);
+    decoder.set_encryption(&shared_secret);
+    Ok(())
+}
+
+fn is_disconnect(err: &ProxyError) -> bool {
+    let err = match err {
+        ProxyError::IoError(err) => err,
+        ProxyError::DecodeError(PacketDecodeError::SerializeError(crate::Error::IoError(err))) => {
+            err
+        }
+        ProxyError::DecodeError(PacketDecodeError::ConnectionClosed) => return true,
+        _ => return false,
+    };
+    matches!(
+        err.kind(),
+        io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::NotConnected
+    )
+}
+
+fn read(decoder: &mut Decoder, state: State, dir: Direction) -> Result<Packet, ProxyError> {
+    let raw = decoder.get_raw_packet()?;
+    Ok(packet::packet_by_id(
+        state,
+        dir,
+        raw.id,
+        &mut &raw.payload[..],
+    )?)
+}
+
+fn send<P: PacketType>(encoder: &mut Encoder, packet: &P) -> Result<(), ProxyError> {
+    let mut buf = Vec::new();
+    packet.write(&mut buf)?;
+    encoder.write_packet(&buf)?;
+    Ok(())
+}
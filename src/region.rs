@@ -0,0 +1,482 @@
+//! Anvil region files (`region/r.<x>.<z>.mca`): the saved chunks of a 32×32 chunk area.
+//!
+//! # Format
+//!
+//! The file is made of 4 KiB sectors, all numbers are big endian.
+//!
+//! ```text
+//! locations     1024 × u32   sector offset << 8 | sector count, 0 if the chunk is missing
+//! timestamps    1024 × u32   unix time of the last save, in seconds
+//! chunks        at their sector offset, padded to whole sectors:
+//!     length    u32          bytes after this field
+//!     type      u8           1: gzip, 2: zlib, 3: uncompressed, 4: LZ4, +128: external
+//!     data      named NBT, compressed
+//! ```
+//!
+//! Entry `x + 32 * z` is the chunk at `x`, `z` within the region. A chunk that needs more than
+//! 255 sectors is external: its data is in `c.<chunk x>.<chunk z>.mcc` next to the region file,
+//! and the region only holds its type.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+};
+use thiserror::Error;
+
+use crate::nbt::Tag;
+
+pub const SECTOR_SIZE: usize = 4096;
+/// Chunks per region side
+pub const REGION_SIZE: i32 = 32;
+const CHUNKS: usize = (REGION_SIZE * REGION_SIZE) as usize;
+/// Larger chunks go to `.mcc` files
+const MAX_SECTORS: usize = 255;
+const EXTERNAL: u8 = 0x80;
+
+#[derive(Error, Debug)]
+pub enum RegionError {
+    #[error("Io Error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("serialize error: {0}")]
+    SerializeError(#[from] crate::Error),
+    #[error("invalid region file: {0}")]
+    InvalidRegion(String),
+    #[error("chunk {0}, {1}: unsupported compression type {2}")]
+    UnsupportedCompression(i32, i32, u8),
+    #[error("chunk {0}, {1}: invalid LZ4 data: {2}")]
+    InvalidLz4(i32, i32, String),
+    #[error("chunk {0}, {1} is external, only `Region::open` and `save` handle .mcc files")]
+    ExternalChunk(i32, i32),
+}
+
+/// How a chunk is compressed, vanilla uses `Zlib` unless configured otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ChunkCompression {
+    Gzip,
+    #[default]
+    Zlib,
+    None,
+    /// lz4-java's block stream format, since 1.20.5
+    Lz4,
+}
+
+impl ChunkCompression {
+    pub fn id(self) -> u8 {
+        match self {
+            ChunkCompression::Gzip => 1,
+            ChunkCompression::Zlib => 2,
+            ChunkCompression::None => 3,
+            ChunkCompression::Lz4 => 4,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(ChunkCompression::Gzip),
+            2 => Some(ChunkCompression::Zlib),
+            3 => Some(ChunkCompression::None),
+            4 => Some(ChunkCompression::Lz4),
+            _ => None,
+        }
+    }
+}
+
+/// A chunk as stored, decompressed when read
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StoredChunk {
+    /// The type byte without the external flag
+    compression: u8,
+    data: Vec<u8>,
+    timestamp: u32,
+    /// Stored in a `.mcc` file
+    external: bool,
+}
+
+/// A region file held in memory. Chunk coordinates can be absolute or within the region, only
+/// their low 5 bits count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Region coordinates, chunk coordinates divided by 32
+    pub x: i32,
+    pub z: i32,
+    /// For chunks set from now on
+    pub compression: ChunkCompression,
+    chunks: Vec<Option<StoredChunk>>,
+}
+
+impl Region {
+    pub fn new(x: i32, z: i32) -> Self {
+        Region {
+            x,
+            z,
+            compression: ChunkCompression::default(),
+            chunks: vec![None; CHUNKS],
+        }
+    }
+
+    pub fn with_compression(mut self, compression: ChunkCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Reads a region file and its external chunks. The region coordinates come from the name
+    /// `r.<x>.<z>.mca`, `0, 0` for other names.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, RegionError> {
+        let path = path.as_ref();
+        let (x, z) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(region_coords)
+            .unwrap_or((0, 0));
+        let mut region = Self::read(File::open(path)?, x, z)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for (i, chunk) in region.chunks.iter_mut().enumerate() {
+            if let Some(chunk) = chunk.as_mut().filter(|chunk| chunk.external) {
+                let (chunk_x, chunk_z) = chunk_coords(x, z, i);
+                chunk.data = fs::read(dir.join(external_name(chunk_x, chunk_z)))?;
+            }
+        }
+        Ok(region)
+    }
+
+    /// Reads a region file at region `x`, `z`. External chunks can't be read, see `open`.
+    pub fn read<R: Read>(mut reader: R, x: i32, z: i32) -> Result<Self, RegionError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut region = Region::new(x, z);
+        // an empty file is an empty region
+        if data.is_empty() {
+            return Ok(region);
+        }
+        if data.len() < 2 * SECTOR_SIZE {
+            return Err(RegionError::InvalidRegion("truncated header".to_owned()));
+        }
+        let u32_at = |pos: usize| u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap());
+        for i in 0..CHUNKS {
+            let location = u32_at(i * 4);
+            if location == 0 {
+                continue;
+            }
+            let (chunk_x, chunk_z) = chunk_coords(x, z, i);
+            let invalid = |message: &str| {
+                RegionError::InvalidRegion(format!("chunk {}, {}: {}", chunk_x, chunk_z, message))
+            };
+            let offset = (location >> 8) as usize * SECTOR_SIZE;
+            let sectors = (location & 0xff) as usize;
+            if offset < 2 * SECTOR_SIZE || offset + 5 > data.len() {
+                return Err(invalid("sector offset outside the file"));
+            }
+            let length = u32_at(offset) as usize;
+            if length == 0 || offset + 4 + length > data.len() {
+                return Err(invalid("length outside the file"));
+            }
+            if 4 + length > sectors * SECTOR_SIZE {
+                return Err(invalid("length larger than its sectors"));
+            }
+            let ty = data[offset + 4];
+            region.chunks[i] = Some(StoredChunk {
+                compression: ty & !EXTERNAL,
+                data: data[offset + 5..offset + 4 + length].to_vec(),
+                timestamp: u32_at(SECTOR_SIZE + i * 4),
+                external: ty & EXTERNAL != 0,
+            });
+        }
+        Ok(region)
+    }
+
+    /// Writes the region file and the `.mcc` files of oversized chunks, removing those of
+    /// chunks that fit again
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RegionError> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_with(&mut writer, |chunk_x, chunk_z, data| {
+            let external = dir.join(external_name(chunk_x, chunk_z));
+            match data {
+                Some(data) => fs::write(external, data)?,
+                None => match fs::remove_file(external) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+                    _ => {}
+                },
+            }
+            Ok(())
+        })?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the region file, chunks in order without gaps. Fails for oversized chunks, which
+    /// need `save`.
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), RegionError> {
+        self.write_with(writer, |chunk_x, chunk_z, data| match data {
+            Some(_) => Err(RegionError::ExternalChunk(chunk_x, chunk_z)),
+            None => Ok(()),
+        })
+    }
+
+    /// `external` gets the data of each oversized chunk, `None` for the chunks that aren't
+    fn write_with<W: Write>(
+        &self,
+        mut writer: W,
+        mut external: impl FnMut(i32, i32, Option<&[u8]>) -> Result<(), RegionError>,
+    ) -> Result<(), RegionError> {
+        let mut header = vec![0; 2 * SECTOR_SIZE];
+        let mut body = Vec::new();
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            let Some(chunk) = chunk else { continue };
+            let (chunk_x, chunk_z) = chunk_coords(self.x, self.z, i);
+            let oversized = (5 + chunk.data.len()).div_ceil(SECTOR_SIZE) > MAX_SECTORS;
+            if chunk.external && chunk.data.is_empty() {
+                return Err(RegionError::ExternalChunk(chunk_x, chunk_z));
+            }
+            let start = body.len();
+            if oversized {
+                external(chunk_x, chunk_z, Some(&chunk.data))?;
+                body.extend_from_slice(&1u32.to_be_bytes());
+                body.push(chunk.compression | EXTERNAL);
+            } else {
+                external(chunk_x, chunk_z, None)?;
+                body.extend_from_slice(&(chunk.data.len() as u32 + 1).to_be_bytes());
+                body.push(chunk.compression);
+                body.extend_from_slice(&chunk.data);
+            }
+            body.resize(body.len().next_multiple_of(SECTOR_SIZE), 0);
+            let offset = 2 + start / SECTOR_SIZE;
+            let sectors = (body.len() - start) / SECTOR_SIZE;
+            if offset >= 1 << 24 {
+                return Err(RegionError::InvalidRegion(
+                    "more than 2^24 sectors".to_owned(),
+                ));
+            }
+            let location = (offset as u32) << 8 | sectors as u32;
+            header[i * 4..i * 4 + 4].copy_from_slice(&location.to_be_bytes());
+            header[SECTOR_SIZE + i * 4..SECTOR_SIZE + i * 4 + 4]
+                .copy_from_slice(&chunk.timestamp.to_be_bytes());
+        }
+        writer.write_all(&header)?;
+        writer.write_all(&body)?;
+        Ok(())
+    }
+
+    /// The chunk's NBT, `None` if it was never saved
+    pub fn chunk(&self, x: i32, z: i32) -> Result<Option<Tag>, RegionError> {
+        let Some(chunk) = &self.chunks[index(x, z)] else {
+            return Ok(None);
+        };
+        let (chunk_x, chunk_z) = chunk_coords(self.x, self.z, index(x, z));
+        if chunk.external && chunk.data.is_empty() {
+            return Err(RegionError::ExternalChunk(chunk_x, chunk_z));
+        }
+        let data = match ChunkCompression::from_id(chunk.compression) {
+            Some(ChunkCompression::Gzip) => decompress(GzDecoder::new(&chunk.data[..]))?,
+            Some(ChunkCompression::Zlib) => decompress(ZlibDecoder::new(&chunk.data[..]))?,
+            Some(ChunkCompression::None) => chunk.data.clone(),
+            Some(ChunkCompression::Lz4) => lz4::decompress(&chunk.data)
+                .map_err(|message| RegionError::InvalidLz4(chunk_x, chunk_z, message))?,
+            None => {
+                return Err(RegionError::UnsupportedCompression(
+                    chunk_x,
+                    chunk_z,
+                    chunk.compression,
+                ));
+            }
+        };
+        let mut buf = &data[..];
+        let (_, tag) = Tag::read_named(&mut buf)?;
+        Ok(Some(tag))
+    }
+
+    /// Compresses the chunk with `compression` and stamps it with the current time
+    pub fn set_chunk(&mut self, x: i32, z: i32, tag: &Tag) -> Result<(), RegionError> {
+        let mut data = Vec::new();
+        tag.write_named(&mut data, "")?;
+        let level = flate2::Compression::default();
+        let data = match self.compression {
+            ChunkCompression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), level);
+                encoder.write_all(&data)?;
+                encoder.finish()?
+            }
+            ChunkCompression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), level);
+                encoder.write_all(&data)?;
+                encoder.finish()?
+            }
+            ChunkCompression::None => data,
+            ChunkCompression::Lz4 => lz4::compress(&data),
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs() as u32);
+        self.chunks[index(x, z)] = Some(StoredChunk {
+            compression: self.compression.id(),
+            data,
+            timestamp,
+            external: false,
+        });
+        Ok(())
+    }
+
+    pub fn remove_chunk(&mut self, x: i32, z: i32) -> bool {
+        self.chunks[index(x, z)].take().is_some()
+    }
+
+    /// Unix time in seconds of the chunk's last save
+    pub fn timestamp(&self, x: i32, z: i32) -> Option<u32> {
+        self.chunks[index(x, z)]
+            .as_ref()
+            .map(|chunk| chunk.timestamp)
+    }
+
+    /// How the chunk is compressed, `None` if it is missing or uses a type this crate can't read
+    pub fn chunk_compression(&self, x: i32, z: i32) -> Option<ChunkCompression> {
+        self.chunks[index(x, z)]
+            .as_ref()
+            .and_then(|chunk| ChunkCompression::from_id(chunk.compression))
+    }
+
+    /// Absolute coordinates of the chunks present, in file order
+    pub fn positions(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| chunk.is_some())
+            .map(|(i, _)| chunk_coords(self.x, self.z, i))
+    }
+
+    /// The NBT of every chunk present, with its absolute coordinates
+    pub fn chunks(&self) -> impl Iterator<Item = (i32, i32, Result<Tag, RegionError>)> + '_ {
+        self.positions().map(|(x, z)| {
+            let tag = self.chunk(x, z).map(|tag| tag.unwrap_or(Tag::End));
+            (x, z, tag)
+        })
+    }
+}
+
+fn index(x: i32, z: i32) -> usize {
+    (x & (REGION_SIZE - 1)) as usize + (z & (REGION_SIZE - 1)) as usize * REGION_SIZE as usize
+}
+
+fn chunk_coords(region_x: i32, region_z: i32, index: usize) -> (i32, i32) {
+    let (x, z) = (index as i32 % REGION_SIZE, index as i32 / REGION_SIZE);
+    (region_x * REGION_SIZE + x, region_z * REGION_SIZE + z)
+}
+
+/// `r.-1.2.mca` is region -1, 2
+fn region_coords(name: &str) -> Option<(i32, i32)> {
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+    let coords = (parts.next()?.parse().ok()?, parts.next()?.parse().ok()?);
+    parts.next().is_none().then_some(coords)
+}
+
+fn external_name(chunk_x: i32, chunk_z: i32) -> String {
+    format!("c.{}.{}.mcc", chunk_x, chunk_z)
+}
+
+fn decompress<R: Read>(mut decoder: R) -> Result<Vec<u8>, io::Error> {
+    let mut data = Vec::new();
+    decoder.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// lz4-java's `LZ4BlockOutputStream` format, which vanilla uses rather than LZ4 frames
+mod lz4 {
+    use twox_hash::XxHash32;
+
+    const MAGIC: &[u8; 8] = b"LZ4Block";
+    const HEADER_LEN: usize = MAGIC.len() + 13;
+    const RAW: u8 = 0x10;
+    const LZ4: u8 = 0x20;
+    /// The default 64 KiB blocks
+    const BLOCK_SIZE: usize = 1 << 16;
+    /// log2 of the block size - 10, in the low bits of the token
+    const LEVEL: u8 = 6;
+    const SEED: u32 = 0x9747b28c;
+
+    fn checksum(data: &[u8]) -> u32 {
+        XxHash32::oneshot(SEED, data) & 0x0fff_ffff
+    }
+
+    pub fn compress(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for block in data.chunks(BLOCK_SIZE) {
+            let compressed = lz4_flex::block::compress(block);
+            let (method, payload) = if compressed.len() < block.len() {
+                (LZ4, &compressed[..])
+            } else {
+                (RAW, block)
+            };
+            write_header(
+                &mut out,
+                method,
+                payload.len(),
+                block.len(),
+                checksum(block),
+            );
+            out.extend_from_slice(payload);
+        }
+        // the end mark is an empty raw block
+        write_header(&mut out, RAW, 0, 0, 0);
+        out
+    }
+
+    fn write_header(out: &mut Vec<u8>, method: u8, len: usize, original: usize, check: u32) {
+        out.extend_from_slice(MAGIC);
+        out.push(method | LEVEL);
+        out.extend_from_slice(&(len as u32).to_le_bytes());
+        out.extend_from_slice(&(original as u32).to_le_bytes());
+        out.extend_from_slice(&check.to_le_bytes());
+    }
+
+    pub fn decompress(mut data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        loop {
+            if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+                return Err("missing block header".to_owned());
+            }
+            let u32_at = |pos: usize| u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+            let method = data[MAGIC.len()] & 0xf0;
+            let len = u32_at(9) as usize;
+            let original = u32_at(13) as usize;
+            let check = u32_at(17);
+            data = &data[HEADER_LEN..];
+            if original == 0 {
+                return Ok(out);
+            }
+            // lz4-java doesn't write larger blocks either, checked before allocating `original`
+            if original > BLOCK_SIZE || len > BLOCK_SIZE {
+                return Err(format!(
+                    "block of {} bytes ({} compressed) is larger than {} bytes",
+                    original, len, BLOCK_SIZE
+                ));
+            }
+            if data.len() < len {
+                return Err("truncated block".to_owned());
+            }
+            let block = match method {
+                RAW if len == original => data[..len].to_vec(),
+                RAW => {
+                    return Err(format!(
+                        "raw block of {} bytes claims to hold {} bytes",
+                        len, original
+                    ));
+                }
+                LZ4 => lz4_flex::block::decompress(&data[..len], original)
+                    .map_err(|err| err.to_string())?,
+                _ => return Err(format!("invalid block method {:#x}", method)),
+            };
+            if block.len() != original || checksum(&block) != check {
+                return Err("block checksum mismatch".to_owned());
+            }
+            out.extend_from_slice(&block);
+            data = &data[len..];
+        }
+    }
+}
@@ -0,0 +1,370 @@
+//! ReplayMod recordings (`.mcpr`).
+//!
+//! A recording is a zip file with two entries:
+//!
+//! ```text
+//! recording.tmcpr   the clientbound packets, until the end of the entry:
+//!     timestamp     i32       milliseconds since the recording started
+//!     length        i32       length of the packet
+//!     packet        VarInt packet ID + packet data, uncompressed and unencrypted
+//! metaData.json     see `ReplayMetaData`
+//! ```
+//!
+//! Recordings start in the login state with the `LoginSuccess` that switched to configuration,
+//! the rest of the login (encryption, compression, plugin requests) isn't part of a replay.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, Write},
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zip::{ZipArchive, ZipWriter, result::ZipError, write::SimpleFileOptions};
+
+use crate::{
+    PROTOCOL_VERSION, RawPacket, Serializable, UUID, VarInt,
+    capture::{Capture, CaptureError},
+    packet::{
+        self, Direction, GameProfile, Packet, State,
+        s2c::{configuration::FinishConfiguration, login::LoginSuccess, play::StartConfiguration},
+    },
+};
+
+pub const RECORDING_ENTRY: &str = "recording.tmcpr";
+pub const META_DATA_ENTRY: &str = "metaData.json";
+/// Version of the file format written by ReplayMod for 1.21.9
+pub const FILE_FORMAT_VERSION: i32 = 14;
+/// Minecraft version of `PROTOCOL_VERSION`
+pub const MC_VERSION: &str = "1.21.10";
+
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    #[error("Io Error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("zip error: {0}")]
+    ZipError(#[from] ZipError),
+    #[error("invalid meta data: {0}")]
+    MetaDataError(#[from] serde_json::Error),
+    #[error("serialize error: {0}")]
+    SerializeError(#[from] crate::Error),
+    #[error("capture error: {0}")]
+    CaptureError(#[from] CaptureError),
+    #[error("invalid recording: {0}")]
+    InvalidRecording(String),
+    #[error("only clientbound packets can be recorded, got {0}")]
+    ServerboundPacket(&'static str),
+    #[error("the recording doesn't start with a LoginSuccess and no profile was given")]
+    MissingProfile,
+}
+
+/// Contents of `metaData.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayMetaData {
+    #[serde(default)]
+    pub singleplayer: bool,
+    #[serde(default)]
+    pub server_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_server_name: Option<String>,
+    /// Length of the recording in milliseconds
+    #[serde(default)]
+    pub duration: i32,
+    /// Unix time the recording started, in milliseconds
+    #[serde(default)]
+    pub date: i64,
+    #[serde(rename = "mcversion", default)]
+    pub mc_version: String,
+    #[serde(default)]
+    pub file_format: String,
+    #[serde(default)]
+    pub file_format_version: i32,
+    #[serde(default)]
+    pub protocol: i32,
+    #[serde(default)]
+    pub generator: String,
+    /// Entity ID of the recording player, -1 if unknown
+    #[serde(default = "unknown_self_id")]
+    pub self_id: i32,
+    #[serde(default)]
+    pub players: Vec<UUID>,
+}
+
+fn unknown_self_id() -> i32 {
+    -1
+}
+
+impl ReplayMetaData {
+    /// Meta data for a multiplayer recording of this crate's protocol version starting now
+    pub fn new() -> Self {
+        ReplayMetaData {
+            singleplayer: false,
+            server_name: String::new(),
+            custom_server_name: None,
+            duration: 0,
+            date: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|t| t.as_millis() as i64)
+                .unwrap_or_default(),
+            mc_version: MC_VERSION.to_owned(),
+            file_format: "MCPR".to_owned(),
+            file_format_version: FILE_FORMAT_VERSION,
+            protocol: PROTOCOL_VERSION,
+            generator: concat!("mc-rust-protocol ", env!("CARGO_PKG_VERSION")).to_owned(),
+            self_id: -1,
+            players: Vec::new(),
+        }
+    }
+
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = server_name.into();
+        self
+    }
+
+    pub fn with_singleplayer(mut self, singleplayer: bool) -> Self {
+        self.singleplayer = singleplayer;
+        self
+    }
+
+    /// Unix time in milliseconds
+    pub fn with_date(mut self, date: i64) -> Self {
+        self.date = date;
+        self
+    }
+}
+
+impl Default for ReplayMetaData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes a `.mcpr` file from clientbound packets.
+///
+/// Packets can start in any state: login packets other than `LoginSuccess` are skipped, and
+/// when the stream starts after the login the `LoginSuccess` ReplayMod expects is made up from
+/// the profile given with `with_profile`. State switches the stream skipped over (e.g. from
+/// configuration straight to a play packet) get their `FinishConfiguration` or
+/// `StartConfiguration` too, so the replay always decodes.
+pub struct ReplayWriter<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    meta: ReplayMetaData,
+    profile: Option<GameProfile>,
+    /// State of the recording so far
+    state: State,
+    start: Instant,
+    last_timestamp: Duration,
+}
+
+impl<W: Write + Seek> ReplayWriter<W> {
+    pub fn new(writer: W, meta: ReplayMetaData) -> Result<Self, ReplayError> {
+        let mut zip = ZipWriter::new(writer);
+        zip.start_file(RECORDING_ENTRY, SimpleFileOptions::default())?;
+        Ok(ReplayWriter {
+            zip,
+            meta,
+            profile: None,
+            state: State::Login,
+            start: Instant::now(),
+            last_timestamp: Duration::ZERO,
+        })
+    }
+
+    /// Profile of the recording player, used when the stream has no `LoginSuccess`
+    pub fn with_profile(mut self, profile: GameProfile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Records `packet` as received now
+    pub fn record(&mut self, packet: &Packet) -> Result<(), ReplayError> {
+        self.record_at(self.start.elapsed(), packet)
+    }
+
+    /// Records `packet` as received `timestamp` after the recording started
+    pub fn record_at(&mut self, timestamp: Duration, packet: &Packet) -> Result<(), ReplayError> {
+        if packet.direction() != Direction::Clientbound {
+            return Err(ReplayError::ServerboundPacket(packet.name()));
+        }
+        // never go back in time, ReplayMod expects sorted timestamps
+        let timestamp = timestamp.max(self.last_timestamp);
+
+        match (packet.state(), self.state) {
+            (State::Login, State::Login) => {
+                if let Packet::LoginSuccess(success) = packet {
+                    self.meta.players.push(success.profile.uuid);
+                    self.write_packet(timestamp, packet)?;
+                }
+                return Ok(());
+            }
+            // a second login can't be replayed
+            (State::Login, _) => return Ok(()),
+            (_, State::Login) => {
+                let profile = self.profile.take().ok_or(ReplayError::MissingProfile)?;
+                self.meta.players.push(profile.uuid);
+                self.write_packet(timestamp, &Packet::LoginSuccess(LoginSuccess { profile }))?;
+            }
+            _ => {}
+        }
+        match (packet.state(), self.state) {
+            (State::Play, State::Configuration) => self.write_packet(
+                timestamp,
+                &Packet::FinishConfiguration(FinishConfiguration {}),
+            )?,
+            (State::Configuration, State::Play) => self.write_packet(
+                timestamp,
+                &Packet::StartConfiguration(StartConfiguration {}),
+            )?,
+            _ => {}
+        }
+
+        if let Packet::LoginPlay(login) = packet {
+            self.meta.self_id = login.entity_id;
+        }
+        self.write_packet(timestamp, packet)
+    }
+
+    fn write_packet(&mut self, timestamp: Duration, packet: &Packet) -> Result<(), ReplayError> {
+        let mut data = Vec::new();
+        packet.write(&mut data)?;
+        self.zip
+            .write_i32::<BigEndian>(timestamp.as_millis() as i32)?;
+        self.zip.write_i32::<BigEndian>(data.len() as i32)?;
+        self.zip.write_all(&data)?;
+
+        self.last_timestamp = timestamp;
+        if let Some(state) = packet.state_transition() {
+            self.state = state;
+        }
+        Ok(())
+    }
+
+    /// Writes the meta data and finishes the zip file
+    pub fn finish(mut self) -> Result<W, ReplayError> {
+        self.meta.duration = self.last_timestamp.as_millis() as i32;
+        self.zip
+            .start_file(META_DATA_ENTRY, SimpleFileOptions::default())?;
+        serde_json::to_writer(&mut self.zip, &self.meta)?;
+        Ok(self.zip.finish()?)
+    }
+}
+
+/// Converts the clientbound side of a capture into a replay. Packets that didn't decode are
+/// left out.
+pub fn export_capture<W: Write + Seek>(
+    capture: &Capture,
+    writer: W,
+    meta: ReplayMetaData,
+) -> Result<W, ReplayError> {
+    let mut replay = ReplayWriter::new(writer, meta.with_date(capture.header.started_at))?;
+    for packet in capture.packets()? {
+        let packet = packet?;
+        if packet.direction == Direction::Clientbound
+            && let Ok(decoded) = &packet.packet
+        {
+            replay.record_at(packet.timestamp, decoded)?;
+        }
+    }
+    replay.finish()
+}
+
+/// A `.mcpr` file loaded in memory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub meta: ReplayMetaData,
+    /// Contents of `recording.tmcpr`
+    pub recording: Vec<u8>,
+}
+
+impl Replay {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ReplayError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn read<R: Read + Seek>(reader: R) -> Result<Self, ReplayError> {
+        let mut zip = ZipArchive::new(reader)?;
+        let meta = serde_json::from_reader(zip.by_name(META_DATA_ENTRY)?)?;
+        let mut recording = Vec::new();
+        zip.by_name(RECORDING_ENTRY)?.read_to_end(&mut recording)?;
+        Ok(Replay { meta, recording })
+    }
+
+    /// Decodes the recorded packets, following the state switches like the client did.
+    /// Recordings of other protocol versions read fine but most packets won't decode.
+    pub fn packets(&self) -> ReplayPackets<'_> {
+        ReplayPackets {
+            data: &self.recording,
+            state: State::Login,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ReplayPacket {
+    /// Time since the recording started
+    pub timestamp: Duration,
+    /// State the packet was decoded in
+    pub state: State,
+    pub raw: RawPacket,
+    /// The decoded packet, or why it couldn't be decoded (the recording keeps going either way)
+    pub packet: Result<Packet, crate::Error>,
+}
+
+/// Iterator over the packets of a replay, see `Replay::packets`
+pub struct ReplayPackets<'a> {
+    data: &'a [u8],
+    state: State,
+}
+
+impl ReplayPackets<'_> {
+    fn read_packet(&mut self) -> Result<ReplayPacket, ReplayError> {
+        let truncated = |_| ReplayError::InvalidRecording("truncated packet".to_owned());
+        let timestamp = self.data.read_i32::<BigEndian>().map_err(truncated)?;
+        let len = self.data.read_i32::<BigEndian>().map_err(truncated)?;
+        if timestamp < 0 || len < 0 || len as usize > self.data.len() {
+            return Err(ReplayError::InvalidRecording(format!(
+                "invalid packet header: timestamp {}, length {}",
+                timestamp, len
+            )));
+        }
+        let (mut data, rest) = self.data.split_at(len as usize);
+        self.data = rest;
+
+        let raw = RawPacket {
+            id: VarInt::read_from(&mut data)?.0,
+            payload: data.to_vec(),
+        };
+        let state = self.state;
+        let packet =
+            packet::packet_by_id(state, Direction::Clientbound, raw.id, &mut &raw.payload[..]);
+        if let Some(next) = packet.as_ref().ok().and_then(Packet::state_transition) {
+            self.state = next;
+        }
+        Ok(ReplayPacket {
+            timestamp: Duration::from_millis(timestamp as u64),
+            state,
+            raw,
+            packet,
+        })
+    }
+}
+
+impl Iterator for ReplayPackets<'_> {
+    type Item = Result<ReplayPacket, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let packet = self.read_packet();
+        if packet.is_err() {
+            // nothing after a broken header can be trusted
+            self.data = &[];
+        }
+        Some(packet)
+    }
+}
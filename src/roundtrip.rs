@@ -0,0 +1,170 @@
+//! Checks that encoding a decoded value gives back the bytes it was decoded from.
+//!
+//! A `write_to` that doesn't mirror its `read_from` only shows up once a proxy or server
+//! re-encodes a packet, these checks find it from captured bytes alone.
+
+use std::fmt::{self, Display};
+
+use thiserror::Error;
+
+use crate::{
+    Serializable, VarInt,
+    packet::{Direction, Packet, State},
+    span::{self, Annotated, Span},
+};
+
+#[derive(Error, Debug)]
+pub enum RoundtripError {
+    #[error("failed to decode{}: {source}", field_suffix(.field))]
+    Decode {
+        /// Path of the field that failed, e.g. `profile.properties[0].name`
+        field: Option<String>,
+        source: crate::Error,
+    },
+    #[error("failed to encode {name}: {source}")]
+    Encode { name: String, source: crate::Error },
+    #[error("{0}")]
+    Mismatch(Box<Mismatch>),
+}
+
+fn field_suffix(field: &Option<String>) -> String {
+    field
+        .as_ref()
+        .map(|field| format!(" {}", field))
+        .unwrap_or_default()
+}
+
+/// The first byte where the re-encoded value differs from the decoded bytes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// Packet or type name
+    pub name: String,
+    pub offset: usize,
+    /// `None` past the end of the decoded bytes
+    pub read: Option<u8>,
+    /// `None` past the end of the re-encoded bytes
+    pub written: Option<u8>,
+    /// Path of the innermost field the offset was read from, `None` for bytes no field read
+    pub field: Option<String>,
+    /// Type of that field
+    pub ty: Option<String>,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let byte = |byte: Option<u8>| match byte {
+            Some(byte) => format!("{:#04x}", byte),
+            None => "nothing".to_owned(),
+        };
+        write!(
+            f,
+            "{}: byte {} was {}, re-encoded as {}",
+            self.name,
+            self.offset,
+            byte(self.read),
+            byte(self.written)
+        )?;
+        match (&self.field, &self.ty) {
+            (Some(field), Some(ty)) => write!(f, " in {}: {}", field, ty),
+            (Some(field), None) => write!(f, " in {}", field),
+            _ if self.read.is_some() => f.write_str(", no field was read from it"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Decodes a packet's payload (without the packet ID), encodes it again and compares
+pub fn verify_roundtrip(
+    state: State,
+    dir: Direction,
+    id: i32,
+    payload: &[u8],
+) -> Result<(), RoundtripError> {
+    let annotated = span::annotate_packet(state, dir, id, payload);
+    compare(annotated, |packet: &Packet| {
+        let mut buf = Vec::new();
+        packet.write(&mut buf)?;
+        // `Packet::write` starts with the ID
+        let mut data = &buf[..];
+        VarInt::read_from(&mut data)?;
+        Ok(data.to_vec())
+    })
+}
+
+/// Like `verify_roundtrip`, for any `Serializable`
+pub fn verify_value_roundtrip<T: Serializable + fmt::Debug>(
+    bytes: &[u8],
+) -> Result<(), RoundtripError> {
+    compare(span::annotate::<T>(bytes), |value: &T| {
+        let mut buf = Vec::new();
+        value.write_to(&mut buf)?;
+        Ok(buf)
+    })
+}
+
+fn compare<T>(
+    annotated: Annotated<T>,
+    write: impl FnOnce(&T) -> Result<Vec<u8>, crate::Error>,
+) -> Result<(), RoundtripError> {
+    let Annotated { value, root, bytes } = annotated;
+    let value = match value {
+        Ok(value) => value,
+        Err(source) => {
+            let field = path_to(&root, &|span| span.error.is_some()).map(|(path, _)| path);
+            return Err(RoundtripError::Decode { field, source });
+        }
+    };
+    let written = write(&value).map_err(|source| RoundtripError::Encode {
+        name: root.ty.clone(),
+        source,
+    })?;
+
+    let Some(offset) =
+        (0..bytes.len().max(written.len())).find(|&i| bytes.get(i) != written.get(i))
+    else {
+        return Ok(());
+    };
+    let field = path_to(&root, &|span| {
+        span.children.is_empty() && span.range.contains(&offset)
+    });
+    Err(RoundtripError::Mismatch(Box::new(Mismatch {
+        name: root.ty.clone(),
+        offset,
+        read: bytes.get(offset).copied(),
+        written: written.get(offset).copied(),
+        ty: field.as_ref().map(|(_, ty)| ty.clone()),
+        field: field.map(|(path, _)| path),
+    })))
+}
+
+/// Path and type of the first span below `root` matching `found`, e.g. `entries[2].data`
+fn path_to(root: &Span, found: &dyn Fn(&Span) -> bool) -> Option<(String, String)> {
+    fn search(span: &Span, found: &dyn Fn(&Span) -> bool, path: &mut String) -> Option<String> {
+        if found(span) {
+            return Some(span.ty.clone());
+        }
+        let len = path.len();
+        for child in &span.children {
+            if !child.name.starts_with('[') && !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(&child.name);
+            if let Some(ty) = search(child, found, path) {
+                return Some(ty);
+            }
+            path.truncate(len);
+        }
+        None
+    }
+
+    let mut path = String::new();
+    // the root itself is the whole value, not a field
+    for child in &root.children {
+        path.clear();
+        path.push_str(&child.name);
+        if let Some(ty) = search(child, found, &mut path) {
+            return Some((path, ty));
+        }
+    }
+    None
+}
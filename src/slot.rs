@@ -1,11 +1,11 @@
 use crate::{
     IdOrX, IdSet, Identifier, Lengthable, Position, PrefixedArray, Serializable, TextComponent,
-    UUID, VarInt, nbt, packet::ProfileProperty,
+    UUID, VarInt, Visit, nbt, packet::ProfileProperty,
 };
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct HashedStack {
     pub item_id: VarInt,
     pub item_count: VarInt,
@@ -13,13 +13,13 @@ pub struct HashedStack {
     pub components_to_remove: PrefixedArray<VarInt>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct HashedComponent {
     pub component_type: VarInt,
     pub component_data_hash: u32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Visit)]
 pub struct Slot {
     pub item_count: VarInt,
     pub item: Option<Item>,
@@ -45,7 +45,7 @@ impl Serializable for Slot {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Visit)]
 pub struct Item {
     pub item_id: VarInt,
     pub components_to_add: Vec<Component>,
@@ -89,7 +89,7 @@ impl Serializable for Item {
     }
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum Component {
     CustomData {
@@ -363,7 +363,7 @@ pub enum Component {
     ShulkerColor(DyeColor),
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum Rarity {
     Common,
@@ -372,13 +372,13 @@ pub enum Rarity {
     Epic,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct Enchantment {
     pub type_id: VarInt,
     pub level: VarInt,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct BlockPredicate {
     pub blocks: Option<IdSet>,
     pub properties: Option<PrefixedArray<Property>>,
@@ -387,29 +387,29 @@ pub struct BlockPredicate {
     pub partial_data_component_predicates: PrefixedArray<PartialDataComponentMatcher>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct Property {
     pub name: String,
     pub match_type: PropertyMatch,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(bool, 0)]
 pub enum PropertyMatch {
     RangedMatch { min: String, max: String },
     ExactMatch(String),
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct ExactDataComponentMatcher(pub Component);
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct PartialDataComponentMatcher {
     pub ty: PartialDataComponentMatcherType,
     pub predicate: nbt::Tag,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum PartialDataComponentMatcherType {
     Damage,
@@ -428,7 +428,7 @@ pub enum PartialDataComponentMatcherType {
     JukeboxPlayable,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct AttributeModifier {
     pub attribute_id: VarInt,
     pub modifier_id: Identifier,
@@ -437,7 +437,7 @@ pub struct AttributeModifier {
     pub slot: AttributeModifierSlot,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum AttributeOperation {
     Add,
@@ -445,7 +445,7 @@ pub enum AttributeOperation {
     MultiplyTotal,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum AttributeModifierSlot {
     Any,
@@ -460,7 +460,7 @@ pub enum AttributeModifierSlot {
     Body,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ConsumeAnimation {
     None,
@@ -475,13 +475,13 @@ pub enum ConsumeAnimation {
     Brush,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct SoundEvent {
     pub sound_name: Identifier,
     pub fixed_range: Option<f32>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum ConsumeEffect {
     ApplyEffects {
@@ -500,13 +500,13 @@ pub enum ConsumeEffect {
     },
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct PotionEffect {
     pub type_id: VarInt,
     pub details: PotionEffectDetail,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct PotionEffectDetail {
     pub amplifier: VarInt,
     /// -1 for infinite
@@ -517,14 +517,14 @@ pub struct PotionEffectDetail {
     pub hidden_effect: Option<Box<PotionEffectDetail>>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct ToolRule {
     pub blocks: IdSet,
     pub speed: Option<f32>,
     pub correct_drop_for_blocks: Option<bool>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum EquippableSlot {
     Mainhand,
@@ -536,7 +536,7 @@ pub enum EquippableSlot {
     Body,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct DamageReduction {
     pub horizontal_blocking_angle: f32,
     pub ty: Option<IdSet>,
@@ -545,7 +545,7 @@ pub struct DamageReduction {
 }
 
 /// Color as 0xRRGGBB, top bits are ignored
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Visit)]
 pub struct ColorI32 {
     pub r: u8,
     pub g: u8,
@@ -569,39 +569,39 @@ impl Serializable for ColorI32 {
     }
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum MapPostProcessingType {
     Lock,
     Scale,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct SuspiciousStewEffect {
     pub type_id: VarInt,
     pub duration: VarInt,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct BookPage {
     pub raw_content: String,
     pub filtered_content: Option<String>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct TrimMaterial {
     pub suffix: String,
     pub overrides: PrefixedArray<TrimMaterialOverrides>,
     pub description: TextComponent,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct TrimMaterialOverrides {
     pub armor_material_type: Identifier,
     pub overriden_asset_name: String,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct TrimPattern {
     pub asset_name: String,
     pub template_item: VarInt,
@@ -609,7 +609,7 @@ pub struct TrimPattern {
     pub decal: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct Instrument {
     pub sound_event: IdOrX<SoundEvent>,
     pub sound_range: f32,
@@ -617,7 +617,7 @@ pub struct Instrument {
     pub description: TextComponent,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct JukeboxSong {
     pub sound_event: IdOrX<SoundEvent>,
     pub description: TextComponent,
@@ -625,21 +625,21 @@ pub struct JukeboxSong {
     pub output: VarInt,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(i8, 0)]
 pub enum ProvidesTrimMaterialMode {
     Identifier(Identifier),
     IdOr(IdOrX<TrimMaterial>),
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 #[enum_info(i8, 0)]
 pub enum JukeboxPlayable {
     Identifier(Identifier),
     IdOr(IdOrX<JukeboxSong>),
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct FireworkExplosion {
     pub shape: FireworkExplosionShape,
     pub colors: PrefixedArray<ColorI32>,
@@ -648,7 +648,7 @@ pub struct FireworkExplosion {
     pub has_twinkle: bool,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum FireworkExplosionShape {
     SmallBall,
@@ -658,19 +658,19 @@ pub enum FireworkExplosionShape {
     Burst,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct BannerLayer {
     pub pattern_type: IdOrX<BannerLayerData>,
     pub color: DyeColor,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct BannerLayerData {
     pub asset_id: Identifier,
     pub translation_key: String,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum DyeColor {
     White,
@@ -691,27 +691,27 @@ pub enum DyeColor {
     Black,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 pub struct BlockStateProperty {
     pub name: String,
     pub value: String,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct Bee {
     pub entity_data: nbt::Tag,
     pub ticks_in_hive: VarInt,
     pub min_ticks_in_hive: VarInt,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum FoxVariant {
     Red,
     Snow,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum SalmonSize {
     Small,
@@ -719,7 +719,7 @@ pub enum SalmonSize {
     Large,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum TropicalFishPattern {
     Kob,
@@ -736,14 +736,14 @@ pub enum TropicalFishPattern {
     Clayfish,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum MooshroomVariant {
     Red,
     Brown,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum RabbitVariant {
     Brown,
@@ -755,14 +755,14 @@ pub enum RabbitVariant {
     Evil,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(i8, 0)]
 pub enum ChickenVariant {
     Identifier(Identifier),
     Id(VarInt),
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum HorseVariant {
     White,
@@ -774,7 +774,7 @@ pub enum HorseVariant {
     DarkBrown,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Serializable)]
 pub struct PaintingVariant {
     pub width: i32,
     pub height: i32,
@@ -783,7 +783,7 @@ pub struct PaintingVariant {
     pub author: Option<TextComponent>,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum LlamaVariant {
     Creamy,
@@ -792,7 +792,7 @@ pub enum LlamaVariant {
     Gray,
 }
 
-#[derive(Debug, Serializable)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serializable)]
 #[enum_info(VarInt, 0)]
 pub enum AxolotlVariant {
     Lucy,
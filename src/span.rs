@@ -0,0 +1,345 @@
+//! Byte spans of decoded values, for dissector-style output.
+//!
+//! `annotate` and `annotate_packet` decode like `Serializable::read_from` and `packet_by_id`,
+//! and record which bytes every field was read from. The `Serializable` derive records its
+//! fields, hand-written impls record theirs with `field`, `element` and `entry`; outside of
+//! `annotate` these are plain calls to the read closure.
+//!
+//! Positions come from the reader `annotate` passes down, so values decoded from a copy of the
+//! bytes (e.g. decompressed data) get spans at the position the copy was made.
+
+use std::{
+    any::type_name,
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    fmt::{self, Debug, Display},
+    io::{self, Read},
+    ops::Range,
+};
+
+use serde::Serialize;
+
+use crate::{
+    Error, Serializable,
+    packet::{self, Direction, Packet, State},
+};
+
+/// Longest value shown in the text view, longer ones are cut off
+const MAX_VALUE_LEN: usize = 80;
+/// Most bytes shown per span in the text view
+const MAX_HEX_BYTES: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Span {
+    /// Field name, `[index]` for elements of a sequence, or the key of a compound entry
+    pub name: Cow<'static, str>,
+    /// Type the field was read as, without module paths
+    pub ty: String,
+    pub range: Range<usize>,
+    /// `Debug` output of the value, for spans without children
+    pub value: Option<String>,
+    /// Why reading this span failed, only set on the innermost span that failed
+    pub error: Option<String>,
+    pub children: Vec<Span>,
+}
+
+impl Span {
+    fn new(name: Cow<'static, str>, ty: &'static str, start: usize) -> Self {
+        Span {
+            name,
+            ty: short_type_name(ty),
+            range: start..start,
+            value: None,
+            error: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// The innermost span that failed to read
+    pub fn error_span(&self) -> Option<&Span> {
+        if self.error.is_some() {
+            return Some(self);
+        }
+        self.children.iter().find_map(Span::error_span)
+    }
+
+    /// Spans without children, in byte order
+    pub fn leaves(&self) -> Vec<&Span> {
+        if self.children.is_empty() {
+            return vec![self];
+        }
+        self.children.iter().flat_map(Span::leaves).collect()
+    }
+
+    fn fmt_tree(&self, f: &mut fmt::Formatter<'_>, bytes: &[u8], depth: usize) -> fmt::Result {
+        let marker = if self.error.is_some() { "!!" } else { "  " };
+        let mut line = format!(
+            "{}{:04x}..{:04x} {:indent$}{}: {}",
+            marker,
+            self.range.start,
+            self.range.end,
+            "",
+            self.name,
+            self.ty,
+            indent = depth * 2
+        );
+        if let Some(value) = &self.value {
+            line.push_str(" = ");
+            if value.chars().count() > MAX_VALUE_LEN {
+                line.extend(value.chars().take(MAX_VALUE_LEN));
+                line.push('…');
+            } else {
+                line.push_str(value);
+            }
+        }
+        if self.children.is_empty() {
+            let range = self.range.start.min(bytes.len())..self.range.end.min(bytes.len());
+            let hex = hex_bytes(&bytes[range]);
+            write!(f, "{}", format!("{:<72}  {}", line, hex).trim_end())?;
+        } else {
+            f.write_str(&line)?;
+        }
+        if let Some(error) = &self.error {
+            write!(f, "  error: {}", error)?;
+        }
+        writeln!(f)?;
+        for child in &self.children {
+            child.fmt_tree(f, bytes, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    let mut hex = bytes
+        .iter()
+        .take(MAX_HEX_BYTES)
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if bytes.len() > MAX_HEX_BYTES {
+        hex.push_str(" …");
+    }
+    hex
+}
+
+/// `alloc::vec::Vec<mc_rust_protocol::VarInt>` -> `Vec<VarInt>`
+fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut segment_start = 0;
+    for (i, c) in name.char_indices() {
+        match c {
+            ':' => segment_start = i + 1,
+            '<' | '>' | ',' | ' ' | '(' | ')' | '[' | ']' | ';' | '&' => {
+                short.push_str(&name[segment_start..i]);
+                short.push(c);
+                segment_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    short.push_str(&name[segment_start..]);
+    short
+}
+
+struct Recorder {
+    position: usize,
+    /// Open spans, the root first
+    stack: Vec<Span>,
+}
+
+thread_local! {
+    static RECORDING: Cell<bool> = const { Cell::new(false) };
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+fn with_recorder<T>(f: impl FnOnce(&mut Recorder) -> T) -> Option<T> {
+    RECORDER.with(|recorder| recorder.borrow_mut().as_mut().map(f))
+}
+
+fn record<T: Debug>(
+    name: impl FnOnce() -> Cow<'static, str>,
+    read: impl FnOnce() -> Result<T, Error>,
+) -> Result<T, Error> {
+    if !RECORDING.get() {
+        return read();
+    }
+    with_recorder(|recorder| {
+        let span = Span::new(name(), type_name::<T>(), recorder.position);
+        recorder.stack.push(span);
+    });
+    let result = read();
+    with_recorder(|recorder| {
+        let Some(mut span) = recorder.stack.pop() else {
+            return;
+        };
+        span.range.end = recorder.position;
+        match &result {
+            Ok(value) if span.children.is_empty() => span.value = Some(format!("{:?}", value)),
+            Ok(_) => {}
+            Err(err) if span.error_span().is_none() => span.error = Some(err.to_string()),
+            Err(_) => {}
+        }
+        if let Some(parent) = recorder.stack.last_mut() {
+            parent.children.push(span);
+        }
+    });
+    result
+}
+
+/// Reads a named field
+pub fn field<T: Debug>(
+    name: &'static str,
+    read: impl FnOnce() -> Result<T, Error>,
+) -> Result<T, Error> {
+    record(|| Cow::Borrowed(name), read)
+}
+
+/// Reads an element of a sequence, named `[index]`
+pub fn element<T: Debug>(
+    index: usize,
+    read: impl FnOnce() -> Result<T, Error>,
+) -> Result<T, Error> {
+    record(|| Cow::Owned(format!("[{}]", index)), read)
+}
+
+/// Reads an entry with a name only known while decoding, like the key of an NBT compound
+pub fn entry<T: Debug>(name: &str, read: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    record(|| Cow::Owned(name.to_owned()), read)
+}
+
+/// Names the enum variant the last read discriminant selected, e.g. `VarInt(2) (Interact)`
+pub fn variant(name: &'static str) {
+    if !RECORDING.get() {
+        return;
+    }
+    with_recorder(|recorder| {
+        if let Some(discriminant) = recorder
+            .stack
+            .last_mut()
+            .and_then(|span| span.children.last_mut())
+            && let Some(value) = &mut discriminant.value
+        {
+            value.push_str(&format!(" ({})", name));
+        }
+    });
+}
+
+/// Reads from the annotated bytes, moving the recorder's position along
+struct SpanReader<'a> {
+    data: &'a [u8],
+}
+
+impl Read for SpanReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.data.read(buf)?;
+        with_recorder(|recorder| recorder.position += read);
+        Ok(read)
+    }
+}
+
+/// Stops recording when dropped, also when decoding panics
+struct RecordingGuard;
+
+impl RecordingGuard {
+    fn start(root: Span) -> Self {
+        RECORDER.with(|recorder| {
+            *recorder.borrow_mut() = Some(Recorder {
+                position: 0,
+                stack: vec![root],
+            })
+        });
+        RECORDING.set(true);
+        RecordingGuard
+    }
+
+    fn finish(self) -> Span {
+        RECORDING.set(false);
+        let recorder = RECORDER.with(|recorder| recorder.borrow_mut().take());
+        let mut recorder = recorder.expect("recorder is set while recording");
+        let mut root = recorder.stack.swap_remove(0);
+        root.range.end = recorder.position;
+        root
+    }
+}
+
+impl Drop for RecordingGuard {
+    fn drop(&mut self) {
+        RECORDING.set(false);
+        RECORDER.with(|recorder| recorder.borrow_mut().take());
+    }
+}
+
+/// A decoded value with the spans it was read from
+#[derive(Debug)]
+pub struct Annotated<T> {
+    pub value: Result<T, Error>,
+    pub root: Span,
+    pub bytes: Vec<u8>,
+}
+
+impl<T> Annotated<T> {
+    /// Bytes left after decoding, usually a sign of a field missing from the definition
+    pub fn remaining(&self) -> &[u8] {
+        &self.bytes[self.root.range.end.min(self.bytes.len())..]
+    }
+}
+
+impl<T> Display for Annotated<T> {
+    /// Wireshark-like tree: byte range, field, type, value and the bytes of every leaf
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.root.fmt_tree(f, &self.bytes, 0)?;
+        let remaining = self.remaining();
+        if !remaining.is_empty() {
+            writeln!(
+                f,
+                "{} bytes left over: {}",
+                remaining.len(),
+                hex_bytes(remaining)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a `T` from `bytes`, recording spans
+pub fn annotate<T: Serializable + Debug>(bytes: &[u8]) -> Annotated<T> {
+    let guard = RecordingGuard::start(Span::new(Cow::Borrowed("value"), type_name::<T>(), 0));
+    let value = T::read_from(&mut SpanReader { data: bytes });
+    let mut root = guard.finish();
+    match &value {
+        Ok(value) if root.children.is_empty() => root.value = Some(format!("{:?}", value)),
+        Err(err) if root.error_span().is_none() => root.error = Some(err.to_string()),
+        _ => {}
+    }
+    Annotated {
+        value,
+        root,
+        bytes: bytes.to_vec(),
+    }
+}
+
+/// Decodes a packet's payload (without the packet ID) like `packet_by_id`, recording spans.
+/// The root span is named after the packet.
+pub fn annotate_packet(state: State, dir: Direction, id: i32, payload: &[u8]) -> Annotated<Packet> {
+    let guard = RecordingGuard::start(Span::new(Cow::Borrowed("packet"), "Packet", 0));
+    let value = packet::packet_by_id(state, dir, id, &mut SpanReader { data: payload });
+    let mut root = guard.finish();
+    match &value {
+        Ok(packet) => {
+            root.name = Cow::Borrowed(packet.name());
+            root.ty = packet.name().to_owned();
+        }
+        Err(err) => {
+            root.name = Cow::Owned(format!("{:#04x}", id));
+            if root.error_span().is_none() {
+                root.error = Some(err.to_string());
+            }
+        }
+    }
+    Annotated {
+        value,
+        root,
+        bytes: payload.to_vec(),
+    }
+}
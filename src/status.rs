@@ -0,0 +1,334 @@
+use std::{
+    io::{self, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::{
+    PROTOCOL_VERSION, UUID, VarInt,
+    packet::{
+        self, Direction, Intent, Packet, PacketType, State,
+        c2s::{
+            handshake::Handshake,
+            status::{PingRequestStatus, StatusRequest},
+        },
+        s2c::status::{PongResponseStatus, StatusResponse},
+    },
+    packet_decoder::{NetworkDecoder, PacketDecodeError},
+    packet_encoder::{NetworkEncoder, PacketEncodeError},
+    text::JsonTextComponent,
+};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+const FAVICON_PREFIX: &str = "data:image/png;base64,";
+pub const FAVICON_SIZE: u32 = 64;
+
+#[derive(Error, Debug)]
+pub enum StatusError {
+    #[error("invalid favicon: {0}")]
+    InvalidFavicon(String),
+    #[error("json parsing error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("could not resolve {0}")]
+    UnresolvedHost(String),
+    #[error("timed out")]
+    Timeout,
+    #[error("Io Error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("failed to decode packet: {0}")]
+    DecodeError(#[from] PacketDecodeError),
+    #[error("failed to encode packet: {0}")]
+    EncodeError(#[from] PacketEncodeError),
+    #[error("serialize error: {0}")]
+    SerializeError(#[from] crate::Error),
+    #[error("unexpected packet: {0}")]
+    UnexpectedPacket(String),
+    #[error("pong timestamp {received} doesn't match the ping timestamp {sent}")]
+    PongMismatch { sent: i64, received: i64 },
+}
+
+/// The JSON document sent in `StatusResponse` (server list ping).
+///
+/// Fields this struct doesn't know about (Forge's `forgeData`, `modinfo`, `preventsChatReports`...)
+/// are kept in `extra` and written back as is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerStatus {
+    pub version: StatusVersion,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub players: Option<StatusPlayers>,
+    #[serde(default)]
+    pub description: JsonTextComponent,
+    /// `data:image/png;base64,...` encoded 64x64 PNG
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+    #[serde(rename = "enforcesSecureChat", default)]
+    pub enforces_secure_chat: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusVersion {
+    pub name: String,
+    pub protocol: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusPlayers {
+    pub max: i32,
+    pub online: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sample: Vec<StatusPlayerSample>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusPlayerSample {
+    pub name: String,
+    pub id: UUID,
+}
+
+impl ServerStatus {
+    pub fn new(version_name: &str, protocol: i32) -> Self {
+        ServerStatus {
+            version: StatusVersion {
+                name: version_name.to_owned(),
+                protocol,
+            },
+            players: None,
+            description: JsonTextComponent::default(),
+            favicon: None,
+            enforces_secure_chat: false,
+            extra: Map::new(),
+        }
+    }
+
+    pub fn with_players(mut self, max: i32, online: i32) -> Self {
+        let sample = self.players.take().map(|p| p.sample).unwrap_or_default();
+        self.players = Some(StatusPlayers {
+            max,
+            online,
+            sample,
+        });
+        self
+    }
+
+    /// Adds a player to the sample, with 0 max and online players if `with_players` wasn't
+    /// called yet
+    pub fn with_sample(mut self, name: &str, id: UUID) -> Self {
+        let players = self.players.get_or_insert_with(|| StatusPlayers {
+            max: 0,
+            online: 0,
+            sample: Vec::new(),
+        });
+        players.sample.push(StatusPlayerSample {
+            name: name.to_owned(),
+            id,
+        });
+        self
+    }
+
+    /// e.g. `"A Minecraft Server"` or `JsonTextComponent::text("hi").with_color("gold")`
+    pub fn with_description(mut self, description: impl Into<JsonTextComponent>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn with_enforces_secure_chat(mut self, enforces_secure_chat: bool) -> Self {
+        self.enforces_secure_chat = enforces_secure_chat;
+        self
+    }
+
+    /// Validates and encodes a raw PNG file as the favicon
+    pub fn with_favicon_png(mut self, png: &[u8]) -> Result<Self, StatusError> {
+        self.favicon = Some(encode_favicon(png)?);
+        Ok(self)
+    }
+
+    /// Decodes the favicon back to the raw PNG file
+    pub fn favicon_png(&self) -> Option<Result<Vec<u8>, StatusError>> {
+        self.favicon.as_deref().map(decode_favicon)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, StatusError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("status is always serializable")
+    }
+}
+
+impl StatusResponse {
+    pub fn new(status: &ServerStatus) -> Self {
+        StatusResponse {
+            json_response: status.to_json(),
+        }
+    }
+
+    pub fn status(&self) -> Result<ServerStatus, StatusError> {
+        ServerStatus::from_json(&self.json_response)
+    }
+}
+
+/// Checks that `png` is a 64x64 PNG and returns it as a `data:image/png;base64,` URI
+pub fn encode_favicon(png: &[u8]) -> Result<String, StatusError> {
+    let (width, height) = png_dimensions(png)?;
+    if width != FAVICON_SIZE || height != FAVICON_SIZE {
+        return Err(StatusError::InvalidFavicon(format!(
+            "favicon must be {}x{}, got {}x{}",
+            FAVICON_SIZE, FAVICON_SIZE, width, height
+        )));
+    }
+    Ok(FAVICON_PREFIX.to_owned() + &STANDARD.encode(png))
+}
+
+pub fn decode_favicon(favicon: &str) -> Result<Vec<u8>, StatusError> {
+    let data = favicon.strip_prefix(FAVICON_PREFIX).ok_or_else(|| {
+        StatusError::InvalidFavicon(format!("missing \"{}\" prefix", FAVICON_PREFIX))
+    })?;
+    // vanilla servers have been known to send line breaks in the base64 data
+    let data: String = data.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    STANDARD
+        .decode(data)
+        .map_err(|err| StatusError::InvalidFavicon(err.to_string()))
+}
+
+/// Reads the width and height from the IHDR chunk, which is always the first chunk of a PNG
+fn png_dimensions(png: &[u8]) -> Result<(u32, u32), StatusError> {
+    if png.len() < 24 || png[..8] != PNG_SIGNATURE {
+        return Err(StatusError::InvalidFavicon("not a png file".to_owned()));
+    }
+    if &png[12..16] != b"IHDR" {
+        return Err(StatusError::InvalidFavicon(
+            "png is missing the IHDR chunk".to_owned(),
+        ));
+    }
+    let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+    let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+    Ok((width, height))
+}
+
+/// Result of a server list ping
+#[derive(Debug, Clone)]
+pub struct StatusPing {
+    pub status: ServerStatus,
+    /// Round trip time of the ping/pong exchange
+    pub latency: Duration,
+}
+
+/// Performs a full server list ping: handshake with `Intent::Status`, status request and a
+/// ping/pong exchange to measure the round trip time.
+///
+/// `timeout` applies to connecting and to every single read/write.
+/// NOTE: SRV records are not resolved, `host` is looked up as is
+pub fn ping(host: &str, port: u16, timeout: Duration) -> Result<StatusPing, StatusError> {
+    let addr = (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| StatusError::UnresolvedHost(format!("{}:{}", host, port)))?;
+    let stream = TcpStream::connect_timeout(&addr, timeout).map_err(map_timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    stream.set_nodelay(true)?;
+
+    let mut encoder = NetworkEncoder::new(stream.try_clone()?);
+    let mut decoder = NetworkDecoder::new(BufReader::new(stream));
+
+    send(
+        &mut encoder,
+        &Handshake {
+            protocol_version: VarInt(PROTOCOL_VERSION),
+            server_adress: host.to_owned(),
+            server_port: port,
+            intent: Intent::Status,
+        },
+    )?;
+    send(&mut encoder, &StatusRequest {})?;
+
+    let status = match receive(&mut decoder, Direction::Clientbound)? {
+        Packet::StatusResponse(response) => response.status()?,
+        other => return Err(StatusError::UnexpectedPacket(format!("{:?}", other))),
+    };
+
+    let sent = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_millis() as i64)
+        .unwrap_or_default();
+    let start = Instant::now();
+    send(&mut encoder, &PingRequestStatus { timestamp: sent })?;
+
+    let received = match receive(&mut decoder, Direction::Clientbound)? {
+        Packet::PongResponseStatus(pong) => pong.timestamp,
+        other => return Err(StatusError::UnexpectedPacket(format!("{:?}", other))),
+    };
+    let latency = start.elapsed();
+    if received != sent {
+        return Err(StatusError::PongMismatch { sent, received });
+    }
+
+    Ok(StatusPing { status, latency })
+}
+
+/// Server side of the status exchange: answers status requests and pings until the client
+/// has been sent a pong (at which point vanilla clients close the connection).
+///
+/// The handshake has to be read by the caller, this only handles the `Status` state.
+pub fn serve_status<R: Read, W: Write>(
+    decoder: &mut NetworkDecoder<R>,
+    encoder: &mut NetworkEncoder<W>,
+    status: &ServerStatus,
+) -> Result<(), StatusError> {
+    loop {
+        match receive(decoder, Direction::Serverbound)? {
+            Packet::StatusRequest(_) => send(encoder, &StatusResponse::new(status))?,
+            Packet::PingRequestStatus(ping) => {
+                send(
+                    encoder,
+                    &PongResponseStatus {
+                        timestamp: ping.timestamp,
+                    },
+                )?;
+                return Ok(());
+            }
+            other => return Err(StatusError::UnexpectedPacket(format!("{:?}", other))),
+        }
+    }
+}
+
+fn send<W: Write, P: PacketType>(
+    encoder: &mut NetworkEncoder<W>,
+    packet: &P,
+) -> Result<(), StatusError> {
+    let mut buf = Vec::new();
+    packet.write(&mut buf)?;
+    encoder.write_packet(&buf)?;
+    Ok(())
+}
+
+fn receive<R: Read>(
+    decoder: &mut NetworkDecoder<R>,
+    dir: Direction,
+) -> Result<Packet, StatusError> {
+    let raw = decoder.get_raw_packet().map_err(|err| match err {
+        PacketDecodeError::SerializeError(crate::Error::IoError(err)) => map_timeout(err),
+        err => err.into(),
+    })?;
+    Ok(packet::packet_by_id(
+        State::Status,
+        dir,
+        raw.id,
+        &mut &raw.payload[..],
+    )?)
+}
+
+fn map_timeout(err: io::Error) -> StatusError {
+    match err.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => StatusError::Timeout,
+        _ => err.into(),
+    }
+}
@@ -0,0 +1,192 @@
+//! JSON text components, used by `LoginDisconnect` and the status response.
+//!
+//! Packets in the configuration and play states send text components as NBT
+//! (`TextComponent`), the login and status states still use JSON.
+
+use std::io;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    Error, Serializable,
+    visit::{self, Visit, Visitor},
+};
+
+/// A JSON text component.
+///
+/// The shorthand forms are accepted when parsing: a plain string is a component with only
+/// `text`, and an array is its first element with the others appended to `extra`. Keys this
+/// struct doesn't model (`translate`, `with`, `clickEvent`, `font`...) are kept in `other`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(try_from = "Value", into = "Value")]
+pub struct JsonTextComponent {
+    pub text: String,
+    pub color: Option<String>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    pub extra: Vec<JsonTextComponent>,
+    pub other: Map<String, Value>,
+}
+
+/// Keys of the other content types, vanilla reads a component with a `text` key as plain text
+/// no matter what else it contains
+const CONTENT_KEYS: [&str; 5] = ["translate", "score", "selector", "keybind", "nbt"];
+
+impl JsonTextComponent {
+    pub fn text(text: &str) -> Self {
+        JsonTextComponent {
+            text: text.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.color = Some(color.to_owned());
+        self
+    }
+
+    pub fn with_extra(mut self, extra: JsonTextComponent) -> Self {
+        self.extra.push(extra);
+        self
+    }
+
+    /// The text of this component and its children, without any formatting
+    pub fn plain_text(&self) -> String {
+        let mut out = String::new();
+        self.append_plain_text(&mut out);
+        out
+    }
+
+    fn append_plain_text(&self, out: &mut String) {
+        out.push_str(&self.text);
+        self.extra
+            .iter()
+            .for_each(|extra| extra.append_plain_text(out));
+    }
+
+    pub fn to_json(&self) -> Value {
+        self.clone().into()
+    }
+}
+
+impl Visit for JsonTextComponent {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(visit::Value::Json(&self.to_json()));
+    }
+}
+
+/// Sent as a string containing the JSON
+impl Serializable for JsonTextComponent {
+    fn read_from<R: io::Read>(buf: &mut R) -> Result<Self, Error> {
+        Ok(serde_json::from_str(&String::read_from(buf)?)?)
+    }
+    fn write_to<W: io::Write>(&self, buf: &mut W) -> Result<(), Error> {
+        self.to_json().to_string().write_to(buf)
+    }
+}
+
+impl From<&str> for JsonTextComponent {
+    fn from(value: &str) -> Self {
+        JsonTextComponent::text(value)
+    }
+}
+
+impl From<String> for JsonTextComponent {
+    fn from(value: String) -> Self {
+        JsonTextComponent {
+            text: value,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<JsonTextComponent> for Value {
+    fn from(value: JsonTextComponent) -> Self {
+        let mut object = Map::new();
+        if !value.text.is_empty()
+            || !CONTENT_KEYS
+                .iter()
+                .any(|key| value.other.contains_key(*key))
+        {
+            object.insert("text".to_owned(), value.text.into());
+        }
+        if let Some(color) = value.color {
+            object.insert("color".to_owned(), color.into());
+        }
+        for (key, flag) in [
+            ("bold", value.bold),
+            ("italic", value.italic),
+            ("underlined", value.underlined),
+            ("strikethrough", value.strikethrough),
+            ("obfuscated", value.obfuscated),
+        ] {
+            if let Some(flag) = flag {
+                object.insert(key.to_owned(), flag.into());
+            }
+        }
+        if !value.extra.is_empty() {
+            let extra = value.extra.into_iter().map(Value::from).collect();
+            object.insert("extra".to_owned(), Value::Array(extra));
+        }
+        object.extend(value.other);
+        Value::Object(object)
+    }
+}
+
+impl TryFrom<Value> for JsonTextComponent {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(text) => Ok(text.into()),
+            // numbers and booleans are shown as their text
+            Value::Number(_) | Value::Bool(_) => Ok(value.to_string().into()),
+            Value::Array(parts) => {
+                let mut parts = parts.into_iter().map(JsonTextComponent::try_from);
+                let mut first = parts
+                    .next()
+                    .ok_or_else(|| "empty text component list".to_owned())??;
+                for part in parts {
+                    first.extra.push(part?);
+                }
+                Ok(first)
+            }
+            Value::Object(mut object) => {
+                let string = |object: &mut Map<String, Value>, key: &str| match object.remove(key) {
+                    None => Ok(None),
+                    Some(Value::String(value)) => Ok(Some(value)),
+                    Some(other) => Err(format!("expected a string for {}, got {}", key, other)),
+                };
+                let flag = |object: &mut Map<String, Value>, key: &str| match object.remove(key) {
+                    None => Ok(None),
+                    Some(Value::Bool(value)) => Ok(Some(value)),
+                    Some(other) => Err(format!("expected a boolean for {}, got {}", key, other)),
+                };
+                let extra = match object.remove("extra") {
+                    None => Vec::new(),
+                    Some(Value::Array(extra)) => extra
+                        .into_iter()
+                        .map(JsonTextComponent::try_from)
+                        .collect::<Result<_, _>>()?,
+                    Some(other) => return Err(format!("expected a list for extra, got {}", other)),
+                };
+                Ok(JsonTextComponent {
+                    text: string(&mut object, "text")?.unwrap_or_default(),
+                    color: string(&mut object, "color")?,
+                    bold: flag(&mut object, "bold")?,
+                    italic: flag(&mut object, "italic")?,
+                    underlined: flag(&mut object, "underlined")?,
+                    strikethrough: flag(&mut object, "strikethrough")?,
+                    obfuscated: flag(&mut object, "obfuscated")?,
+                    extra,
+                    other: object,
+                })
+            }
+            Value::Null => Err("expected a text component, got null".to_owned()),
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! Walking decoded values field by field.
+//!
+//! Every `Serializable` type in this crate implements `Visit`: the derive generates it alongside
+//! `read_from`/`write_to`, hand-written types implement it next to their `Serializable` impl.
+//! It isn't a supertrait, types implemented outside the crate only need it to use these tools.
+//! A `Visitor` receives the structure as events, which is enough for generic tools like
+//! [`to_json`] without per-packet code.
+
+use std::{marker::PhantomData, time::Duration};
+
+use serde_json::{Map, Number};
+
+use crate::{UUID, VarInt, VarLong, nbt::Tag};
+
+/// A leaf value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value<'a> {
+    /// A missing `Option` or a type without data
+    None,
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Str(&'a str),
+    Bytes(&'a [u8]),
+    Uuid(UUID),
+    Json(&'a serde_json::Value),
+    Position {
+        x: i32,
+        y: i32,
+        z: i32,
+    },
+    /// In degrees
+    Angle(f32),
+    Nbt(&'a Tag),
+}
+
+/// Receives a value's structure. Between `begin_*` and its `end`, a struct or variant reports
+/// `field` followed by the field's value, a sequence reports its elements.
+pub trait Visitor {
+    fn value(&mut self, value: Value<'_>);
+
+    fn begin_struct(&mut self, _name: &'static str) {}
+
+    /// An enum variant, its fields follow like those of a struct
+    fn begin_variant(&mut self, _name: &'static str, _variant: &'static str) {}
+
+    fn begin_seq(&mut self, _len: usize) {}
+
+    /// Names the next value. Not `'static` for the keys of NBT compounds.
+    fn field(&mut self, _name: &str) {}
+
+    fn end(&mut self) {}
+}
+
+pub trait Visit {
+    fn visit(&self, visitor: &mut dyn Visitor);
+
+    /// Visits a sequence of `Self`, overridden by `u8` to report bytes in one go
+    fn visit_slice(items: &[Self], visitor: &mut dyn Visitor)
+    where
+        Self: Sized,
+    {
+        visitor.begin_seq(items.len());
+        for item in items {
+            item.visit(visitor);
+        }
+        visitor.end();
+    }
+}
+
+macro_rules! visit_as {
+    ($variant:ident($as:ty): $($ty:ty),*) => {
+        $(
+            impl Visit for $ty {
+                fn visit(&self, visitor: &mut dyn Visitor) {
+                    visitor.value(Value::$variant(*self as $as));
+                }
+            }
+        )*
+    };
+}
+
+visit_as!(I64(i64): i8, i16, i32, i64);
+visit_as!(U64(u64): u16, u32, u64, usize);
+visit_as!(F32(f32): f32);
+visit_as!(F64(f64): f64);
+visit_as!(Bool(bool): bool);
+
+impl Visit for u8 {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(Value::U64(*self as u64));
+    }
+
+    fn visit_slice(items: &[Self], visitor: &mut dyn Visitor) {
+        visitor.value(Value::Bytes(items));
+    }
+}
+
+impl Visit for VarInt {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(Value::I64(self.0 as i64));
+    }
+}
+
+impl Visit for VarLong {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(Value::I64(self.0));
+    }
+}
+
+impl Visit for String {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(Value::Str(self));
+    }
+}
+
+impl Visit for UUID {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(Value::Uuid(*self));
+    }
+}
+
+impl Visit for serde_json::Value {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(Value::Json(self));
+    }
+}
+
+impl Visit for () {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(Value::None);
+    }
+}
+
+impl<T> Visit for PhantomData<T> {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(Value::None);
+    }
+}
+
+impl<T: Visit> Visit for Vec<T> {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        T::visit_slice(self, visitor);
+    }
+}
+
+impl<T: Visit, const N: usize> Visit for [T; N] {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        T::visit_slice(self, visitor);
+    }
+}
+
+/// In microseconds, like the timestamps of captures
+impl Visit for Duration {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.value(Value::U64(self.as_micros() as u64));
+    }
+}
+
+impl<T: Visit> Visit for Option<T> {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        match self {
+            Some(value) => value.visit(visitor),
+            None => visitor.value(Value::None),
+        }
+    }
+}
+
+impl<T: Visit> Visit for Box<T> {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        (**self).visit(visitor);
+    }
+}
+
+impl<A: Visit, B: Visit, C: Visit> Visit for (A, B, C) {
+    fn visit(&self, visitor: &mut dyn Visitor) {
+        visitor.begin_seq(3);
+        self.0.visit(visitor);
+        self.1.visit(visitor);
+        self.2.visit(visitor);
+        visitor.end();
+    }
+}
+
+enum Frame {
+    Struct {
+        fields: Map<String, serde_json::Value>,
+        key: String,
+    },
+    Variant {
+        variant: &'static str,
+        fields: Map<String, serde_json::Value>,
+        key: String,
+    },
+    Seq(Vec<serde_json::Value>),
+}
+
+/// Builds JSON from the visited structure
+#[derive(Default)]
+struct JsonVisitor {
+    stack: Vec<Frame>,
+    result: Option<serde_json::Value>,
+}
+
+impl JsonVisitor {
+    fn push(&mut self, value: serde_json::Value) {
+        match self.stack.last_mut() {
+            Some(Frame::Struct { fields, key }) | Some(Frame::Variant { fields, key, .. }) => {
+                fields.insert(std::mem::take(key), value);
+            }
+            Some(Frame::Seq(values)) => values.push(value),
+            None => self.result = Some(value),
+        }
+    }
+}
+
+impl Visitor for JsonVisitor {
+    fn value(&mut self, value: Value<'_>) {
+        let value = match value {
+            Value::None => serde_json::Value::Null,
+            Value::Bool(value) => value.into(),
+            Value::I64(value) => value.into(),
+            Value::U64(value) => value.into(),
+            Value::F32(value) => Number::from_f64(value as f64).into(),
+            Value::F64(value) => Number::from_f64(value).into(),
+            Value::Str(value) => value.into(),
+            Value::Bytes(bytes) => hex::encode(bytes).into(),
+            Value::Uuid(uuid) => uuid.to_string().into(),
+            Value::Json(value) => value.clone(),
+            Value::Position { x, y, z } => serde_json::json!({ "x": x, "y": y, "z": z }),
+            Value::Angle(degrees) => Number::from_f64(degrees as f64).into(),
+            Value::Nbt(tag) => tag.to_json(),
+        };
+        self.push(value);
+    }
+
+    fn begin_struct(&mut self, _name: &'static str) {
+        self.stack.push(Frame::Struct {
+            fields: Map::new(),
+            key: String::new(),
+        });
+    }
+
+    fn begin_variant(&mut self, _name: &'static str, variant: &'static str) {
+        self.stack.push(Frame::Variant {
+            variant,
+            fields: Map::new(),
+            key: String::new(),
+        });
+    }
+
+    fn begin_seq(&mut self, len: usize) {
+        self.stack.push(Frame::Seq(Vec::with_capacity(len)));
+    }
+
+    fn field(&mut self, name: &str) {
+        if let Some(Frame::Struct { key, .. } | Frame::Variant { key, .. }) = self.stack.last_mut()
+        {
+            *key = name.to_owned();
+        }
+    }
+
+    fn end(&mut self) {
+        let value = match self.stack.pop() {
+            Some(Frame::Struct { fields, .. }) => fields.into(),
+            Some(Frame::Variant {
+                variant, fields, ..
+            }) if fields.is_empty() => variant.into(),
+            Some(Frame::Variant {
+                variant, fields, ..
+            }) => serde_json::json!({ variant: fields }),
+            Some(Frame::Seq(values)) => values.into(),
+            None => return,
+        };
+        self.push(value);
+    }
+}
+
+/// Structs become objects, variants their name or `{"Variant": {fields}}`, bytes hex strings
+/// and NBT goes through `Tag::to_json`
+pub fn to_json(value: &dyn Visit) -> serde_json::Value {
+    let mut visitor = JsonVisitor::default();
+    value.visit(&mut visitor);
+    visitor.result.unwrap_or_default()
+}
@@ -0,0 +1,221 @@
+use std::io::{self, BufWriter, Sink};
+
+use mc_rust_protocol::{
+    LenPrefixedBytes, PrefixedArray, UUID, VarInt,
+    capture::{Capture, CaptureError, CaptureHeader, CaptureWriter, RecordingWriter},
+    packet::{
+        Direction, GameProfile, Intent, Packet, PacketType, State,
+        c2s::{
+            configuration::AcknowledgeFinishConfiguration,
+            handshake::Handshake,
+            login::{EncryptionResponse, LoginAcknowledged, LoginStart},
+            play::ServerboundKeepAlivePlay,
+        },
+        s2c::{
+            configuration::FinishConfiguration,
+            login::{EncryptionRequest, LoginSuccess, SetCompression},
+            play::{ClientboundKeepAlivePlay, ClientboundPluginMessagePlay},
+        },
+    },
+    packet_encoder::NetworkEncoder,
+};
+
+type Encoder = NetworkEncoder<BufWriter<RecordingWriter<Sink, Vec<u8>>>>;
+
+const SECRET: [u8; 16] = *b"0123456789abcdef";
+
+fn send(encoder: &mut Encoder, packet: &impl PacketType) {
+    let mut buf = Vec::new();
+    packet.write(&mut buf).unwrap();
+    encoder.write_packet(&buf).unwrap();
+}
+
+/// Records a whole login with encryption and compression, like a recorder sitting on the
+/// client's socket would
+fn record_session(record_secret: bool) -> Vec<u8> {
+    let capture = CaptureWriter::new(Vec::new(), &CaptureHeader::new()).unwrap();
+    let mut client: Encoder = NetworkEncoder::new(BufWriter::new(
+        capture.writer(Direction::Serverbound, io::sink()),
+    ));
+    let mut server: Encoder = NetworkEncoder::new(BufWriter::new(
+        capture.writer(Direction::Clientbound, io::sink()),
+    ));
+
+    send(
+        &mut client,
+        &Handshake {
+            protocol_version: VarInt(773),
+            server_adress: "localhost".to_owned(),
+            server_port: 25565,
+            intent: Intent::Login,
+        },
+    );
+    send(
+        &mut client,
+        &LoginStart {
+            name: "Notch".to_owned(),
+            player_uuid: UUID::offline_player("Notch"),
+        },
+    );
+    send(
+        &mut server,
+        &EncryptionRequest {
+            server_id: String::new(),
+            public_key: LenPrefixedBytes::new(vec![1; 162]),
+            verify_token: LenPrefixedBytes::new(vec![1, 2, 3, 4]),
+            should_authenticate: false,
+        },
+    );
+    send(
+        &mut client,
+        &EncryptionResponse {
+            shared_secret: LenPrefixedBytes::new(vec![2; 128]),
+            verify_token: LenPrefixedBytes::new(vec![3; 128]),
+        },
+    );
+    client.set_encryption(&SECRET);
+    server.set_encryption(&SECRET);
+    if record_secret {
+        capture.record_shared_secret(&SECRET).unwrap();
+    }
+
+    send(
+        &mut server,
+        &SetCompression {
+            theshold: VarInt(64),
+        },
+    );
+    server.set_compression((64, 6));
+    client.set_compression((64, 6));
+    send(
+        &mut server,
+        &LoginSuccess {
+            profile: GameProfile {
+                uuid: UUID::offline_player("Notch"),
+                username: "Notch".to_owned(),
+                properties: PrefixedArray::new(vec![]),
+            },
+        },
+    );
+    send(&mut client, &LoginAcknowledged {});
+    send(&mut server, &FinishConfiguration {});
+    send(&mut client, &AcknowledgeFinishConfiguration {});
+    send(
+        &mut server,
+        &ClientboundPluginMessagePlay {
+            channel: "test:big".to_owned(),
+            data: vec![7; 1000],
+        },
+    );
+    send(&mut server, &ClientboundKeepAlivePlay { keep_alive_id: 5 });
+    send(&mut client, &ServerboundKeepAlivePlay { keep_alive_id: 5 });
+
+    drop((client, server));
+    capture.into_inner().unwrap()
+}
+
+#[test]
+fn decode_recorded_session() {
+    let capture = Capture::read(&record_session(true)[..]).unwrap();
+    assert_eq!(capture.header.protocol_version, 773);
+    assert_eq!(capture.shared_secret().unwrap(), Some(SECRET));
+
+    let packets: Vec<_> = capture
+        .packets()
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let summary: Vec<_> = packets
+        .iter()
+        .map(|p| (p.direction, p.state, p.packet.as_ref().unwrap().id()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (Direction::Serverbound, State::Handshake, Handshake::ID),
+            (Direction::Serverbound, State::Login, LoginStart::ID),
+            (Direction::Clientbound, State::Login, EncryptionRequest::ID),
+            (Direction::Serverbound, State::Login, EncryptionResponse::ID),
+            (Direction::Clientbound, State::Login, SetCompression::ID),
+            (Direction::Clientbound, State::Login, LoginSuccess::ID),
+            (Direction::Serverbound, State::Login, LoginAcknowledged::ID),
+            (
+                Direction::Clientbound,
+                State::Configuration,
+                FinishConfiguration::ID
+            ),
+            (
+                Direction::Serverbound,
+                State::Configuration,
+                AcknowledgeFinishConfiguration::ID
+            ),
+            (
+                Direction::Clientbound,
+                State::Play,
+                ClientboundPluginMessagePlay::ID
+            ),
+            (
+                Direction::Clientbound,
+                State::Play,
+                ClientboundKeepAlivePlay::ID
+            ),
+            (
+                Direction::Serverbound,
+                State::Play,
+                ServerboundKeepAlivePlay::ID
+            ),
+        ]
+    );
+
+    let Ok(Packet::ClientboundPluginMessagePlay(message)) = &packets[9].packet else {
+        panic!("expected plugin message");
+    };
+    assert_eq!(message.data, vec![7; 1000]);
+    assert!(packets.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+}
+
+#[test]
+fn missing_secret() {
+    let capture = Capture::read(&record_session(false)[..]).unwrap();
+    let results: Vec<_> = capture.packets().unwrap().collect();
+    // everything up to the encryption request decodes, then the reader gives up
+    assert!(results[..3].iter().all(Result::is_ok));
+    assert!(matches!(results[3], Err(CaptureError::MissingSharedSecret)));
+
+    // the key can also come from the header
+    let mut with_header = capture.clone();
+    with_header.header.shared_secret = Some(SECRET);
+    assert!(with_header.packets().unwrap().all(|p| p.is_ok()));
+}
+
+#[test]
+fn header_and_truncation() {
+    let header = CaptureHeader::new()
+        .with_compression_threshold(256)
+        .with_shared_secret(SECRET);
+    let capture = CaptureWriter::new(Vec::new(), &header).unwrap();
+    // compressed framing: length, data length 0, packet id 0
+    capture
+        .record(Direction::Serverbound, &[0x02, 0x00, 0x00])
+        .unwrap();
+    // half of a packet, the capture stopped here
+    capture
+        .record(Direction::Serverbound, &[0x05, 0x00])
+        .unwrap();
+    let bytes = capture.into_inner().unwrap();
+
+    let capture = Capture::read(&bytes[..]).unwrap();
+    assert_eq!(capture.header, header);
+    assert_eq!(capture.records.len(), 2);
+    // a complete packet that doesn't decode (empty handshake) doesn't stop the stream
+    let packets: Vec<_> = capture.packets().unwrap().collect();
+    assert_eq!(packets.len(), 1);
+    let packet = packets[0].as_ref().unwrap();
+    assert_eq!(packet.raw.id, 0);
+    assert!(packet.packet.is_err());
+
+    assert!(matches!(
+        Capture::read(&b"not a capture file"[..]),
+        Err(CaptureError::InvalidMagic)
+    ));
+}
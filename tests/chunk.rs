@@ -0,0 +1,364 @@
+use mc_rust_protocol::{
+    Serializable,
+    chunk::{ChunkConverter, ChunkError, ChunkRegistries},
+    nbt,
+    nbt::{List, Tag},
+};
+use serde_json::json;
+
+fn registries() -> ChunkRegistries {
+    let blocks = json!({
+        "minecraft:air": { "states": [{ "id": 0, "default": true }] },
+        "minecraft:stone": { "states": [{ "id": 1, "default": true }] },
+        "minecraft:oak_log": { "states": [
+            { "id": 2, "properties": { "axis": "x" } },
+            { "id": 3, "properties": { "axis": "y" }, "default": true },
+            { "id": 4, "properties": { "axis": "z" } },
+        ] },
+        "minecraft:cave_air": { "states": [{ "id": 5, "default": true }] },
+        "minecraft:water": { "states": (0..16)
+            .map(|level| json!({ "id": 6 + level, "properties": { "level": level.to_string() } }))
+            .collect::<Vec<_>>() },
+    });
+    let registries = json!({
+        "minecraft:block_entity_type": { "entries": {
+            "minecraft:furnace": { "protocol_id": 0 },
+            "minecraft:chest": { "protocol_id": 1 },
+        } },
+    });
+    ChunkRegistries::from_reports(&blocks, &registries)
+        .unwrap()
+        .with_biomes(["minecraft:badlands", "minecraft:plains", "minecraft:river"])
+}
+
+/// Reads a VarInt from the front of `data`
+fn var_int(data: &mut &[u8]) -> i32 {
+    let mut value = 0;
+    for i in 0.. {
+        let byte = data[0];
+        *data = &data[1..];
+        value |= ((byte & 0x7f) as i32) << (7 * i);
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    value
+}
+
+fn long(data: &mut &[u8]) -> i64 {
+    let (long, rest) = data.split_at(8);
+    *data = rest;
+    i64::from_be_bytes(long.try_into().unwrap())
+}
+
+/// Block count and the palettes of a section: bits, palette and longs
+#[allow(clippy::type_complexity)]
+fn section(data: &mut &[u8]) -> (i16, [(u8, Vec<i32>, Vec<i64>); 2]) {
+    let count = i16::from_be_bytes([data[0], data[1]]);
+    *data = &data[2..];
+    let mut container = |entries: usize, max_bits: u8| {
+        let bits = data[0];
+        *data = &data[1..];
+        let palette = match bits {
+            0 => vec![var_int(data)],
+            _ if bits <= max_bits => {
+                let len = var_int(data);
+                (0..len).map(|_| var_int(data)).collect()
+            }
+            _ => Vec::new(),
+        };
+        let longs = match bits {
+            0 => 0,
+            _ => entries.div_ceil(64 / bits as usize),
+        };
+        (bits, palette, (0..longs).map(|_| long(data)).collect())
+    };
+    let blocks = container(4096, 8);
+    let biomes = container(64, 3);
+    (count, [blocks, biomes])
+}
+
+fn pack(indices: &[u64], bits: usize) -> Vec<i64> {
+    let per_long = 64 / bits;
+    indices
+        .chunks(per_long)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |long, (i, index)| long | (index << (i * bits))) as i64
+        })
+        .collect()
+}
+
+fn list(tags: &[Tag]) -> Tag {
+    Tag::List(List::try_from(tags.to_vec()).unwrap())
+}
+
+#[test]
+fn converts_chunks() {
+    // stone below y 8, then oak logs along y, and cave air on top
+    let indices: Vec<u64> = (0..4096)
+        .map(|i| match i / 256 {
+            0..8 => 0,
+            8..12 => 1,
+            _ => 2,
+        })
+        .collect();
+    let block_data = pack(&indices, 4);
+    let chunk = nbt!({
+        "DataVersion": 4440,
+        "xPos": -3,
+        "zPos": 7,
+        "Status": "minecraft:full",
+        "sections": [
+            {
+                "Y": -1i8,
+                "block_states": {
+                    "palette": [
+                        { "Name": "minecraft:stone" },
+                        { "Name": "minecraft:oak_log", "Properties": { "axis": "y" } },
+                        { "Name": "minecraft:cave_air" },
+                    ],
+                    "data": block_data.clone(),
+                },
+                "biomes": {
+                    "palette": ["minecraft:river", "minecraft:badlands"],
+                    "data": vec![0x0f0fi64],
+                },
+                "BlockLight": vec![0u8; 2048],
+                "SkyLight": vec![0xffu8; 2048],
+            },
+            {
+                "Y": 0i8,
+                "block_states": { "palette": [{ "Name": "minecraft:stone" }] },
+                "biomes": { "palette": ["minecraft:plains"] },
+            },
+            // below the world, only light
+            { "Y": -3i8, "SkyLight": vec![1u8; 2048] },
+        ],
+        "Heightmaps": {
+            "MOTION_BLOCKING": vec![1i64; 37],
+            "OCEAN_FLOOR": vec![2i64; 37],
+        },
+        "block_entities": [
+            { "id": "minecraft:chest", "x": -47, "y": -4, "z": 113, "keepPacked": false, "Lock": "" },
+            { "id": "minecraft:unknown", "x": -47, "y": -3, "z": 113 },
+        ],
+    });
+
+    let converter = ChunkConverter::new(registries()).with_height(-32, 64);
+    let packet = converter.convert(&chunk).unwrap();
+    assert_eq!((packet.chunk_x, packet.chunk_z), (-3, 7));
+
+    let mut data = &packet.data.data.data[..];
+    let sections: Vec<_> = (0..4).map(|_| section(&mut data)).collect();
+    assert!(data.is_empty());
+    // missing sections are air and plains
+    let empty = (0, [(0, vec![0], vec![]), (0, vec![1], vec![])]);
+    assert_eq!(sections[0], empty);
+    assert_eq!(sections[3], empty);
+    // palettes remapped, indices kept
+    assert_eq!(
+        sections[1],
+        (
+            3072,
+            [
+                (4, vec![1, 3, 5], block_data),
+                (1, vec![2, 0], vec![0x0f0f])
+            ]
+        )
+    );
+    assert_eq!(
+        sections[2],
+        (4096, [(0, vec![1], vec![]), (0, vec![1], vec![])])
+    );
+
+    let heightmaps = &packet.data.heightmaps.data;
+    assert_eq!(heightmaps.len(), 1);
+    assert_eq!(heightmaps[0].ty.0, 4);
+    assert_eq!(heightmaps[0].data.data, vec![1; 37]);
+
+    let block_entities = &packet.data.block_entities.data;
+    assert_eq!(block_entities.len(), 1);
+    let chest = &block_entities[0];
+    assert_eq!(
+        (
+            chest.packed_xz.x(),
+            chest.y,
+            chest.packed_xz.z(),
+            chest.ty.0
+        ),
+        (1, -4, 1, 1)
+    );
+    assert_eq!(chest.data, nbt!({ "Lock": "" }));
+
+    // light sections -3 to 4, the section at -1 is bit 2
+    let light = &packet.light;
+    assert!(light.sky_light_mask.get(0) && light.sky_light_mask.get(2));
+    assert!(!light.sky_light_mask.get(1) && !light.sky_light_mask.get(3));
+    assert!(light.empty_block_light_mask.get(2) && !light.block_light_mask.get(2));
+    assert_eq!(light.sky_light_arrays.data.len(), 2);
+    assert_eq!(light.sky_light_arrays.data[1].data, vec![0xff; 2048]);
+    assert!(light.block_light_arrays.data.is_empty());
+
+    // and it serializes
+    let mut bytes = Vec::new();
+    packet.write_to(&mut bytes).unwrap();
+}
+
+#[test]
+fn large_palettes_use_global_ids() {
+    // air, stone, three logs and sixteen water levels need 5 bits on disk
+    let palette: Vec<Tag> = std::iter::empty()
+        .chain([nbt!({ "Name": "minecraft:air" }), nbt!({ "Name": "minecraft:stone" })])
+        .chain(["x", "y", "z"].map(
+            |axis| nbt!({ "Name": "minecraft:oak_log", "Properties": { "axis": axis } }),
+        ))
+        .chain((0..16).map(|level| {
+            nbt!({ "Name": "minecraft:water", "Properties": { "level": level.to_string() } })
+        }))
+        .collect();
+    let indices: Vec<u64> = (0..4096).map(|i| i % 21).collect();
+    let mut registries = registries();
+    // more states than an indirect palette holds
+    registries.add_block_state("minecraft:light", &[], 300, true);
+    let converter = ChunkConverter::new(registries).with_height(0, 16);
+    let chunk = nbt!({
+        "xPos": 0,
+        "zPos": 0,
+        "sections": [{
+            "Y": 0i8,
+            "block_states": { "palette": list(&palette), "data": pack(&indices, 5) },
+        }],
+    });
+    // still indirect with 8 bits
+    let packet = converter.convert(&chunk).unwrap();
+    let mut data = &packet.data.data.data[..];
+    let (count, [(bits, ids, _), _]) = section(&mut data);
+    assert_eq!((count, bits, ids.len()), (4096 - 196, 5, 21));
+
+    // 257 palette entries need 9 bits, more than the network's indirect palettes
+    let mut palette = palette;
+    palette.extend((21..257).map(|_| nbt!({ "Name": "minecraft:light" })));
+    let indices: Vec<u64> = (0..4096).map(|i| i % 257).collect();
+    let chunk = nbt!({
+        "xPos": 0,
+        "zPos": 0,
+        "sections": [{
+            "Y": 0i8,
+            "block_states": { "palette": list(&palette), "data": pack(&indices, 9) },
+        }],
+    });
+    let packet = converter.convert(&chunk).unwrap();
+    let mut data = &packet.data.data.data[..];
+    let (_, [(bits, ids, longs), _]) = section(&mut data);
+    assert_eq!((bits, ids), (9, vec![]));
+    let global: Vec<u64> = indices
+        .iter()
+        .map(|i| match i {
+            0 => 0,
+            1 => 1,
+            2..5 => *i,
+            5..21 => i + 1,
+            _ => 300,
+        })
+        .collect();
+    assert_eq!(longs, pack(&global, 9));
+}
+
+#[test]
+fn invalid_chunks() {
+    let converter = ChunkConverter::new(registries());
+    let proto = nbt!({ "xPos": 0, "zPos": 0, "Status": "minecraft:features" });
+    assert_eq!(
+        converter.convert(&proto),
+        Err(ChunkError::NotFull("minecraft:features".to_owned()))
+    );
+    assert!(matches!(
+        converter.convert(&nbt!({ "zPos": 0 })),
+        Err(ChunkError::InvalidField(_))
+    ));
+
+    let section = |palette: Tag, data: Vec<i64>| {
+        nbt!({
+            "xPos": 0,
+            "zPos": 0,
+            "sections": [{ "Y": 0i8, "block_states": { "palette": palette, "data": data } }],
+        })
+    };
+    let unknown = section(
+        nbt!([{ "Name": "minecraft:oak_log", "Properties": { "axis": "w" } }]),
+        vec![],
+    );
+    assert_eq!(
+        converter.convert(&unknown),
+        Err(ChunkError::UnknownBlock(
+            "minecraft:oak_log[axis=w]".to_owned()
+        ))
+    );
+    let short = section(
+        nbt!([{ "Name": "minecraft:air" }, { "Name": "minecraft:stone" }]),
+        vec![0; 10],
+    );
+    assert!(matches!(
+        converter.convert(&short),
+        Err(ChunkError::InvalidField(_))
+    ));
+    let out_of_palette = section(
+        nbt!([{ "Name": "minecraft:air" }, { "Name": "minecraft:stone" }]),
+        vec![2; 256],
+    );
+    assert!(matches!(
+        converter.convert(&out_of_palette),
+        Err(ChunkError::InvalidField(_))
+    ));
+    assert_eq!(
+        converter.convert(&section(nbt!([]), vec![])),
+        Err(ChunkError::InvalidField("block_states palette".to_owned()))
+    );
+    let no_biomes = nbt!({
+        "xPos": 0,
+        "zPos": 0,
+        "sections": [{ "Y": 0i8, "biomes": { "palette": [] } }],
+    });
+    assert_eq!(
+        converter.convert(&no_biomes),
+        Err(ChunkError::InvalidField("biomes palette".to_owned()))
+    );
+
+    let biome = nbt!({
+        "xPos": 0,
+        "zPos": 0,
+        "sections": [{ "Y": 0i8, "biomes": { "palette": ["minecraft:the_void"] } }],
+    });
+    assert_eq!(
+        converter.convert(&biome),
+        Err(ChunkError::UnknownBiome("minecraft:the_void".to_owned()))
+    );
+
+    // more palette entries than the registry has biomes
+    let one_biome = ChunkConverter::new(registries().with_biomes(["minecraft:plains"]));
+    let duplicates = nbt!({
+        "xPos": 0,
+        "zPos": 0,
+        "sections": [{
+            "Y": 0i8,
+            "biomes": { "palette": vec!["minecraft:plains"; 9], "data": vec![0i64; 4] },
+        }],
+    });
+    assert_eq!(
+        one_biome.convert(&duplicates),
+        Err(ChunkError::InvalidField("palette length".to_owned()))
+    );
+
+    let short_light = nbt!({
+        "xPos": 0,
+        "zPos": 0,
+        "sections": [{ "Y": 0i8, "BlockLight": vec![1u8; 100] }],
+    });
+    assert_eq!(
+        converter.convert(&short_light),
+        Err(ChunkError::InvalidField("BlockLight".to_owned()))
+    );
+}
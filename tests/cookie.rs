@@ -0,0 +1,184 @@
+use std::{net::TcpListener, thread};
+
+use mc_rust_protocol::{
+    cookie::{
+        self, CookieError, CookieJar, CookieStore, MAX_COOKIE_SIZE, TransferTarget,
+        read_transfer_cookies,
+    },
+    packet::{self, Direction, Packet, PacketType, State, c2s::login::CookieResponseLogin},
+    packet_decoder::NetworkDecoder,
+    packet_encoder::NetworkEncoder,
+};
+
+fn send(encoder: &mut NetworkEncoder<impl std::io::Write>, packet: &impl PacketType) {
+    let mut buf = Vec::new();
+    packet.write(&mut buf).unwrap();
+    encoder.write_packet(&buf).unwrap();
+}
+
+#[test]
+fn size_limit() {
+    let mut jar = CookieJar::new();
+    jar.store("test:ok", vec![0; MAX_COOKIE_SIZE]).unwrap();
+    assert!(matches!(
+        jar.store("test:big", vec![0; MAX_COOKIE_SIZE + 1]),
+        Err(CookieError::TooLarge { size, .. }) if size == MAX_COOKIE_SIZE + 1
+    ));
+    assert_eq!(jar.len(), 1);
+
+    assert!(cookie::store_cookie(State::Play, "test:big", vec![0; MAX_COOKIE_SIZE + 1]).is_err());
+    assert!(matches!(
+        cookie::store_cookie(State::Login, "test:ok", vec![]),
+        Err(CookieError::InvalidState(_))
+    ));
+}
+
+#[test]
+fn jar_answers_requests() {
+    let mut jar = CookieJar::new();
+    let store =
+        cookie::store_cookie(State::Configuration, "test:session", b"abc".to_vec()).unwrap();
+    assert!(jar.handle(&store).unwrap().is_none());
+    assert_eq!(jar.get("test:session").unwrap(), b"abc");
+
+    for state in [State::Login, State::Configuration, State::Play] {
+        let request = cookie::request_cookie(state, "test:session").unwrap();
+        let response = jar.handle(&request).unwrap().unwrap();
+        let (key, payload) = cookie::cookie_response(&response).unwrap().unwrap();
+        assert_eq!(key, "test:session");
+        assert_eq!(payload, Some(&b"abc"[..]));
+    }
+
+    let request = cookie::request_cookie(State::Play, "test:missing").unwrap();
+    let response = jar.handle(&request).unwrap().unwrap();
+    assert_eq!(
+        cookie::cookie_response(&response).unwrap().unwrap(),
+        ("test:missing", None)
+    );
+}
+
+#[test]
+fn transfer_flow() {
+    // the first server stores a cookie and transfers the client
+    let target = TransferTarget::new("127.0.0.1", 0);
+    let packets = target
+        .packets(State::Play, &[("test:token", b"secret")])
+        .unwrap();
+    assert_eq!(packets.len(), 2);
+
+    let mut jar = CookieJar::new();
+    let mut transfer = None;
+    for packet in &packets {
+        jar.handle(packet).unwrap();
+        transfer = transfer.or(TransferTarget::from_packet(packet));
+    }
+    assert_eq!(transfer.unwrap(), target);
+
+    // the second server reads it back after the transfer handshake
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut decoder = NetworkDecoder::new(stream.try_clone().unwrap());
+        let mut encoder = NetworkEncoder::new(stream);
+
+        let raw = decoder.get_raw_packet().unwrap();
+        let Packet::Handshake(handshake) = packet::packet_by_id(
+            State::Handshake,
+            Direction::Serverbound,
+            raw.id,
+            &mut &raw.payload[..],
+        )
+        .unwrap() else {
+            panic!("expected a handshake");
+        };
+        assert!(handshake.is_transfer());
+        assert!(matches!(State::from(handshake.intent), State::Login));
+
+        read_transfer_cookies(&mut decoder, &mut encoder, &["test:token", "test:other"]).unwrap()
+    });
+
+    let stream = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut decoder = NetworkDecoder::new(stream.try_clone().unwrap());
+    let mut encoder = NetworkEncoder::new(stream);
+    send(
+        &mut encoder,
+        &TransferTarget::new("127.0.0.1", port).handshake(),
+    );
+    for _ in 0..2 {
+        let raw = decoder.get_raw_packet().unwrap();
+        let request = packet::packet_by_id(
+            State::Login,
+            Direction::Clientbound,
+            raw.id,
+            &mut &raw.payload[..],
+        )
+        .unwrap();
+        let response = jar.handle(&request).unwrap().unwrap();
+        let mut buf = Vec::new();
+        response.write(&mut buf).unwrap();
+        encoder.write_packet(&buf).unwrap();
+    }
+
+    let cookies = server.join().unwrap();
+    assert_eq!(cookies["test:token"].as_deref(), Some(&b"secret"[..]));
+    assert_eq!(cookies["test:other"], None);
+}
+
+/// Serverbound login packets as the client would send them
+fn client_bytes(packets: &[CookieResponseLogin]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut encoder = NetworkEncoder::new(&mut bytes);
+    for packet in packets {
+        send(&mut encoder, packet);
+    }
+    bytes
+}
+
+fn response(key: &str) -> CookieResponseLogin {
+    CookieResponseLogin {
+        key: key.to_owned(),
+        payload: None,
+    }
+}
+
+#[test]
+fn transfer_cookies_pending_keys() {
+    // a key requested twice is requested and answered once
+    let input = client_bytes(&[response("test:b"), response("test:a")]);
+    let mut decoder = NetworkDecoder::new(&input[..]);
+    let mut output = Vec::new();
+    let cookies = read_transfer_cookies(
+        &mut decoder,
+        &mut NetworkEncoder::new(&mut output),
+        &["test:a", "test:a", "test:b"],
+    )
+    .unwrap();
+    assert_eq!(cookies.len(), 2);
+
+    let mut requests = NetworkDecoder::new(&output[..]);
+    let mut requested = Vec::new();
+    while let Ok(raw) = requests.get_raw_packet() {
+        let packet = packet::packet_by_id(
+            State::Login,
+            Direction::Clientbound,
+            raw.id,
+            &mut &raw.payload[..],
+        )
+        .unwrap();
+        let Packet::CookieRequestLogin(request) = packet else {
+            panic!("expected a cookie request");
+        };
+        requested.push(request.key);
+    }
+    assert_eq!(requested, ["test:a", "test:b"]);
+
+    // answers to keys that weren't requested don't count
+    let input = client_bytes(&[response("test:other"), response("test:a")]);
+    let result = read_transfer_cookies(
+        &mut NetworkDecoder::new(&input[..]),
+        &mut NetworkEncoder::new(Vec::new()),
+        &["test:a"],
+    );
+    assert!(matches!(result, Err(CookieError::UnexpectedPacket(_))));
+}
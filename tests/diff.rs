@@ -0,0 +1,198 @@
+use mc_rust_protocol::{
+    PrefixedArray, Serializable, UUID, VarInt,
+    capture::{Capture, CaptureHeader, CaptureWriter},
+    diff::{
+        DiffOptions, FieldDiff, FieldValue, PacketDiff, diff_captures, diff_packets, diff_streams,
+    },
+    packet::{
+        GameProfile, Intent, Packet,
+        c2s::{handshake::Handshake, login::LoginStart},
+        s2c::{
+            configuration::FeatureFlags,
+            login::LoginSuccess,
+            play::{ClientboundKeepAlivePlay, SetHealth},
+        },
+    },
+};
+
+fn login_success(username: &str) -> Packet {
+    Packet::LoginSuccess(LoginSuccess {
+        profile: GameProfile {
+            uuid: UUID::offline_player("Notch"),
+            username: username.to_owned(),
+            properties: PrefixedArray::new(vec![]),
+        },
+    })
+}
+
+fn keep_alive(keep_alive_id: i64) -> Packet {
+    Packet::ClientboundKeepAlivePlay(ClientboundKeepAlivePlay { keep_alive_id })
+}
+
+fn feature_flags(flags: &[&str]) -> Packet {
+    Packet::FeatureFlags(FeatureFlags {
+        feature_flags: PrefixedArray::new(flags.iter().map(|flag| (*flag).to_owned()).collect()),
+    })
+}
+
+fn set_health(health: f32) -> Packet {
+    Packet::SetHealth(SetHealth {
+        health,
+        food: VarInt(20),
+        food_saturation: 5.,
+    })
+}
+
+#[test]
+fn field_differences() {
+    let options = DiffOptions::new();
+    assert_eq!(
+        diff_packets(&login_success("Notch"), &login_success("jeb_"), &options),
+        [FieldDiff::Changed {
+            path: "profile.username".to_owned(),
+            left: FieldValue::Str("Notch".to_owned()),
+            right: FieldValue::Str("jeb_".to_owned()),
+        }]
+    );
+
+    let diffs = diff_packets(
+        &feature_flags(&["minecraft:vanilla"]),
+        &feature_flags(&["minecraft:vanilla", "minecraft:trade_rebalance"]),
+        &options,
+    );
+    assert_eq!(
+        diffs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["feature_flags[1]: added (\"minecraft:trade_rebalance\")"]
+    );
+}
+
+#[test]
+fn tolerances_and_ignored_fields() {
+    let options = DiffOptions::new();
+    assert_eq!(
+        diff_packets(&set_health(20.), &set_health(19.99), &options).len(),
+        1
+    );
+    let options = DiffOptions::new().with_float_tolerance(0.1);
+    assert!(diff_packets(&set_health(20.), &set_health(19.99), &options).is_empty());
+
+    let options = DiffOptions::new();
+    assert_eq!(
+        diff_packets(&keep_alive(1), &keep_alive(2), &options).len(),
+        1
+    );
+    let options = DiffOptions::new().with_volatile_fields();
+    assert!(diff_packets(&keep_alive(1), &keep_alive(2), &options).is_empty());
+
+    // a field of one packet type only
+    let options = DiffOptions::new().with_ignored("LoginSuccess.profile");
+    assert!(diff_packets(&login_success("Notch"), &login_success("jeb_"), &options).is_empty());
+    let options = DiffOptions::new().with_ignored("SetHealth.profile");
+    assert_eq!(
+        diff_packets(&login_success("Notch"), &login_success("jeb_"), &options).len(),
+        1
+    );
+}
+
+#[test]
+fn stream_alignment() {
+    let left = [
+        keep_alive(1),
+        feature_flags(&["minecraft:vanilla"]),
+        set_health(20.),
+        keep_alive(2),
+    ];
+    let right = [
+        keep_alive(1),
+        login_success("Notch"),
+        feature_flags(&["minecraft:vanilla"]),
+        keep_alive(3),
+    ];
+    let diffs = diff_streams(&left, &right, &DiffOptions::new());
+    assert_eq!(
+        diffs,
+        [
+            PacketDiff::Added {
+                index: 1,
+                name: "LoginSuccess"
+            },
+            PacketDiff::Removed {
+                index: 2,
+                name: "SetHealth"
+            },
+            PacketDiff::Changed {
+                left: 3,
+                right: 3,
+                name: "ClientboundKeepAlivePlay",
+                fields: vec![FieldDiff::Changed {
+                    path: "keep_alive_id".to_owned(),
+                    left: FieldValue::Int(2),
+                    right: FieldValue::Int(3),
+                }],
+            },
+        ]
+    );
+    assert_eq!(
+        diffs[2].to_string(),
+        "~ #3/#3 ClientboundKeepAlivePlay\n    keep_alive_id: 2 -> 3"
+    );
+
+    let options = DiffOptions::new().with_volatile_fields();
+    assert_eq!(diff_streams(&left, &right, &options).len(), 2);
+}
+
+fn capture(packets: &[Packet]) -> Capture {
+    let writer = CaptureWriter::new(Vec::new(), &CaptureHeader::new()).unwrap();
+    for packet in packets {
+        let mut data = Vec::new();
+        packet.write(&mut data).unwrap();
+        let mut framed = Vec::new();
+        VarInt(data.len() as i32).write_to(&mut framed).unwrap();
+        framed.extend(data);
+        writer.record(packet.direction(), &framed).unwrap();
+    }
+    Capture::read(&writer.into_inner().unwrap()[..]).unwrap()
+}
+
+#[test]
+fn capture_differences() {
+    let session = |username: &str| {
+        capture(&[
+            Packet::Handshake(Handshake {
+                protocol_version: VarInt(773),
+                server_adress: "localhost".to_owned(),
+                server_port: 25565,
+                intent: Intent::Login,
+            }),
+            Packet::LoginStart(LoginStart {
+                name: username.to_owned(),
+                player_uuid: UUID::offline_player("Notch"),
+            }),
+            login_success(username),
+        ])
+    };
+    let options = DiffOptions::new();
+    assert!(
+        diff_captures(&session("Notch"), &session("Notch"), &options)
+            .unwrap()
+            .is_empty()
+    );
+
+    let diff = diff_captures(&session("Notch"), &session("jeb_"), &options).unwrap();
+    let changed = |diffs: &[PacketDiff]| -> Vec<String> {
+        diffs
+            .iter()
+            .flat_map(|diff| match diff {
+                PacketDiff::Changed { fields, .. } => {
+                    fields.iter().map(ToString::to_string).collect()
+                }
+                _ => vec![],
+            })
+            .collect()
+    };
+    assert_eq!(changed(&diff.serverbound), ["name: \"Notch\" -> \"jeb_\""]);
+    assert_eq!(
+        changed(&diff.clientbound),
+        ["profile.username: \"Notch\" -> \"jeb_\""]
+    );
+}
@@ -0,0 +1,118 @@
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use mc_rust_protocol::{
+    UUID,
+    keep_alive::{Clock, KeepAliveClient, KeepAliveError, KeepAliveServer},
+    packet::{Packet, PlayerAction, State},
+};
+
+#[derive(Clone)]
+struct MockClock(Rc<Cell<Instant>>);
+
+impl MockClock {
+    fn new() -> Self {
+        MockClock(Rc::new(Cell::new(Instant::now())))
+    }
+
+    fn advance(&self, by: Duration) {
+        self.0.set(self.0.get() + by);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.0.get()
+    }
+}
+
+#[test]
+fn server_and_client_exchange() {
+    let clock = MockClock::new();
+    let mut server = KeepAliveServer::with_clock(clock.clone()).interval(Duration::from_secs(15));
+    let mut client = KeepAliveClient::with_clock(clock.clone());
+
+    let packet = server.poll(State::Play).unwrap().unwrap();
+    assert!(matches!(packet, Packet::ClientboundKeepAlivePlay(_)));
+    // nothing new while waiting for the answer
+    assert!(server.poll(State::Play).unwrap().is_none());
+
+    clock.advance(Duration::from_millis(40));
+    let answer = client.handle(&packet).unwrap();
+    let rtt = server.handle(&answer).unwrap().unwrap();
+    assert_eq!(rtt, Duration::from_millis(40));
+    assert_eq!(server.latency(), Some(Duration::from_millis(40)));
+
+    // next one only after the interval
+    clock.advance(Duration::from_secs(10));
+    assert!(server.poll(State::Play).unwrap().is_none());
+    clock.advance(Duration::from_secs(5));
+    let packet = server.poll(State::Configuration).unwrap().unwrap();
+    assert!(matches!(
+        packet,
+        Packet::ClientboundKeepAliveConfiguration(_)
+    ));
+
+    clock.advance(Duration::from_millis(80));
+    let answer = client.handle(&packet).unwrap();
+    assert!(matches!(
+        answer,
+        Packet::ServerboundKeepAliveConfiguration(_)
+    ));
+    server.handle(&answer).unwrap().unwrap();
+    // (40 * 3 + 80) / 4
+    assert_eq!(server.latency(), Some(Duration::from_millis(50)));
+
+    let uuid = UUID::offline_player("Notch");
+    let Some(Packet::PlayerInfoUpdate(update)) = server.latency_update(uuid) else {
+        panic!("expected a player info update");
+    };
+    let actions = &update.actions.players_actions[0];
+    assert_eq!(actions.uuid, uuid);
+    assert!(matches!(
+        actions.player_actions[0],
+        PlayerAction::UpdateLatency { ping } if ping.0 == 50
+    ));
+}
+
+#[test]
+fn server_rejects_wrong_id() {
+    let mut server = KeepAliveServer::with_clock(MockClock::new());
+    assert!(matches!(
+        server.handle_response(5),
+        Err(KeepAliveError::UnexpectedId {
+            expected: None,
+            received: 5
+        })
+    ));
+
+    let Some(Packet::ClientboundKeepAlivePlay(packet)) = server.poll(State::Play).unwrap() else {
+        panic!("expected a keep alive");
+    };
+    assert!(server.handle_response(packet.keep_alive_id + 1).is_err());
+    assert!(server.handle_response(packet.keep_alive_id).is_ok());
+}
+
+#[test]
+fn dead_peers_time_out() {
+    let clock = MockClock::new();
+    let mut server = KeepAliveServer::with_clock(clock.clone()).timeout(Duration::from_secs(15));
+    let client = KeepAliveClient::with_clock(clock.clone()).timeout(Duration::from_secs(30));
+
+    assert!(server.poll(State::Login).unwrap().is_none());
+    server.poll(State::Play).unwrap().unwrap();
+    clock.advance(Duration::from_secs(14));
+    assert!(server.poll(State::Play).unwrap().is_none());
+    clock.advance(Duration::from_secs(1));
+    assert!(matches!(
+        server.poll(State::Play),
+        Err(KeepAliveError::TimedOut(_))
+    ));
+
+    assert!(client.poll().is_ok());
+    clock.advance(Duration::from_secs(15));
+    assert!(matches!(client.poll(), Err(KeepAliveError::TimedOut(_))));
+}
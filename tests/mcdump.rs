@@ -0,0 +1,116 @@
+use std::process::Command;
+
+fn mcdump(args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_mcdump"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn dump_sample_stream() {
+    let (success, output) = mcdump(&[
+        "stream",
+        "tests/sample_data/S2C.bin",
+        "--direction",
+        "clientbound",
+        "--aes-key",
+        "7532710be168544415a69d2a122b4230",
+        "--json",
+    ]);
+    assert!(success);
+    let lines: Vec<serde_json::Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines[0]["name"], "EncryptionRequest");
+    assert_eq!(lines[0]["offset"], 0);
+    assert_eq!(lines[1]["name"], "SetCompression");
+    assert_eq!(lines[1]["fields"], serde_json::json!({ "theshold": 256 }));
+    assert_eq!(lines[2]["name"], "LoginSuccess");
+    assert_eq!(lines[3]["state"], "configuration");
+    assert!(lines.iter().any(|line| line["state"] == "play"));
+}
+
+#[test]
+fn dump_hex_payload() {
+    let (success, output) = mcdump(&[
+        "hex",
+        "00 00 00 00 00 00 00 05",
+        "--direction",
+        "clientbound",
+        "--state",
+        "play",
+        "--id",
+        "0x2b",
+    ]);
+    assert!(success);
+    assert!(output.starts_with("#0 @0x0 Clientbound Play 0x2b ClientboundKeepAlivePlay (8 bytes)"));
+    assert!(output.contains("keep_alive_id: 5"));
+
+    let (success, _) = mcdump(&[
+        "hex",
+        "05",
+        "--direction",
+        "clientbound",
+        "--state",
+        "play",
+        "--id",
+        "0x2b",
+    ]);
+    assert!(!success);
+}
+
+#[test]
+fn truncated_stream_tail() {
+    // SetCompression(256), then the start of a packet announcing 5 bytes
+    let complete: &[u8] = &[0x03, 0x03, 0x80, 0x02];
+    let dir = std::env::temp_dir();
+    let clean = dir.join(format!("mcdump-clean-{}.bin", std::process::id()));
+    let truncated = dir.join(format!("mcdump-truncated-{}.bin", std::process::id()));
+    std::fs::write(&clean, complete).unwrap();
+    std::fs::write(&truncated, [complete, &[0x05, 0x00]].concat()).unwrap();
+
+    let dump = |path: &std::path::Path| {
+        mcdump(&[
+            "stream",
+            path.to_str().unwrap(),
+            "--direction",
+            "clientbound",
+        ])
+    };
+    let (success, output) = dump(&clean);
+    assert!(success);
+    assert_eq!(
+        output.lines().filter(|line| line.starts_with('#')).count(),
+        1
+    );
+
+    // the packets before are printed, then the cut off one is reported
+    let (success, output) = dump(&truncated);
+    assert!(!success);
+    assert_eq!(
+        output.lines().filter(|line| line.starts_with('#')).count(),
+        1
+    );
+
+    // without compression: a StatusRequest announcing 10 bytes, cut off after 3
+    std::fs::write(&truncated, [0x0a, 0x00, 0x01, 0x02]).unwrap();
+    let (success, output) = mcdump(&[
+        "stream",
+        truncated.to_str().unwrap(),
+        "--direction",
+        "serverbound",
+        "--state",
+        "status",
+    ]);
+    assert!(!success);
+    assert!(!output.lines().any(|line| line.starts_with('#')));
+
+    std::fs::remove_file(clean).unwrap();
+    std::fs::remove_file(truncated).unwrap();
+}
@@ -0,0 +1,656 @@
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use mc_rust_protocol::{
+    nbt,
+    nbt::{Compression, List, NbtFile, Tag, TagError, read_string, write_string},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+// {Data:{LevelName:"world"}}, named "" like level.dat
+const NAMED: &[u8] = &[
+    10, 0, 0, // compound, empty name
+    10, 0, 4, b'D', b'a', b't', b'a', // compound "Data"
+    8, 0, 9, b'L', b'e', b'v', b'e', b'l', b'N', b'a', b'm', b'e', 0, 5, b'w', b'o', b'r', b'l',
+    b'd', // string "LevelName"
+    0, 0,
+];
+
+fn level() -> Tag {
+    let mut data = Tag::new_compound();
+    data.put("LevelName", Tag::String("world".to_owned()));
+    let mut root = Tag::new_compound();
+    root.put("Data", data);
+    root
+}
+
+#[test]
+fn named_and_network() {
+    let (name, tag) = Tag::read_named(&mut &NAMED[..]).unwrap();
+    assert_eq!((name.as_str(), &tag), ("", &level()));
+    let mut bytes = Vec::new();
+    tag.write_named(&mut bytes, "").unwrap();
+    assert_eq!(bytes, NAMED);
+
+    // network NBT is the same without the root's name
+    let mut network = Vec::new();
+    tag.write_network(&mut network).unwrap();
+    assert_eq!(network[..], [&NAMED[..1], &NAMED[3..]].concat());
+    assert_eq!(Tag::read_network(&mut &network[..]).unwrap(), tag);
+
+    let mut bytes = Vec::new();
+    Tag::Int(7).write_named(&mut bytes, "hi").unwrap();
+    assert_eq!(bytes, [3, 0, 2, b'h', b'i', 0, 0, 0, 7]);
+}
+
+#[test]
+fn malformed_binary() {
+    // negative lengths, a huge length without the data, a short byte array
+    for bytes in [
+        &[11, 0xff, 0xff, 0xff, 0xff][..],
+        &[7, 0x80, 0, 0, 0],
+        &[12, 0x7f, 0xff, 0xff, 0xff, 0, 0],
+        &[7, 0, 0, 0, 5, 1, 2],
+    ] {
+        assert!(Tag::read_network(&mut &bytes[..]).is_err());
+    }
+    assert_eq!(
+        Tag::read_network(&mut &[7, 0, 0, 0, 2, 1, 2][..]).unwrap(),
+        Tag::ByteArray(vec![1, 2])
+    );
+
+    // 512 levels take about 2 MiB of stack in debug builds, more than a test thread has
+    let deep = std::thread::Builder::new().stack_size(8 << 20).spawn(|| {
+        // lists of lists and compounds holding a compound "a", 512 deep and one more
+        let lists =
+            |depth: usize| [vec![9], [9, 0, 0, 0, 1].repeat(depth - 1), vec![0; 5]].concat();
+        let compounds =
+            |depth: usize| [vec![10], [10, 0, 1, b'a'].repeat(depth - 1), vec![0; depth]].concat();
+        for nested in [lists, compounds] {
+            assert!(Tag::read_network(&mut &nested(512)[..]).is_ok());
+            assert!(Tag::read_network(&mut &nested(513)[..]).is_err());
+            assert!(Tag::read_network(&mut &nested(100_000)[..]).is_err());
+        }
+    });
+    deep.unwrap().join().unwrap();
+    // a list of End tags can't have elements
+    assert!(Tag::read_network(&mut &[9, 0, 0x7f, 0xff, 0xff, 0xff][..]).is_err());
+}
+
+#[test]
+fn files() {
+    for compression in [Compression::Gzip, Compression::Zlib, Compression::None] {
+        let file = NbtFile::new(level())
+            .with_name("level")
+            .with_compression(compression);
+        let mut bytes = Vec::new();
+        file.write(&mut bytes).unwrap();
+        let read = NbtFile::read(&bytes[..]).unwrap();
+        assert_eq!(read, file);
+
+        let mut again = Vec::new();
+        read.write(&mut again).unwrap();
+        assert_eq!(again, bytes);
+    }
+
+    let file = NbtFile::read(NAMED).unwrap();
+    assert_eq!(file.compression, Compression::None);
+    assert!(NbtFile::read(&[NAMED, &[0]].concat()[..]).is_err());
+}
+
+#[test]
+fn modified_utf8() {
+    let cases: [(&str, &[u8]); 4] = [
+        ("abc", b"abc"),
+        ("a\0b", &[b'a', 0xc0, 0x80, b'b']),
+        ("é", &[0xc3, 0xa9]),
+        // U+1F600 as the surrogate pair D83D DE00
+        ("😀", &[0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]),
+    ];
+    for (s, encoded) in cases {
+        let mut bytes = Vec::new();
+        write_string(&mut bytes, s).unwrap();
+        assert_eq!(bytes[2..], *encoded, "{:?}", s);
+        assert_eq!(bytes[..2], (encoded.len() as u16).to_be_bytes());
+        assert_eq!(read_string(&mut &bytes[..]).unwrap(), s);
+    }
+
+    // a lone surrogate, a 4 byte UTF-8 sequence and a truncated sequence
+    for bytes in [
+        &[0, 3, 0xed, 0xa0, 0xbd][..],
+        &[0, 4, 0xf0, 0x9f, 0x98, 0x80],
+        &[0, 1, 0xc3],
+    ] {
+        assert!(read_string(&mut &bytes[..]).is_err());
+    }
+
+    assert!(write_string(&mut Vec::new(), &"a".repeat(65535)).is_ok());
+    assert!(write_string(&mut Vec::new(), &"a".repeat(65536)).is_err());
+    // 3 bytes each
+    assert!(write_string(&mut Vec::new(), &"\u{800}".repeat(21846)).is_err());
+}
+
+#[test]
+fn snbt_parsing() {
+    let tag = Tag::from_snbt("{Count:1b,tag:{Damage:5s},list:[I;1,2,3]}").unwrap();
+    assert_eq!(tag.get("Count"), Some(&Tag::Byte(1)));
+    assert_eq!(tag.get("tag").unwrap().get("Damage"), Some(&Tag::Short(5)));
+    assert_eq!(tag.get("list"), Some(&Tag::IntArray(vec![1, 2, 3])));
+
+    let cases = [
+        ("1", Tag::Int(1)),
+        ("-2L", Tag::Long(-2)),
+        ("1.5", Tag::Double(1.5)),
+        (".5f", Tag::Float(0.5)),
+        ("1e3", Tag::Double(1000.)),
+        ("3d", Tag::Double(3.)),
+        ("0b", Tag::Byte(0)),
+        ("0xff", Tag::Int(255)),
+        ("0x1fsb", Tag::Byte(31)),
+        ("0b101", Tag::Int(5)),
+        ("255ub", Tag::Byte(-1)),
+        ("1_000", Tag::Int(1000)),
+        ("true", Tag::Byte(1)),
+        ("abc", Tag::String("abc".to_owned())),
+        ("1.2.3", Tag::String("1.2.3".to_owned())),
+        ("'say \"hi\"'", Tag::String("say \"hi\"".to_owned())),
+        ("\"a\\nb\\u00e9\"", Tag::String("a\nbé".to_owned())),
+        ("[B;1b,-2b]", Tag::ByteArray(vec![1, 254])),
+        ("[L; 1L, 2L, ]", Tag::LongArray(vec![1, 2])),
+        ("[]", Tag::new_list()),
+        (" [ 1 , 2 , ] ", nbt!([1, 2])),
+        ("{}", Tag::new_compound()),
+    ];
+    for (snbt, tag) in cases {
+        assert_eq!(Tag::from_snbt(snbt), Ok(tag), "{}", snbt);
+    }
+
+    for snbt in [
+        "128b",
+        "-129b",
+        "2147483648",
+        "-1ub",
+        "[1,2b]",
+        "[B;1.5]",
+        "{a:1,}b",
+        "{:1}",
+        "\"abc",
+        "\"\\q\"",
+        // `:` needs quotes
+        "minecraft:stone",
+        "",
+    ] {
+        assert!(Tag::from_snbt(snbt).is_err(), "{}", snbt);
+    }
+    // only an unquoted key can't be empty
+    let tag = Tag::from_snbt("{\"\":1b}").unwrap();
+    assert_eq!(tag.get(""), Some(&Tag::Byte(1)));
+    let err = Tag::from_snbt("{a:1 b:2}").unwrap_err();
+    assert_eq!(
+        (err.position, err.message.as_str()),
+        (5, "expected ',' or '}'")
+    );
+}
+
+#[test]
+fn snbt_depth_limit() {
+    let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+    assert!(Tag::from_snbt(&nested(512)).is_ok());
+    let err = Tag::from_snbt(&nested(513)).unwrap_err();
+    assert_eq!(err.position, 512);
+    // errors instead of overflowing the stack
+    assert!(Tag::from_snbt(&nested(100_000)).is_err());
+    assert!(Tag::from_snbt(&"{a:".repeat(100_000)).is_err());
+    assert!(
+        format!("a[{}]", "{b:".repeat(100_000))
+            .parse::<nbt::Path>()
+            .is_err()
+    );
+}
+
+#[test]
+fn snbt_printing() {
+    let snbt = "{a:[B;1b,2b],b:[{c:\"d\"},{}],e:'say \"hi\"',\"f g\":[1.5f,2f],h:[L;3L]}";
+    let tag = Tag::from_snbt(snbt).unwrap();
+    assert_eq!(tag.to_snbt(), snbt);
+    assert_eq!(
+        tag.to_snbt_pretty(),
+        "{
+    a: [B; 1b, 2b],
+    b: [
+        {
+            c: \"d\"
+        },
+        {}
+    ],
+    e: 'say \"hi\"',
+    \"f g\": [1.5f, 2f],
+    h: [L; 3L]
+}"
+    );
+    assert_eq!(Tag::from_snbt(&tag.to_snbt_pretty()).unwrap(), tag);
+
+    let tag = Tag::String("a\\b\n\u{1}".to_owned());
+    assert_eq!(tag.to_snbt(), "\"a\\\\b\\n\\u0001\"");
+    assert_eq!(Tag::from_snbt(&tag.to_snbt()).unwrap(), tag);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct DimensionType {
+    has_skylight: bool,
+    height: i32,
+    coordinate_scale: f64,
+    fixed_time: Option<i64>,
+    effects: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DyeColor {
+    Black,
+    Red,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SignText {
+    color: DyeColor,
+    has_glowing_text: bool,
+    messages: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SignBlockEntity {
+    front_text: SignText,
+    is_waxed: bool,
+    #[serde(with = "nbt::int_array")]
+    owner: Vec<i32>,
+    #[serde(with = "nbt::long_array")]
+    heightmap: [i64; 2],
+    #[serde(with = "nbt::byte_array")]
+    flags: Vec<i8>,
+    custom: Tag,
+}
+
+#[test]
+fn serde_structs() {
+    let tag = Tag::from_snbt(
+        "{has_skylight:1b,height:384,coordinate_scale:1d,effects:\"minecraft:overworld\",ultrawarm:0b}",
+    )
+    .unwrap();
+    let dimension: DimensionType = nbt::from_tag(tag).unwrap();
+    assert_eq!(
+        dimension,
+        DimensionType {
+            has_skylight: true,
+            height: 384,
+            coordinate_scale: 1.,
+            fixed_time: None,
+            effects: "minecraft:overworld".to_owned(),
+        }
+    );
+    // `None` is left out, the fields keep their order
+    assert_eq!(
+        nbt::to_tag(&dimension).unwrap().to_snbt(),
+        "{has_skylight:1b,height:384,coordinate_scale:1d,effects:\"minecraft:overworld\"}"
+    );
+
+    let snbt = "{custom:{a:[I;1],b:[B;2b]},flags:[B;1b,-1b],front_text:{color:\"red\",\
+        has_glowing_text:0b,messages:[\"hi\",\"\"]},heightmap:[L;3L,4L],is_waxed:1b,owner:[I;5,6]}";
+    let sign: SignBlockEntity = nbt::from_tag(Tag::from_snbt(snbt).unwrap()).unwrap();
+    assert_eq!(sign.front_text.color, DyeColor::Red);
+    assert_eq!(sign.owner, [5, 6]);
+    assert_eq!(sign.custom.get("a"), Some(&Tag::IntArray(vec![1])));
+    let mut tag = nbt::to_tag(&sign).unwrap();
+    tag.sort_keys();
+    assert_eq!(tag.to_snbt(), snbt);
+
+    let mut bytes = Vec::new();
+    nbt::to_writer(&mut bytes, &sign).unwrap();
+    let read: SignBlockEntity = nbt::from_reader(&mut &bytes[..]).unwrap();
+    assert_eq!(read, sign);
+    assert_eq!(
+        nbt::from_tag::<DyeColor>(Tag::String("black".to_owned())).unwrap(),
+        DyeColor::Black
+    );
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct LightSection {
+    #[serde(rename = "BlockLight", with = "nbt::byte_array")]
+    block_light: Vec<u8>,
+    #[serde(rename = "SkyLight", with = "nbt::byte_array")]
+    sky_light: Vec<i8>,
+}
+
+#[test]
+fn serde_unsigned_bytes() {
+    // `u8` elements keep their bits, `i8` ones their sign
+    let mut tag = Tag::new_compound();
+    tag.put("BlockLight", Tag::ByteArray(vec![0, 128, 200, 255]));
+    tag.put("SkyLight", Tag::ByteArray(vec![255]));
+    let section: LightSection = nbt::from_tag(tag.clone()).unwrap();
+    assert_eq!(section.block_light, [0, 128, 200, 255]);
+    assert_eq!(section.sky_light, [-1]);
+    assert_eq!(nbt::to_tag(&section).unwrap(), tag);
+    assert_eq!(
+        nbt::from_tag::<Vec<u8>>(Tag::ByteArray(vec![200, 1])).unwrap(),
+        [200, 1]
+    );
+}
+
+#[test]
+fn serde_errors() {
+    // wrong type, number out of range, missing field
+    for snbt in [
+        "{has_skylight:1b,height:\"a\",coordinate_scale:1d,effects:\"\"}",
+        "{has_skylight:1b,height:3000000000L,coordinate_scale:1d,effects:\"\"}",
+        "{has_skylight:1b,coordinate_scale:1d,effects:\"\"}",
+    ] {
+        assert!(nbt::from_tag::<DimensionType>(Tag::from_snbt(snbt).unwrap()).is_err());
+    }
+    // lists hold one type, compound keys are strings
+    assert!(nbt::to_tag(&(1, "a")).is_err());
+    assert!(nbt::to_tag(&HashMap::from([(1, 2)])).is_err());
+    assert!(nbt::to_tag(&200u8).is_err());
+    assert_eq!(
+        nbt::to_tag(&HashMap::from([("a", 2u8)])).unwrap().to_snbt(),
+        "{a:2b}"
+    );
+}
+
+#[test]
+fn compound_order() {
+    let tag = Tag::from_snbt("{b:1,a:{d:1,c:2},list:[{z:1,y:2}]}").unwrap();
+    assert_eq!(tag.to_snbt(), "{b:1,a:{d:1,c:2},list:[{z:1,y:2}]}");
+    let mut bytes = Vec::new();
+    tag.write_network(&mut bytes).unwrap();
+    let read = Tag::read_network(&mut &bytes[..]).unwrap();
+    assert_eq!(
+        read.as_compound().unwrap().keys().collect::<Vec<_>>(),
+        ["b", "a", "list"]
+    );
+    let mut again = Vec::new();
+    read.write_network(&mut again).unwrap();
+    assert_eq!(again, bytes);
+
+    // replacing keeps the position
+    let mut replaced = tag.clone();
+    replaced.put("b", Tag::Int(2));
+    assert_eq!(replaced.to_snbt(), "{b:2,a:{d:1,c:2},list:[{z:1,y:2}]}");
+
+    let mut sorted = tag.clone();
+    sorted.sort_keys();
+    assert_eq!(sorted.to_snbt(), "{a:{c:2,d:1},b:1,list:[{y:2,z:1}]}");
+    let mut sorted_bytes = Vec::new();
+    tag.write_sorted(&mut sorted_bytes).unwrap();
+    let mut expected = Vec::new();
+    sorted.write_network(&mut expected).unwrap();
+    assert_eq!(sorted_bytes, expected);
+    // the order doesn't matter for equality
+    assert_eq!(sorted, tag);
+}
+
+#[test]
+fn typed_lists() {
+    let mut list = List::new();
+    list.push(1i32).unwrap();
+    assert_eq!(
+        list.push("two"),
+        Err(TagError::ListType {
+            list: "Int",
+            element: "String"
+        })
+    );
+    assert!(List::try_from(vec![Tag::Byte(1), Tag::Int(2)]).is_err());
+    assert_eq!(list.set(0, Tag::Long(3)), Ok(Some(Tag::Int(1))));
+    assert_eq!(list.element_type(), Some("Long"));
+
+    // a mixed list made through `get_mut` doesn't get written
+    let mut tag = nbt!({ "list": [1i8, 2i8] });
+    if let Some(Tag::List(list)) = tag.get_mut("list") {
+        *list.get_mut(1).unwrap() = Tag::Int(2);
+    }
+    assert!(tag.write_network(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn editing() {
+    let mut tag = nbt!({
+        "name": "Steve",
+        "health": 20.0f32,
+        "flying": true,
+        "pos": [1.5, 64.0, -3.5],
+        "inventory": [{ "slot": 0i8, "id": "minecraft:stone" }],
+        "scores": vec![1, 2, 3],
+    });
+    assert_eq!(tag.get_as::<&str>("name"), Some("Steve"));
+    assert_eq!(tag.get_or("health", 0.0f32), 20.0);
+    assert_eq!(tag.get_or("missing", 5i32), 5);
+    // wrong types fall back to the default
+    assert_eq!(tag.get_or("health", 1i32), 1);
+    assert!(tag.get_or("flying", false));
+    assert_eq!(
+        tag.get("pos").and_then(Tag::as_list).map(<[Tag]>::len),
+        Some(3)
+    );
+    assert_eq!(tag.get("scores"), Some(&Tag::IntArray(vec![1, 2, 3])));
+
+    assert_eq!(tag.insert("health", 10.0f32), Ok(Some(Tag::Float(20.0))));
+    assert_eq!(
+        tag.as_compound().unwrap().keys().collect::<Vec<_>>(),
+        ["name", "health", "flying", "pos", "inventory", "scores"]
+    );
+    assert_eq!(tag.remove("flying"), Some(Tag::Byte(1)));
+    assert_eq!(tag.remove("flying"), None);
+    tag.get_mut("inventory")
+        .unwrap()
+        .push(nbt!({ "slot": 1i8 }))
+        .unwrap();
+    assert_eq!(
+        tag.get_mut("pos").unwrap().push("x"),
+        Err(TagError::ListType {
+            list: "Double",
+            element: "String"
+        })
+    );
+    assert_eq!(
+        Tag::Int(1).insert("a", 1i8),
+        Err(TagError::NotACompound("Int"))
+    );
+    assert_eq!(
+        tag.get_mut("name").unwrap().push(1i8),
+        Err(TagError::NotAList("String"))
+    );
+
+    let map: IndexMap<String, i32> = [("a".to_owned(), 1)].into_iter().collect();
+    assert_eq!(Tag::from(map), nbt!({ "a": 1 }));
+    let tag: Tag = [("b", 2i64)].into_iter().collect();
+    assert_eq!(tag.to_string(), "{b:2L}");
+}
+
+#[test]
+fn paths() {
+    let player = nbt!({
+        "Inventory": [
+            { "Slot": 0i8, "id": "minecraft:stone", "count": 64i8 },
+            { "Slot": 1i8, "id": "minecraft:dirt", "count": 3i8 },
+        ],
+        "display": { "Name": "Steve" },
+        "weird key": 1,
+    });
+    let get = |path: &str| {
+        let path: nbt::Path = path.parse().unwrap();
+        path.get(&player).into_iter().cloned().collect::<Vec<_>>()
+    };
+    assert_eq!(get("display.Name"), [Tag::from("Steve")]);
+    assert_eq!(get("Inventory[1].id"), [Tag::from("minecraft:dirt")]);
+    assert_eq!(get("Inventory[-1].Slot"), [Tag::Byte(1)]);
+    assert_eq!(get("Inventory[{Slot:1b}].count"), [Tag::Byte(3)]);
+    assert_eq!(get("Inventory[].Slot"), [Tag::Byte(0), Tag::Byte(1)]);
+    assert_eq!(get("display{Name:\"Steve\"}.Name").len(), 1);
+    assert_eq!(get("display{Name:\"Alex\"}.Name"), []);
+    assert_eq!(get("{display:{}}.'weird key'"), [Tag::Int(1)]);
+    assert_eq!(get("Inventory[5]"), []);
+
+    for path in [
+        "Inventory[{Slot:1b}].count",
+        "{display:{}}.\"weird key\"",
+        "a[][0].b{c:1b}",
+    ] {
+        assert_eq!(path.parse::<nbt::Path>().unwrap().to_string(), path);
+    }
+    for (path, position) in [("a..b", 2), ("a[x]", 2), ("a[0", 3), ("a b", 1), ("", 0)] {
+        assert_eq!(
+            path.parse::<nbt::Path>().unwrap_err().position,
+            position,
+            "{}",
+            path
+        );
+    }
+}
+
+#[test]
+fn path_editing() {
+    let path = |path: &str| path.parse::<nbt::Path>().unwrap();
+    let mut tag = nbt!({ "Inventory": [{ "Slot": 0i8 }, { "Slot": 1i8 }] });
+
+    // missing parents are created
+    assert_eq!(path("a.b[].c").set(&mut tag, &Tag::Int(1)), Ok(1));
+    assert_eq!(tag.get("a"), Some(&nbt!({ "b": [{ "c": 1 }] })));
+    assert_eq!(path("a.b[].c").set(&mut tag, &Tag::Int(1)), Ok(0));
+    assert_eq!(
+        path("Inventory[{Slot:1b}].count").set(&mut tag, &Tag::Byte(5)),
+        Ok(1)
+    );
+    assert_eq!(
+        path("Inventory[0]").set(&mut tag, &Tag::Int(1)),
+        Err(TagError::ListType {
+            list: "Compound",
+            element: "Int"
+        })
+    );
+    assert_eq!(path("Inventory[-1].count").get(&tag), [&Tag::Byte(5)]);
+
+    assert_eq!(
+        path("Inventory[{Slot:0b}]").merge(&mut tag, &nbt!({ "id": "minecraft:stone" })),
+        Ok(1)
+    );
+    assert_eq!(
+        path("Inventory[0]").get(&tag),
+        [&nbt!({ "Slot": 0i8, "id": "minecraft:stone" })]
+    );
+    assert_eq!(
+        path("a")
+            .merge(&mut tag, &nbt!({ "b": 1 }))
+            .and(path("a").merge(&mut tag, &nbt!({}))),
+        Ok(0)
+    );
+    assert_eq!(tag.get("a"), Some(&nbt!({ "b": 1 })));
+
+    assert_eq!(path("Inventory[{Slot:0b}]").remove(&mut tag), Ok(1));
+    assert_eq!(path("Inventory[].Slot").get(&tag), [&Tag::Byte(1)]);
+    assert_eq!(path("a.b").remove(&mut tag), Ok(1));
+    assert_eq!(path("a.b").remove(&mut tag), Ok(0));
+    assert_eq!(path("{}").remove(&mut tag), Err(TagError::PathRoot));
+    for tag in path("Inventory[]").get_mut(&mut tag) {
+        tag.insert("count", 1i8).unwrap();
+    }
+    assert_eq!(tag.to_string(), "{Inventory:[{Slot:1b,count:1b}],a:{}}");
+}
+
+#[test]
+fn json() {
+    let tag = nbt!({
+        "text": "hi",
+        "bold": true,
+        "size": 1.5f32,
+        "ratio": 0.1f64,
+        "big": 5_000_000_000i64,
+        "bytes": vec![1u8, 2],
+        "ints": vec![1, 2],
+        "extra": [{ "text": "a" }, { "text": "b" }],
+    });
+    let json = json!({
+        "text": "hi",
+        "bold": 1,
+        "size": 1.5,
+        "ratio": 0.1,
+        "big": 5_000_000_000i64,
+        "bytes": [1, 2],
+        "ints": [1, 2],
+        "extra": [{ "text": "a" }, { "text": "b" }],
+    });
+    assert_eq!(tag.to_json(), json);
+    assert_eq!(Tag::Float(0.1).to_json(), json!(0.1));
+
+    // numbers shrink and integer lists turn into arrays
+    let back = Tag::from_json(&json);
+    assert_eq!(back.get("bold"), Some(&Tag::Byte(1)));
+    assert_eq!(back.get("ratio"), Some(&Tag::Double(0.1)));
+    assert_eq!(back.get("big"), Some(&Tag::Long(5_000_000_000)));
+    assert_eq!(back.get("ints"), Some(&Tag::ByteArray(vec![1, 2])));
+    assert_eq!(
+        Tag::from_json(&json!([1, 300])).to_string(),
+        r#"[{"":1b},{"":300s}]"#
+    );
+    assert_eq!(
+        Tag::from_json(&json!([70000, 80000])),
+        Tag::IntArray(vec![70000, 80000])
+    );
+
+    // mixed lists wrap their elements and unwrap again
+    let mixed = Tag::from_json(&json!(["a", { "text": "b" }, { "": 1 }, null]));
+    assert_eq!(
+        mixed,
+        Tag::from_snbt(r#"[{"":"a"},{text:"b"},{"":{"":1b}}]"#).unwrap()
+    );
+    assert_eq!(mixed.to_json(), json!(["a", { "text": "b" }, { "": 1 }]));
+    assert_eq!(Tag::from_json(&json!({ "a": null })), Tag::new_compound());
+}
+
+#[test]
+fn typed_json() {
+    let tag = nbt!({
+        "byte": 1i8,
+        "short": 2i16,
+        "long": 3i64,
+        "float": 0.1f32,
+        "nan": f64::NAN,
+        "bytes": vec![255u8],
+        "longs": vec![1i64],
+        "list": [vec![1, 2], vec![3]],
+        "nested": { "a/b": "c" },
+    });
+    let json = tag.to_typed_json();
+    assert_eq!(json["compound"]["byte"], json!({ "byte": 1 }));
+    assert_eq!(json["compound"]["float"], json!({ "float": 0.1 }));
+    assert_eq!(json["compound"]["nan"], json!({ "double": "NaN" }));
+    assert_eq!(json["compound"]["bytes"], json!({ "byte_array": [-1] }));
+    assert_eq!(
+        json["compound"]["list"],
+        json!({ "list": [{ "int_array": [1, 2] }, { "int_array": [3] }] })
+    );
+    let back = Tag::from_typed_json(&json).unwrap();
+    // NaN != NaN
+    assert_eq!(back.to_snbt(), tag.to_snbt());
+    assert_eq!(
+        Tag::from_typed_json(&serde_json::from_str(&json.to_string()).unwrap())
+            .unwrap()
+            .to_snbt(),
+        tag.to_snbt()
+    );
+
+    let err = |json: serde_json::Value| Tag::from_typed_json(&json).unwrap_err();
+    assert_eq!(err(json!(1)).pointer, "");
+    assert_eq!(
+        err(json!({ "byte": 300 })).message,
+        "expected an integer for byte"
+    );
+    assert_eq!(
+        err(json!({ "compound": { "a/b": { "list": [{ "int": 1 }, { "byte": 1 }] } } })).pointer,
+        "/a~1b/1"
+    );
+    assert_eq!(err(json!({ "int_array": [1, 1e10] })).pointer, "/1");
+    assert_eq!(err(json!({ "blob": 1 })).message, "unknown type 'blob'");
+}
@@ -0,0 +1,99 @@
+use mc_rust_protocol::{
+    Angle, LenPrefixedBytes, Position, PrefixedArray, UUID, VarInt,
+    nbt::{List, Tag},
+    packet::{
+        GameProfile, Intent, Packet,
+        c2s::{handshake::Handshake, login::CookieResponseLogin},
+        s2c::{configuration::FeatureFlags, login::LoginSuccess},
+    },
+    pretty::{PrettyOptions, pretty},
+};
+
+fn login_success() -> LoginSuccess {
+    LoginSuccess {
+        profile: GameProfile {
+            uuid: UUID::offline_player("Notch"),
+            username: "Notch".to_owned(),
+            properties: PrefixedArray::new(vec![]),
+        },
+    }
+}
+
+#[test]
+fn packet_display() {
+    let handshake = Packet::Handshake(Handshake {
+        protocol_version: VarInt(773),
+        server_adress: "localhost".to_owned(),
+        server_port: 25565,
+        intent: Intent::Login,
+    });
+    assert_eq!(
+        handshake.to_string(),
+        "Handshake { protocol_version: 773, server_adress: \"localhost\", server_port: 25565, intent: Login }"
+    );
+
+    let uuid = UUID::offline_player("Notch");
+    assert_eq!(
+        format!("{:#}", pretty(&login_success())),
+        format!(
+            "LoginSuccess {{\n    profile: {{\n        uuid: {},\n        username: \"Notch\",\n        properties: [],\n    }},\n}}",
+            uuid
+        )
+    );
+}
+
+#[test]
+fn limits() {
+    let flags = FeatureFlags {
+        feature_flags: PrefixedArray::new((0..20).map(|i| format!("flag:{}", i)).collect()),
+    };
+    let options = PrettyOptions::new().with_max_items(2);
+    assert_eq!(
+        pretty(&flags).with_options(options).to_string(),
+        "FeatureFlags { feature_flags: [\"flag:0\", \"flag:1\", … 18 more] }"
+    );
+
+    let options = PrettyOptions::new().with_max_depth(1);
+    assert_eq!(
+        pretty(&login_success()).with_options(options).to_string(),
+        "LoginSuccess { profile: {…} }"
+    );
+
+    let cookie = CookieResponseLogin {
+        key: "a".repeat(10),
+        payload: Some(LenPrefixedBytes::new((0..100).collect())),
+    };
+    let options = PrettyOptions::new().with_max_bytes(4).with_max_len(5);
+    assert_eq!(
+        pretty(&cookie).with_options(options).to_string(),
+        "CookieResponseLogin { key: \"aaaa…, payload: <100 bytes: 00 01 02 03 …> }"
+    );
+}
+
+#[test]
+fn compact_values() {
+    let position = Position::new(-12, -64, 30_000_000);
+    assert_eq!(pretty(&position).to_string(), "-12,-64,30000000");
+
+    assert_eq!(pretty(&Angle::from_degrees(90.)).to_string(), "90°");
+    assert_eq!(pretty(&Angle::from_degrees(270.)).to_string(), "-90°");
+
+    let mut item = Tag::new_compound();
+    item.put("id", Tag::String("minecraft:stone".to_owned()));
+    item.put("count", Tag::Byte(1));
+    item.put("my key", Tag::IntArray(vec![1, 2]));
+    assert_eq!(
+        pretty(&item).to_string(),
+        "{id:\"minecraft:stone\",count:1b,\"my key\":[I;1,2]}"
+    );
+
+    // text components show their text
+    let mut text = Tag::new_compound();
+    text.put("text", Tag::String("Hello ".to_owned()));
+    let mut world = Tag::new_compound();
+    world.put("text", Tag::String("world".to_owned()));
+    let mut bang = Tag::new_compound();
+    bang.put("text", Tag::String("!".to_owned()));
+    text.put("extra", List::try_from(vec![world, bang]).unwrap().into());
+    assert_eq!(pretty(&text).to_string(), "\"Hello world!\"");
+}
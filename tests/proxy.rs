@@ -0,0 +1,268 @@
+use std::{
+    net::{TcpListener, TcpStream},
+    sync::{Arc, mpsc},
+    thread,
+};
+
+use mc_rust_protocol::{
+    LenPrefixedBytes, PrefixedArray, UUID, VarInt,
+    packet::{
+        self, Direction, GameProfile, Packet, PacketType, State,
+        c2s::{
+            configuration::AcknowledgeFinishConfiguration,
+            handshake::Handshake,
+            login::{EncryptionResponse, LoginAcknowledged, LoginStart},
+            play::ServerboundKeepAlivePlay,
+        },
+        s2c::{
+            configuration::FinishConfiguration,
+            login::{EncryptionRequest, LoginSuccess, SetCompression},
+            play::{ClientboundKeepAlivePlay, ClientboundPluginMessagePlay},
+        },
+    },
+    packet_decoder::NetworkDecoder,
+    packet_encoder::NetworkEncoder,
+    proxy::{InterceptContext, Proxy, Verdict, generate_key},
+};
+use rsa::{
+    Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey,
+    pkcs8::{DecodePublicKey, EncodePublicKey},
+    rand_core::OsRng,
+};
+
+struct Conn {
+    decoder: NetworkDecoder<TcpStream>,
+    encoder: NetworkEncoder<TcpStream>,
+    dir: Direction,
+}
+
+impl Conn {
+    /// `dir` is the direction of the packets this side receives
+    fn new(stream: TcpStream, dir: Direction) -> Self {
+        Conn {
+            decoder: NetworkDecoder::new(stream.try_clone().unwrap()),
+            encoder: NetworkEncoder::new(stream),
+            dir,
+        }
+    }
+
+    fn recv(&mut self, state: State) -> Packet {
+        let raw = self.decoder.get_raw_packet().unwrap();
+        packet::packet_by_id(state, self.dir, raw.id, &mut &raw.payload[..]).unwrap()
+    }
+
+    fn send(&mut self, packet: &impl PacketType) {
+        let mut buf = Vec::new();
+        packet.write(&mut buf).unwrap();
+        self.encoder.write_packet(&buf).unwrap();
+    }
+
+    fn set_encryption(&mut self, key: &[u8; 16]) {
+        self.decoder.set_encryption(key);
+        self.encoder.set_encryption(key);
+    }
+
+    fn set_compression(&mut self, threshold: usize) {
+        self.decoder.set_compression(threshold);
+        self.encoder.set_compression((threshold, 6));
+    }
+}
+
+/// Offline mode server that still encrypts and compresses, like a vanilla server with
+/// `online-mode=false` behind `network-compression-threshold`
+fn spawn_server(listener: TcpListener, key: Arc<RsaPrivateKey>) -> thread::JoinHandle<Vec<Packet>> {
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut conn = Conn::new(stream, Direction::Serverbound);
+
+        assert!(matches!(conn.recv(State::Handshake), Packet::Handshake(_)));
+        let Packet::LoginStart(login_start) = conn.recv(State::Login) else {
+            panic!("expected login start");
+        };
+
+        conn.send(&EncryptionRequest {
+            server_id: String::new(),
+            public_key: LenPrefixedBytes::new(
+                key.to_public_key().to_public_key_der().unwrap().into_vec(),
+            ),
+            verify_token: LenPrefixedBytes::new(vec![1, 2, 3, 4]),
+            should_authenticate: false,
+        });
+        let Packet::EncryptionResponse(response) = conn.recv(State::Login) else {
+            panic!("expected encryption response");
+        };
+        assert_eq!(
+            key.decrypt(Pkcs1v15Encrypt, &response.verify_token.data)
+                .unwrap(),
+            [1, 2, 3, 4]
+        );
+        let secret: [u8; 16] = key
+            .decrypt(Pkcs1v15Encrypt, &response.shared_secret.data)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        conn.set_encryption(&secret);
+        conn.send(&SetCompression {
+            theshold: VarInt(64),
+        });
+        conn.set_compression(64);
+
+        conn.send(&LoginSuccess {
+            profile: GameProfile {
+                uuid: login_start.player_uuid,
+                username: login_start.name,
+                properties: PrefixedArray::new(vec![]),
+            },
+        });
+        assert!(matches!(
+            conn.recv(State::Login),
+            Packet::LoginAcknowledged(_)
+        ));
+        conn.send(&FinishConfiguration {});
+        assert!(matches!(
+            conn.recv(State::Configuration),
+            Packet::AcknowledgeFinishConfiguration(_)
+        ));
+
+        conn.send(&ClientboundPluginMessagePlay {
+            channel: "test:big".to_owned(),
+            data: vec![7; 1000],
+        });
+        conn.send(&ClientboundKeepAlivePlay { keep_alive_id: 1 });
+
+        vec![conn.recv(State::Play), conn.recv(State::Play)]
+    })
+}
+
+#[test]
+fn relay_through_proxy() {
+    let key = Arc::new(generate_key().unwrap());
+
+    let server_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let server_port = server_listener.local_addr().unwrap().port();
+    let server = spawn_server(server_listener, key.clone());
+
+    let proxy_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy_port = proxy_listener.local_addr().unwrap().port();
+    let (injector_tx, injector_rx) = mpsc::channel();
+    let proxy = {
+        let key = key.clone();
+        thread::spawn(move || {
+            let (client, _) = proxy_listener.accept().unwrap();
+            let session = Proxy::new("127.0.0.1", server_port)
+                .encryption(key)
+                .compression_threshold(256)
+                .interceptor(|ctx: &mut InterceptContext, packet: &Packet| match packet {
+                    Packet::ClientboundKeepAlivePlay(p) if p.keep_alive_id == 1 => {
+                        Verdict::Replace(Box::new(Packet::ClientboundKeepAlivePlay(
+                            ClientboundKeepAlivePlay { keep_alive_id: 2 },
+                        )))
+                    }
+                    Packet::ServerboundKeepAlivePlay(p) if p.keep_alive_id == 99 => {
+                        ctx.inject(
+                            Direction::Serverbound,
+                            Packet::ServerboundKeepAlivePlay(ServerboundKeepAlivePlay {
+                                keep_alive_id: 100,
+                            }),
+                        );
+                        Verdict::Drop
+                    }
+                    _ => Verdict::Pass,
+                })
+                .connect(client)
+                .unwrap();
+            injector_tx.send(session.injector()).unwrap();
+            session.run().unwrap();
+        })
+    };
+
+    let mut client = Conn::new(
+        TcpStream::connect(("127.0.0.1", proxy_port)).unwrap(),
+        Direction::Clientbound,
+    );
+    client.send(&Handshake {
+        protocol_version: VarInt(773),
+        server_adress: "127.0.0.1".to_owned(),
+        server_port: proxy_port,
+        intent: packet::Intent::Login,
+    });
+    client.send(&LoginStart {
+        name: "Notch".to_owned(),
+        player_uuid: UUID::offline_player("Notch"),
+    });
+
+    // the client leg has its own encryption and compression
+    let Packet::EncryptionRequest(request) = client.recv(State::Login) else {
+        panic!("expected encryption request");
+    };
+    assert!(!request.should_authenticate);
+    let proxy_key = RsaPublicKey::from_public_key_der(&request.public_key.data).unwrap();
+    let secret = [42u8; 16];
+    client.send(&EncryptionResponse {
+        shared_secret: LenPrefixedBytes::new(
+            proxy_key
+                .encrypt(&mut OsRng, Pkcs1v15Encrypt, &secret)
+                .unwrap(),
+        ),
+        verify_token: LenPrefixedBytes::new(
+            proxy_key
+                .encrypt(&mut OsRng, Pkcs1v15Encrypt, &request.verify_token.data)
+                .unwrap(),
+        ),
+    });
+    client.set_encryption(&secret);
+    let Packet::SetCompression(compression) = client.recv(State::Login) else {
+        panic!("expected set compression");
+    };
+    assert_eq!(compression.theshold.0, 256);
+    client.set_compression(256);
+
+    // the upstream's encryption and compression never reach the client
+    let Packet::LoginSuccess(success) = client.recv(State::Login) else {
+        panic!("expected login success");
+    };
+    assert_eq!(success.profile.username, "Notch");
+    client.send(&LoginAcknowledged {});
+    assert!(matches!(
+        client.recv(State::Configuration),
+        Packet::FinishConfiguration(_)
+    ));
+    client.send(&AcknowledgeFinishConfiguration {});
+
+    let Packet::ClientboundPluginMessagePlay(message) = client.recv(State::Play) else {
+        panic!("expected plugin message");
+    };
+    assert_eq!(message.data, vec![7; 1000]);
+    let Packet::ClientboundKeepAlivePlay(keep_alive) = client.recv(State::Play) else {
+        panic!("expected keep alive");
+    };
+    assert_eq!(keep_alive.keep_alive_id, 2);
+
+    let injector = injector_rx.recv().unwrap();
+    injector
+        .send(
+            Direction::Clientbound,
+            &Packet::ClientboundKeepAlivePlay(ClientboundKeepAlivePlay { keep_alive_id: 7 }),
+        )
+        .unwrap();
+    let Packet::ClientboundKeepAlivePlay(keep_alive) = client.recv(State::Play) else {
+        panic!("expected keep alive");
+    };
+    assert_eq!(keep_alive.keep_alive_id, 7);
+
+    client.send(&ServerboundKeepAlivePlay { keep_alive_id: 99 });
+    client.send(&ServerboundKeepAlivePlay { keep_alive_id: 2 });
+    let received = server.join().unwrap();
+    let ids: Vec<_> = received
+        .iter()
+        .map(|p| match p {
+            Packet::ServerboundKeepAlivePlay(p) => p.keep_alive_id,
+            other => panic!("unexpected {:?}", other),
+        })
+        .collect();
+    assert_eq!(ids, [100, 2]);
+
+    // the server hung up, the proxy closes the client leg
+    assert!(client.decoder.get_raw_packet().is_err());
+    proxy.join().unwrap();
+}
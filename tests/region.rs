@@ -0,0 +1,178 @@
+use std::{fs, path::PathBuf};
+
+use mc_rust_protocol::{
+    nbt,
+    nbt::Tag,
+    region::{ChunkCompression, Region, RegionError, SECTOR_SIZE},
+};
+
+fn chunk(x: i32, z: i32) -> Tag {
+    nbt!({
+        "DataVersion": 4440,
+        "xPos": x,
+        "zPos": z,
+        "Status": "minecraft:full",
+        "sections": [{ "Y": 0i8, "block_states": { "palette": [{ "Name": "minecraft:stone" }] } }],
+    })
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("mc-rust-protocol-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn chunks_roundtrip() {
+    let mut region = Region::new(-1, 2);
+    let compressions = [
+        ChunkCompression::Gzip,
+        ChunkCompression::Zlib,
+        ChunkCompression::None,
+        ChunkCompression::Lz4,
+    ];
+    for (i, compression) in compressions.into_iter().enumerate() {
+        region.compression = compression;
+        let (x, z) = (-32 + i as i32, 64 + 31);
+        region.set_chunk(x, z, &chunk(x, z)).unwrap();
+    }
+    assert_eq!(region.chunk(0, 0).unwrap(), None);
+
+    let mut bytes = Vec::new();
+    region.write(&mut bytes).unwrap();
+    assert_eq!(bytes.len() % SECTOR_SIZE, 0);
+    let read = Region::read(&bytes[..], -1, 2).unwrap();
+    assert_eq!(
+        read.positions().collect::<Vec<_>>(),
+        [(-32, 95), (-31, 95), (-30, 95), (-29, 95)]
+    );
+    for (x, z, tag) in read.chunks() {
+        assert_eq!(tag.unwrap(), chunk(x, z));
+    }
+    // local coordinates work too
+    assert_eq!(read.chunk(3, 31).unwrap(), Some(chunk(-29, 95)));
+    assert_eq!(read.chunk_compression(3, 31), Some(ChunkCompression::Lz4));
+    assert_eq!(read.timestamp(0, 31), region.timestamp(0, 31));
+
+    let mut again = Vec::new();
+    read.write(&mut again).unwrap();
+    assert_eq!(again, bytes);
+
+    let mut read = read;
+    assert!(read.remove_chunk(0, 31));
+    assert!(!read.remove_chunk(0, 31));
+    assert_eq!(read.positions().count(), 3);
+}
+
+#[test]
+fn lz4_blocks() {
+    let mut region = Region::new(0, 0).with_compression(ChunkCompression::Lz4);
+    // several 64 KiB blocks
+    let big = nbt!({ "data": vec![7i64; 40_000] });
+    region.set_chunk(0, 0, &big).unwrap();
+    let mut bytes = Vec::new();
+    region.write(&mut bytes).unwrap();
+
+    let offset = 2 * SECTOR_SIZE;
+    let length = u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+    let data = &bytes[offset + 5..offset + 4 + length];
+    assert!(data.starts_with(b"LZ4Block"));
+    // the end mark: an empty raw block with the 64 KiB level
+    assert_eq!(
+        data[data.len() - 21..],
+        *b"LZ4Block\x16\0\0\0\0\0\0\0\0\0\0\0\0"
+    );
+    assert_eq!(region.chunk(0, 0).unwrap(), Some(big));
+
+    // a flipped bit fails the checksum
+    let mut corrupt = bytes.clone();
+    corrupt[offset + 5 + 30] ^= 1;
+    assert!(matches!(
+        Region::read(&corrupt[..], 0, 0).unwrap().chunk(0, 0),
+        Err(RegionError::InvalidLz4(0, 0, _))
+    ));
+
+    // the first block header: method at 8, lengths at 9 and 13
+    let header = offset + 5;
+    let lz4_error = |bytes: &[u8]| match Region::read(bytes, 0, 0).unwrap().chunk(0, 0) {
+        Err(RegionError::InvalidLz4(0, 0, message)) => message,
+        other => panic!("expected an LZ4 error, got {:?}", other),
+    };
+    // rejected before allocating 4 GiB
+    let mut huge = bytes.clone();
+    huge[header + 13..header + 17].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(lz4_error(&huge).contains("larger than 65536 bytes"));
+    // compressed, so the lengths differ
+    let mut raw = bytes.clone();
+    raw[header + 8] = 0x16;
+    assert!(lz4_error(&raw).starts_with("raw block of"));
+}
+
+#[test]
+fn external_chunks() {
+    let dir = temp_dir("external");
+    let path = dir.join("r.1.-1.mca");
+    let mut region = Region::new(1, -1).with_compression(ChunkCompression::None);
+    // more than 255 sectors uncompressed
+    let huge = nbt!({ "data": vec![1i64; 140_000] });
+    region.set_chunk(33, -2, &huge).unwrap();
+    region.set_chunk(32, -32, &chunk(32, -32)).unwrap();
+
+    assert!(matches!(
+        region.write(&mut Vec::new()),
+        Err(RegionError::ExternalChunk(33, -2))
+    ));
+    region.save(&path).unwrap();
+    let external = dir.join("c.33.-2.mcc");
+    assert!(external.exists());
+    // header, the small chunk and a sector holding the type
+    assert_eq!(fs::metadata(&path).unwrap().len(), 4 * SECTOR_SIZE as u64);
+
+    let mut read = Region::open(&path).unwrap();
+    assert_eq!((read.x, read.z), (1, -1));
+    assert_eq!(read.chunk(33, -2).unwrap(), Some(huge));
+    assert_eq!(read.chunk(32, -32).unwrap(), Some(chunk(32, -32)));
+    let bytes = fs::read(&path).unwrap();
+    assert!(matches!(
+        Region::read(&bytes[..], 1, -1).unwrap().chunk(33, -2),
+        Err(RegionError::ExternalChunk(33, -2))
+    ));
+
+    // fitting again removes the .mcc file
+    read.set_chunk(33, -2, &chunk(33, -2)).unwrap();
+    read.save(&path).unwrap();
+    assert!(!external.exists());
+    assert_eq!(
+        Region::open(&path).unwrap().chunk(33, -2).unwrap(),
+        Some(chunk(33, -2))
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn invalid_regions() {
+    assert_eq!(Region::read(&[][..], 0, 0).unwrap(), Region::new(0, 0));
+    assert!(matches!(
+        Region::read(&[0; 100][..], 0, 0),
+        Err(RegionError::InvalidRegion(_))
+    ));
+
+    let mut bytes = vec![0; 3 * SECTOR_SIZE];
+    // chunk 1, 0 in sector 2, length 5, type 99
+    bytes[4..8].copy_from_slice(&0x0201u32.to_be_bytes());
+    bytes[2 * SECTOR_SIZE..2 * SECTOR_SIZE + 5].copy_from_slice(&[0, 0, 0, 5, 99]);
+    let region = Region::read(&bytes[..], 0, 0).unwrap();
+    assert_eq!(region.chunk_compression(1, 0), None);
+    assert!(matches!(
+        region.chunk(1, 0),
+        Err(RegionError::UnsupportedCompression(1, 0, 99))
+    ));
+
+    // pointing past the end of the file
+    bytes[4..8].copy_from_slice(&0x0901u32.to_be_bytes());
+    assert!(matches!(
+        Region::read(&bytes[..], 0, 0),
+        Err(RegionError::InvalidRegion(_))
+    ));
+}
@@ -0,0 +1,151 @@
+use std::{io::Cursor, time::Duration};
+
+use mc_rust_protocol::{
+    PrefixedArray, Serializable, UUID, VarInt,
+    capture::{Capture, CaptureHeader, CaptureWriter},
+    packet::{
+        GameProfile, Intent, Packet, PacketType, State,
+        c2s::{handshake::Handshake, login::LoginStart, play::ServerboundKeepAlivePlay},
+        s2c::{
+            configuration::{ClientboundKeepAliveConfiguration, FinishConfiguration},
+            login::{CookieRequestLogin, LoginSuccess},
+            play::{ClientboundKeepAlivePlay, StartConfiguration},
+        },
+    },
+    replay::{Replay, ReplayError, ReplayMetaData, ReplayWriter, export_capture},
+};
+
+fn profile() -> GameProfile {
+    GameProfile {
+        uuid: UUID::offline_player("Notch"),
+        username: "Notch".to_owned(),
+        properties: PrefixedArray::new(vec![]),
+    }
+}
+
+fn summary(replay: &Replay) -> Vec<(u64, State, i32)> {
+    replay
+        .packets()
+        .map(|p| {
+            let p = p.unwrap();
+            (
+                p.timestamp.as_millis() as u64,
+                p.state,
+                p.packet.unwrap().id(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn synthesize_login() {
+    let meta = ReplayMetaData::new().with_server_name("localhost");
+    let mut writer = ReplayWriter::new(Cursor::new(Vec::new()), meta)
+        .unwrap()
+        .with_profile(profile());
+    // the recording starts in the middle of play
+    writer
+        .record_at(
+            Duration::from_millis(10),
+            &Packet::ClientboundKeepAlivePlay(ClientboundKeepAlivePlay { keep_alive_id: 1 }),
+        )
+        .unwrap();
+    writer
+        .record_at(
+            Duration::from_millis(20),
+            &Packet::ClientboundKeepAliveConfiguration(ClientboundKeepAliveConfiguration {
+                keep_alive_id: 2,
+            }),
+        )
+        .unwrap();
+    assert!(matches!(
+        writer.record_at(
+            Duration::from_millis(30),
+            &Packet::ServerboundKeepAlivePlay(ServerboundKeepAlivePlay { keep_alive_id: 3 }),
+        ),
+        Err(ReplayError::ServerboundPacket("ServerboundKeepAlivePlay"))
+    ));
+    let bytes = writer.finish().unwrap().into_inner();
+
+    let replay = Replay::read(Cursor::new(bytes)).unwrap();
+    assert_eq!(replay.meta.server_name, "localhost");
+    assert_eq!(replay.meta.protocol, 773);
+    assert_eq!(replay.meta.duration, 20);
+    assert_eq!(replay.meta.players, [UUID::offline_player("Notch")]);
+    assert_eq!(
+        summary(&replay),
+        [
+            (10, State::Login, LoginSuccess::ID),
+            (10, State::Configuration, FinishConfiguration::ID),
+            (10, State::Play, ClientboundKeepAlivePlay::ID),
+            (20, State::Play, StartConfiguration::ID),
+            (
+                20,
+                State::Configuration,
+                ClientboundKeepAliveConfiguration::ID
+            ),
+        ]
+    );
+}
+
+#[test]
+fn missing_profile() {
+    let mut writer = ReplayWriter::new(Cursor::new(Vec::new()), ReplayMetaData::new()).unwrap();
+    assert!(matches!(
+        writer.record(&Packet::FinishConfiguration(FinishConfiguration {})),
+        Err(ReplayError::MissingProfile)
+    ));
+}
+
+#[test]
+fn export_from_capture() {
+    let capture = CaptureWriter::new(Vec::new(), &CaptureHeader::new()).unwrap();
+    let record = |packet: Packet| {
+        let mut data = Vec::new();
+        packet.write(&mut data).unwrap();
+        let mut framed = Vec::new();
+        VarInt(data.len() as i32).write_to(&mut framed).unwrap();
+        framed.extend(data);
+        capture.record(packet.direction(), &framed).unwrap();
+    };
+    record(Packet::Handshake(Handshake {
+        protocol_version: VarInt(773),
+        server_adress: "localhost".to_owned(),
+        server_port: 25565,
+        intent: Intent::Login,
+    }));
+    record(Packet::LoginStart(LoginStart {
+        name: "Notch".to_owned(),
+        player_uuid: UUID::offline_player("Notch"),
+    }));
+    record(Packet::CookieRequestLogin(CookieRequestLogin {
+        key: "test:cookie".to_owned(),
+    }));
+    record(Packet::LoginSuccess(LoginSuccess { profile: profile() }));
+    record(Packet::FinishConfiguration(FinishConfiguration {}));
+    record(Packet::ClientboundKeepAlivePlay(ClientboundKeepAlivePlay {
+        keep_alive_id: 1,
+    }));
+    let bytes = capture.into_inner().unwrap();
+    let capture = Capture::read(&bytes[..]).unwrap();
+
+    let bytes = export_capture(&capture, Cursor::new(Vec::new()), ReplayMetaData::new())
+        .unwrap()
+        .into_inner();
+    let replay = Replay::read(Cursor::new(bytes)).unwrap();
+    assert_eq!(replay.meta.date, capture.header.started_at);
+    assert_eq!(replay.meta.players, [UUID::offline_player("Notch")]);
+    // the cookie request and serverbound packets aren't part of a replay
+    let ids: Vec<_> = summary(&replay)
+        .into_iter()
+        .map(|(_, state, id)| (state, id))
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            (State::Login, LoginSuccess::ID),
+            (State::Configuration, FinishConfiguration::ID),
+            (State::Play, ClientboundKeepAlivePlay::ID),
+        ]
+    );
+}
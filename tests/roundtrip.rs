@@ -0,0 +1,106 @@
+use std::fs::File;
+
+use mc_rust_protocol::{
+    RawPacket, Serializable,
+    nbt::{List, Tag},
+    packet::{self, Direction, Packet, State},
+    packet_decoder::NetworkDecoder,
+    roundtrip::{RoundtripError, verify_roundtrip, verify_value_roundtrip},
+};
+
+const AES_KEY: &str = "7532710be168544415a69d2a122b4230";
+
+/// Raw packets of a sample stream with the state they were sent in
+fn sample_packets(direction: Direction) -> Vec<(State, RawPacket)> {
+    let (path, mut state) = match direction {
+        Direction::Serverbound => ("tests/sample_data/C2S.bin", State::Handshake),
+        Direction::Clientbound => ("tests/sample_data/S2C.bin", State::Login),
+    };
+    let mut decoder = NetworkDecoder::new(File::open(path).unwrap());
+    let aes_key: [u8; 16] = hex::decode(AES_KEY).unwrap().try_into().unwrap();
+
+    let mut packets = Vec::new();
+    while let Ok(raw) = decoder.get_raw_packet() {
+        let packet = packet::packet_by_id(state, direction, raw.id, &mut &raw.payload[..]);
+        packets.push((state, raw));
+        let Ok(packet) = packet else {
+            continue;
+        };
+        match &packet {
+            Packet::Handshake(p) => state = p.intent.into(),
+            Packet::EncryptionRequest(_) => decoder.set_encryption(&aes_key),
+            Packet::EncryptionResponse(_) => {
+                decoder.set_encryption(&aes_key);
+                // the serverbound stream can't see `SetCompression`
+                decoder.set_compression(256);
+            }
+            Packet::SetCompression(p) => decoder.set_compression(p.theshold.0 as usize),
+            _ => {}
+        }
+        if let Some(next) = packet.state_transition() {
+            state = next;
+        }
+    }
+    packets
+}
+
+#[test]
+fn sample_packets_roundtrip() {
+    let mut failures = Vec::new();
+    let mut checked = 0;
+    for direction in [Direction::Serverbound, Direction::Clientbound] {
+        for (state, raw) in sample_packets(direction) {
+            checked += 1;
+            if let Err(err) = verify_roundtrip(state, direction, raw.id, &raw.payload) {
+                failures.push(format!(
+                    "{:?} {:?} {:#04x}: {}",
+                    direction, state, raw.id, err
+                ));
+            }
+        }
+    }
+    assert!(checked > 100);
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn reports_field_context() {
+    // a String field whose length prefix claims more bytes than there are
+    let err = verify_roundtrip(State::Login, Direction::Serverbound, 0x00, &[5, b'a']);
+    let Err(RoundtripError::Decode { field, .. }) = err else {
+        panic!("expected a decode error, got {:?}", err);
+    };
+    assert_eq!(field.as_deref(), Some("name"));
+
+    // trailing bytes no field reads
+    let err = verify_roundtrip(
+        State::Play,
+        Direction::Clientbound,
+        0x2b,
+        &[0, 0, 0, 0, 0, 0, 0, 1, 0xff],
+    );
+    let Err(RoundtripError::Mismatch(mismatch)) = err else {
+        panic!("expected a mismatch, got {:?}", err);
+    };
+    assert_eq!(
+        (mismatch.offset, mismatch.read, mismatch.written),
+        (8, Some(0xff), None)
+    );
+    assert_eq!(mismatch.field, None);
+}
+
+#[test]
+fn nbt_roundtrip() {
+    let mut inner = Tag::new_compound();
+    inner.put("a", Tag::Byte(1));
+    let mut tag = Tag::new_compound();
+    tag.put(
+        "b",
+        List::try_from(vec![inner, Tag::new_compound()])
+            .unwrap()
+            .into(),
+    );
+    let mut bytes = Vec::new();
+    tag.write_to(&mut bytes).unwrap();
+    verify_value_roundtrip::<Tag>(&bytes).unwrap();
+}
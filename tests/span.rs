@@ -0,0 +1,126 @@
+use mc_rust_protocol::{
+    Serializable, VarInt,
+    nbt::Tag,
+    packet::{
+        Direction, PacketType, State,
+        s2c::{configuration::FeatureFlags, play::ClientboundKeepAlivePlay},
+    },
+    span::{annotate, annotate_packet},
+};
+
+#[test]
+fn packet_spans() {
+    let mut payload = Vec::new();
+    VarInt(2).write_to(&mut payload).unwrap();
+    "minecraft:vanilla"
+        .to_owned()
+        .write_to(&mut payload)
+        .unwrap();
+    "x:y".to_owned().write_to(&mut payload).unwrap();
+
+    let annotated = annotate_packet(
+        State::Configuration,
+        Direction::Clientbound,
+        FeatureFlags::ID,
+        &payload,
+    );
+    assert!(annotated.value.is_ok());
+    assert_eq!(annotated.root.name, "FeatureFlags");
+    assert_eq!(annotated.root.range, 0..payload.len());
+    assert!(annotated.remaining().is_empty());
+
+    let flags = &annotated.root.children[0];
+    assert_eq!(flags.name, "feature_flags");
+    assert_eq!(flags.ty, "PrefixedArray<String>");
+    let summary: Vec<_> = flags
+        .children
+        .iter()
+        .map(|span| {
+            (
+                span.name.as_ref(),
+                span.range.clone(),
+                span.value.as_deref().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("length", 0..1, "VarInt(2)"),
+            ("[0]", 1..19, "\"minecraft:vanilla\""),
+            ("[1]", 19..23, "\"x:y\""),
+        ]
+    );
+    // the text view has a line per span
+    assert_eq!(annotated.to_string().lines().count(), 5);
+}
+
+#[test]
+fn pinpoint_errors() {
+    // a keep alive with only 3 of its 8 bytes
+    let annotated = annotate_packet(
+        State::Play,
+        Direction::Clientbound,
+        ClientboundKeepAlivePlay::ID,
+        &[0, 0, 1],
+    );
+    assert!(annotated.value.is_err());
+    let error = annotated.root.error_span().unwrap();
+    assert_eq!(error.name, "keep_alive_id");
+    assert_eq!(error.ty, "i64");
+    assert!(annotated.to_string().contains("!!"));
+
+    // bytes the definition doesn't account for are left over
+    let annotated = annotate_packet(
+        State::Configuration,
+        Direction::Clientbound,
+        FeatureFlags::ID,
+        &[0, 0xff],
+    );
+    assert!(annotated.value.is_ok());
+    assert_eq!(annotated.remaining(), [0xff]);
+    assert!(annotated.to_string().ends_with("1 bytes left over: ff\n"));
+}
+
+#[test]
+fn nbt_spans() {
+    let mut tag = Tag::new_compound();
+    tag.put("a", Tag::Byte(1));
+    // network NBT: the root's type byte, then the unnamed compound
+    let mut bytes = Vec::new();
+    tag.write_to(&mut bytes).unwrap();
+
+    let annotated = annotate::<Tag>(&bytes);
+    assert!(annotated.value.is_ok());
+    let value = &annotated.root.children[1];
+    let names: Vec<_> = value
+        .children
+        .iter()
+        .map(|span| (span.name.as_ref(), span.range.clone()))
+        .collect();
+    // type byte, name, the entry itself and the end tag
+    assert_eq!(
+        names,
+        [("type", 1..2), ("name", 2..5), ("a", 5..6), ("type", 6..7)]
+    );
+    assert_eq!(value.children[2].value.as_deref(), Some("Byte(1)"));
+}
+
+/// Implemented outside the crate, without `Debug` or `Visit`
+struct Opaque(u8);
+
+impl Serializable for Opaque {
+    fn read_from<R: std::io::Read>(buf: &mut R) -> Result<Self, mc_rust_protocol::Error> {
+        Ok(Opaque(u8::read_from(buf)?))
+    }
+    fn write_to<W: std::io::Write>(&self, buf: &mut W) -> Result<(), mc_rust_protocol::Error> {
+        self.0.write_to(buf)
+    }
+}
+
+#[test]
+fn serializable_without_debug() {
+    let mut bytes = Vec::new();
+    Opaque(7).write_to(&mut bytes).unwrap();
+    assert_eq!(Opaque::read_from(&mut &bytes[..]).unwrap().0, 7);
+}
@@ -0,0 +1,196 @@
+use std::{
+    net::TcpListener,
+    thread,
+    time::{Duration, Instant},
+};
+
+use mc_rust_protocol::{
+    Serializable, UUID,
+    packet::{
+        self, Direction, Intent, Packet, State,
+        s2c::{login::LoginDisconnect, status::StatusResponse},
+    },
+    packet_decoder::NetworkDecoder,
+    packet_encoder::NetworkEncoder,
+    status::{self, ServerStatus, StatusError, decode_favicon, encode_favicon},
+    text::JsonTextComponent,
+};
+use serde_json::json;
+
+fn png_header(width: u32, height: u32) -> Vec<u8> {
+    let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    png.extend_from_slice(&13u32.to_be_bytes());
+    png.extend_from_slice(b"IHDR");
+    png.extend_from_slice(&width.to_be_bytes());
+    png.extend_from_slice(&height.to_be_bytes());
+    png.extend_from_slice(&[8, 6, 0, 0, 0]);
+    png
+}
+
+#[test]
+fn parse_vanilla_status() {
+    let json = r#"{
+        "version": {"name": "1.21.10", "protocol": 773},
+        "players": {
+            "max": 20,
+            "online": 1,
+            "sample": [{"name": "Notch", "id": "069a79f4-44e9-4726-a5be-fca90e38aaf5"}]
+        },
+        "description": {"text": "A Minecraft Server"},
+        "enforcesSecureChat": true,
+        "forgeData": {"fmlNetworkVersion": 3}
+    }"#;
+
+    let status = ServerStatus::from_json(json).unwrap();
+    assert_eq!(status.version.protocol, 773);
+    let players = status.players.as_ref().unwrap();
+    assert_eq!(players.sample[0].name, "Notch");
+    assert_eq!(
+        players.sample[0].id,
+        "069a79f4-44e9-4726-a5be-fca90e38aaf5".parse().unwrap()
+    );
+    assert_eq!(
+        status.description,
+        JsonTextComponent::text("A Minecraft Server")
+    );
+    assert!(status.enforces_secure_chat);
+    assert_eq!(status.extra["forgeData"], json!({"fmlNetworkVersion": 3}));
+
+    // unknown fields survive a round trip
+    let reparsed = ServerStatus::from_json(&status.to_json()).unwrap();
+    assert_eq!(reparsed, status);
+}
+
+#[test]
+fn build_status_response() {
+    let status = ServerStatus::new("1.21.10", 773)
+        .with_players(100, 1)
+        .with_sample("Notch", UUID::offline_player("Notch"))
+        .with_description("hello")
+        .with_favicon_png(&png_header(64, 64))
+        .unwrap();
+
+    let packet = StatusResponse::new(&status);
+    let value: serde_json::Value = serde_json::from_str(&packet.json_response).unwrap();
+    assert_eq!(value["players"]["online"], 1);
+    assert_eq!(value["description"], json!({"text": "hello"}));
+    assert!(value.get("forgeData").is_none());
+
+    let parsed = packet.status().unwrap();
+    assert_eq!(parsed, status);
+    assert_eq!(parsed.favicon_png().unwrap().unwrap(), png_header(64, 64));
+}
+
+#[test]
+fn sample_without_players() {
+    let status = ServerStatus::new("1.21.10", 773).with_sample("Notch", UUID::NIL);
+    let players = status.players.unwrap();
+    assert_eq!((players.max, players.online), (0, 0));
+    assert_eq!(players.sample[0].name, "Notch");
+}
+
+#[test]
+fn text_components() {
+    // the shorthand forms are read as components
+    let parse = |json: serde_json::Value| serde_json::from_value::<JsonTextComponent>(json);
+    assert_eq!(parse(json!("hi")).unwrap(), JsonTextComponent::text("hi"));
+    let list = parse(json!(["a", {"text": "b", "bold": true}])).unwrap();
+    assert_eq!(list.plain_text(), "ab");
+    assert_eq!(list.extra[0].bold, Some(true));
+    assert!(parse(json!({"text": "a", "bold": "yes"})).is_err());
+    assert!(parse(json!([])).is_err());
+
+    // unknown keys survive, and `text` is left out when another content type is used
+    let json = json!({"translate": "disconnect.timeout", "with": ["x"], "color": "red"});
+    let translated = parse(json.clone()).unwrap();
+    assert_eq!(translated.other["translate"], "disconnect.timeout");
+    assert_eq!(translated.to_json(), json);
+
+    let disconnect = LoginDisconnect {
+        reason: JsonTextComponent::text("bye").with_color("red"),
+    };
+    let mut bytes = Vec::new();
+    disconnect.write_to(&mut bytes).unwrap();
+    let read = LoginDisconnect::read_from(&mut &bytes[..]).unwrap();
+    assert_eq!(read.reason, disconnect.reason);
+    assert_eq!(
+        read.reason.to_json(),
+        json!({"text": "bye", "color": "red"})
+    );
+}
+
+#[test]
+fn favicon_validation() {
+    assert!(matches!(
+        encode_favicon(&png_header(128, 128)),
+        Err(StatusError::InvalidFavicon(_))
+    ));
+    assert!(matches!(
+        encode_favicon(b"GIF89a not a png at all"),
+        Err(StatusError::InvalidFavicon(_))
+    ));
+
+    let favicon = encode_favicon(&png_header(64, 64)).unwrap();
+    assert!(favicon.starts_with("data:image/png;base64,"));
+    assert_eq!(decode_favicon(&favicon).unwrap(), png_header(64, 64));
+    assert!(decode_favicon("iVBORw0KGgo=").is_err());
+}
+
+/// Accepts a single connection and answers it like a vanilla server would
+fn spawn_status_server(status: ServerStatus) -> (u16, thread::JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut decoder = NetworkDecoder::new(stream.try_clone().unwrap());
+        let mut encoder = NetworkEncoder::new(stream);
+
+        let raw = decoder.get_raw_packet().unwrap();
+        let handshake = packet::packet_by_id(
+            State::Handshake,
+            Direction::Serverbound,
+            raw.id,
+            &mut &raw.payload[..],
+        )
+        .unwrap();
+        let Packet::Handshake(handshake) = handshake else {
+            panic!("expected a handshake");
+        };
+        assert!(matches!(handshake.intent, Intent::Status));
+
+        status::serve_status(&mut decoder, &mut encoder, &status).unwrap();
+    });
+    (port, handle)
+}
+
+#[test]
+fn ping_in_process_server() {
+    let status = ServerStatus::new("1.21.10", 773)
+        .with_players(20, 3)
+        .with_description(JsonTextComponent::text("in process").with_color("gold"));
+    let (port, server) = spawn_status_server(status.clone());
+
+    let result = status::ping("127.0.0.1", port, Duration::from_secs(5)).unwrap();
+    server.join().unwrap();
+
+    assert_eq!(result.status, status);
+    assert!(result.latency < Duration::from_secs(5));
+}
+
+#[test]
+fn ping_times_out() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    // accept the connection but never answer
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        thread::sleep(Duration::from_secs(1));
+        drop(stream);
+    });
+
+    let start = Instant::now();
+    let result = status::ping("127.0.0.1", port, Duration::from_millis(200));
+    assert!(matches!(result, Err(StatusError::Timeout)), "{:?}", result);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    server.join().unwrap();
+}
@@ -1,20 +1,11 @@
-use std::{error::Error, fs::File, io};
+use std::{error::Error, fs::File};
 
 use mc_rust_protocol::{
-    RawPacket, UUID, VarInt,
-    packet::{
-        self, Direction, Intent, Packet, PacketType, State, c2s::handshake::Handshake,
-        s2c::play::SetPlayerInventorySlot,
-    },
+    RawPacket,
+    packet::{self, Direction, Packet, State},
     packet_decoder::NetworkDecoder,
-    slot::{Item, Slot},
 };
-use rsa::{
-    Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey,
-    pkcs8::{DecodePrivateKey, DecodePublicKey},
-};
-
-use mc_rust_protocol::Serializable;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, pkcs8::DecodePrivateKey};
 
 #[test]
 fn testing() {
@@ -47,7 +38,7 @@ fn sample_data(decrypt_dir: Direction) -> Result<(), Box<dyn Error>> {
 
         match packet {
             Packet::Handshake(p) => state = p.intent.into(),
-            Packet::EncryptionRequest(p) => {
+            Packet::EncryptionRequest(_) => {
                 // server_public_key =
                 //     Some(RsaPublicKey::from_public_key_der(&p.public_key.data)?);
                 let aes_key = hex::decode("7532710be168544415a69d2a122b4230")
@@ -71,19 +62,19 @@ fn sample_data(decrypt_dir: Direction) -> Result<(), Box<dyn Error>> {
                 println!("acquired compression value: {:?}", p.theshold.0);
                 decoder.set_compression(p.theshold.0.try_into().unwrap());
             }
-            Packet::LoginSuccess(p) => {
+            Packet::LoginSuccess(_) => {
                 state = State::Configuration;
                 println!("set state to config");
             }
-            Packet::LoginAcknowledged(p) => {
+            Packet::LoginAcknowledged(_) => {
                 state = State::Configuration;
                 println!("set state to config");
             }
-            Packet::FinishConfiguration(p) => {
+            Packet::FinishConfiguration(_) => {
                 state = State::Play;
                 println!("set state to play");
             }
-            Packet::AcknowledgeFinishConfiguration(p) => {
+            Packet::AcknowledgeFinishConfiguration(_) => {
                 state = State::Play;
                 println!("set state to play");
             }
@@ -93,3 +84,62 @@ fn sample_data(decrypt_dir: Direction) -> Result<(), Box<dyn Error>> {
 
     // Ok(())
 }
+
+#[test]
+fn varint_roundtrip() {
+    use mc_rust_protocol::{Serializable, VarInt};
+
+    for value in [
+        0,
+        1,
+        127,
+        128,
+        255,
+        256,
+        261,
+        25565,
+        2097151,
+        i32::MAX,
+        -1,
+        i32::MIN,
+    ] {
+        let mut buf = Vec::new();
+        VarInt(value).write_to(&mut buf).unwrap();
+        assert_eq!(buf.len(), VarInt(value).written_size());
+        assert_eq!(VarInt::read_from(&mut &buf[..]).unwrap().0, value);
+    }
+}
+
+#[test]
+fn angle_radians() {
+    use mc_rust_protocol::Angle;
+    use std::f32::consts::{PI, TAU};
+
+    let quarter = Angle::from_radians(PI / 2.);
+    assert!((quarter.to_radians() - PI / 2.).abs() < 1e-6);
+    // rounds to the nearest step instead of truncating
+    assert!((Angle::from_radians(TAU / 256. * 0.6).to_radians() - TAU / 256.).abs() < 1e-6);
+    // wraps around past half a turn
+    assert!((Angle::from_radians(3. * PI / 2.).to_radians() + PI / 2.).abs() < 1e-6);
+    assert!((Angle::from_radians(PI).to_radians() + PI).abs() < 1e-6);
+}
+
+#[test]
+fn position_negative_coordinates() {
+    use mc_rust_protocol::{Position, Serializable};
+
+    // x = -12, y = -64, z = -30000000
+    let packed: u64 = ((-12i64 as u64 & 0x3FFFFFF) << 38)
+        | ((-30_000_000i64 as u64 & 0x3FFFFFF) << 12)
+        | (-64i64 as u64 & 0xFFF);
+    let bytes = packed.to_be_bytes();
+    let position = Position::read_from(&mut &bytes[..]).unwrap();
+    assert_eq!(
+        format!("{:?}", position),
+        "Position { x: -12, y: -64, z: -30000000 }"
+    );
+
+    let mut written = Vec::new();
+    position.write_to(&mut written).unwrap();
+    assert_eq!(written, bytes);
+}
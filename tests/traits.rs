@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+
+use mc_rust_protocol::{
+    Position, PrefixedArray, Serializable, UUID, VarInt,
+    nbt::Tag,
+    packet::{
+        self, Direction, GameProfile, Packet, State,
+        s2c::{login::LoginSuccess, play::SetHealth},
+    },
+    slot::{Item, Slot},
+};
+
+fn login_success() -> Packet {
+    Packet::LoginSuccess(LoginSuccess {
+        profile: GameProfile {
+            uuid: UUID::offline_player("Notch"),
+            username: "Notch".to_owned(),
+            properties: PrefixedArray::new(vec![]),
+        },
+    })
+}
+
+#[test]
+fn packets_clone_and_compare() {
+    let packet = login_success();
+    assert_eq!(packet.clone(), packet);
+
+    let mut data = Vec::new();
+    packet.write(&mut data).unwrap();
+    let mut data = &data[..];
+    let id = VarInt::read_from(&mut data).unwrap().0;
+    let read = packet::packet_by_id(State::Login, Direction::Clientbound, id, &mut data).unwrap();
+    assert_eq!(read, packet);
+
+    let health = |health| {
+        Packet::SetHealth(SetHealth {
+            health,
+            food: VarInt(20),
+            food_saturation: 5.,
+        })
+    };
+    assert_ne!(health(20.), health(19.));
+}
+
+#[test]
+fn hashable_values() {
+    let positions: HashSet<Position> = [
+        Position::new(1, 2, 3),
+        Position::new(1, 2, 3),
+        Position::new(-1, 2, 3),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(positions.len(), 2);
+
+    let profiles: HashSet<GameProfile> = (0..3)
+        .map(|_| GameProfile {
+            uuid: UUID::offline_player("Notch"),
+            username: "Notch".to_owned(),
+            properties: PrefixedArray::new(vec![]),
+        })
+        .collect();
+    assert_eq!(profiles.len(), 1);
+}
+
+#[test]
+fn items_and_nbt() {
+    let stone = Slot {
+        item_count: VarInt(1),
+        item: Some(Item {
+            item_id: VarInt(1),
+            components_to_add: vec![],
+            components_to_remove: vec![],
+        }),
+    };
+    let empty = Slot {
+        item_count: VarInt(0),
+        item: None,
+    };
+    let mut slots = vec![stone.clone(), stone.clone(), empty.clone()];
+    slots.dedup();
+    assert_eq!(slots, [stone, empty]);
+
+    let mut tag = Tag::new_compound();
+    tag.put("a", vec![1.0f32].into());
+    let copy = tag.clone();
+    tag.put("b", Tag::Byte(1));
+    assert_ne!(tag, copy);
+}
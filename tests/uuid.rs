@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use mc_rust_protocol::{UUID, nbt::Tag};
+
+#[test]
+fn offline_player_uuid() {
+    let uuid = UUID::offline_player("Notch");
+    assert_eq!(uuid.to_string(), "b50ad385-829d-3141-a216-7e7d7539ba7f");
+    assert_eq!(uuid.version(), 3);
+    assert_eq!(uuid.variant(), 2);
+}
+
+#[test]
+fn uuid_string_forms() {
+    let dashed: UUID = "069a79f4-44e9-4726-a5be-fca90e38aaf5".parse().unwrap();
+    let simple: UUID = "069a79f444e94726a5befca90e38aaf5".parse().unwrap();
+    assert_eq!(dashed, simple);
+    assert_eq!(
+        dashed.to_simple_string(),
+        "069a79f444e94726a5befca90e38aaf5"
+    );
+    assert_eq!(dashed.version(), 4);
+
+    assert!(
+        "069a79f4-44e9-4726-a5be-fca90e38aaf"
+            .parse::<UUID>()
+            .is_err()
+    );
+    assert!(
+        "069a79f4+44e9-4726-a5be-fca90e38aaf5"
+            .parse::<UUID>()
+            .is_err()
+    );
+}
+
+#[test]
+fn uuid_nbt_int_array() {
+    let uuid = UUID::offline_player("Notch");
+    let ints = [-1257581691, -2103627455, -1575584131, 1966717567];
+    assert_eq!(uuid.to_int_array(), ints);
+    assert_eq!(UUID::from_int_array(ints), uuid);
+
+    let tag: Tag = uuid.into();
+    assert_eq!(UUID::from_nbt(&tag), Some(uuid));
+    assert_eq!(UUID::from_nbt(&Tag::IntArray(vec![1, 2, 3])), None);
+}
+
+#[test]
+fn uuid_as_map_key() {
+    let mut players = HashMap::new();
+    players.insert(UUID::offline_player("Notch"), "Notch");
+    players.insert(UUID::offline_player("jeb_"), "jeb_");
+    assert_eq!(players[&UUID::offline_player("jeb_")], "jeb_");
+    assert!(UUID::NIL < UUID::offline_player("Notch"));
+}
@@ -0,0 +1,118 @@
+use mc_rust_protocol::{
+    LenPrefixedBytes, PrefixedArray, UUID, VarInt,
+    nbt::Tag,
+    packet::{
+        GameProfile, Intent, Packet,
+        c2s::{handshake::Handshake, login::CookieResponseLogin},
+        s2c::login::LoginSuccess,
+    },
+    visit::{Value, Visit, Visitor, to_json},
+};
+use serde_json::json;
+
+/// Collects the path of every leaf, the way a redaction tool would find fields
+#[derive(Default)]
+struct Paths {
+    path: Vec<String>,
+    leaves: Vec<String>,
+}
+
+impl Visitor for Paths {
+    fn value(&mut self, _value: Value<'_>) {
+        self.leaves.push(self.path.join("."));
+        self.path.pop();
+    }
+
+    fn begin_variant(&mut self, _name: &'static str, variant: &'static str) {
+        if let Some(field) = self.path.last_mut() {
+            field.push_str(&format!("({})", variant));
+        }
+    }
+
+    fn field(&mut self, name: &str) {
+        self.path.push(name.to_owned());
+    }
+
+    fn end(&mut self) {
+        if !self.path.is_empty() {
+            self.leaves.push(self.path.join("."));
+            self.path.pop();
+        }
+    }
+}
+
+fn handshake() -> Packet {
+    Packet::Handshake(Handshake {
+        protocol_version: VarInt(773),
+        server_adress: "localhost".to_owned(),
+        server_port: 25565,
+        intent: Intent::Login,
+    })
+}
+
+#[test]
+fn packet_to_json() {
+    assert_eq!(
+        to_json(&handshake()),
+        json!({
+            "protocol_version": 773,
+            "server_adress": "localhost",
+            "server_port": 25565,
+            "intent": "Login",
+        })
+    );
+
+    let login_success = LoginSuccess {
+        profile: GameProfile {
+            uuid: UUID::offline_player("Notch"),
+            username: "Notch".to_owned(),
+            properties: PrefixedArray::new(vec![]),
+        },
+    };
+    assert_eq!(
+        to_json(&login_success),
+        json!({
+            "profile": {
+                "uuid": UUID::offline_player("Notch").to_string(),
+                "username": "Notch",
+                "properties": [],
+            }
+        })
+    );
+
+    let cookie = CookieResponseLogin {
+        key: "test:cookie".to_owned(),
+        payload: Some(LenPrefixedBytes::new(vec![0xca, 0xfe])),
+    };
+    assert_eq!(
+        to_json(&cookie),
+        json!({ "key": "test:cookie", "payload": "cafe" })
+    );
+}
+
+#[test]
+fn field_paths() {
+    let mut paths = Paths::default();
+    handshake().visit(&mut paths);
+    assert_eq!(
+        paths.leaves,
+        [
+            "protocol_version",
+            "server_adress",
+            "server_port",
+            "intent(Login)"
+        ]
+    );
+}
+
+#[test]
+fn nbt_to_json() {
+    let mut tag = Tag::new_compound();
+    tag.put("name", Tag::String("stone".to_owned()));
+    tag.put("counts", vec![1i8, 2].into());
+    tag.put("data", Tag::ByteArray(vec![1, 2]));
+    assert_eq!(
+        to_json(&tag),
+        json!({ "name": "stone", "counts": [1, 2], "data": [1, 2] })
+    );
+}